@@ -0,0 +1,228 @@
+use crate::game::{hand_distribution, Action, GameConfig, GameState, DICE_FACES};
+use crate::strategy::{action_to_str, write_atomically, Policy};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, Write};
+
+/// One public state (an opening and the bids so far) with the strategy
+/// marginalized over the mover's hands.
+pub struct PublicState {
+    pub dice_p1: u8,
+    pub dice_p2: u8,
+    pub history: Vec<Action>,
+    /// Probability that play reaches this state, chance and opening included.
+    pub reach: f64,
+    /// Each legal action with its probability given the state is reached.
+    pub actions: Vec<(Action, f64)>,
+}
+
+impl PublicState {
+    fn probability(&self, wanted: &Action) -> f64 {
+        self.actions.iter().find(|(a, _)| a == wanted).map_or(0.0, |(_, p)| *p)
+    }
+}
+
+/// The calls made at one depth (number of bids so far), over every state at it.
+pub struct DepthSummary {
+    pub depth: usize,
+    pub reach: f64,
+    pub challenge: f64,
+    pub calza: f64,
+    /// Share of the bids made here on each face.
+    pub faces: [f64; DICE_FACES as usize],
+}
+
+/// How one bid fares: how often it is made, and what the reply is when it is.
+#[derive(Default)]
+pub struct BidSummary {
+    /// Probability a round sees this bid made.
+    pub made: f64,
+    /// Probability the reply to it is in the walk; below `made` when a depth limit cuts replies off.
+    pub faced: f64,
+    pub challenge: f64,
+    pub calza: f64,
+}
+
+/// The strategy's play by public state, for questions about the strategy as
+/// a whole rather than one hand at a time.
+pub struct Aggregates {
+    pub states: Vec<PublicState>,
+}
+
+impl Aggregates {
+    /// Walks the public bid tree of every opening, pruning states play never
+    /// reaches and, with `max_depth`, states after that many bids. Like the
+    /// best response this tracks reach for every hand, so it is only
+    /// practical for small dice counts.
+    pub fn collect<P: Policy>(policy: &P, config: &GameConfig, max_depth: Option<usize>) -> Self {
+        let mut states = Vec::new();
+        for (round, p) in config.openings() {
+            let hands = [hand_distribution(round.dice_p1, &round), hand_distribution(round.dice_p2, &round)];
+            let reach = [
+                hands[0].iter().map(|(_, q)| p * q).collect(),
+                hands[1].iter().map(|(_, q)| *q).collect(),
+            ];
+            visit(policy, &GameState::new(&round), &hands, &reach, max_depth, &mut states);
+        }
+        Aggregates { states }
+    }
+
+    pub fn by_depth(&self) -> Vec<DepthSummary> {
+        let mut depths: Vec<DepthSummary> = Vec::new();
+        let mut bids = Vec::new();
+        for state in &self.states {
+            let depth = state.history.len();
+            while depths.len() <= depth {
+                depths.push(DepthSummary { depth: depths.len(), reach: 0.0, challenge: 0.0, calza: 0.0, faces: [0.0; DICE_FACES as usize] });
+                bids.push(0.0);
+            }
+            let summary = &mut depths[depth];
+            summary.reach += state.reach;
+            summary.challenge += state.reach * state.probability(&Action::Challenge);
+            summary.calza += state.reach * state.probability(&Action::Calza);
+            for (action, p) in &state.actions {
+                if let Action::Bid(_, face) = action {
+                    summary.faces[*face as usize - 1] += state.reach * p;
+                    bids[depth] += state.reach * p;
+                }
+            }
+        }
+        for (summary, bid_total) in depths.iter_mut().zip(bids) {
+            summary.challenge /= summary.reach.max(f64::MIN_POSITIVE);
+            summary.calza /= summary.reach.max(f64::MIN_POSITIVE);
+            summary.faces.iter_mut().for_each(|f| *f /= bid_total.max(f64::MIN_POSITIVE));
+        }
+        depths
+    }
+
+    /// Every bid play makes, by (quantity, face).
+    pub fn by_bid(&self) -> BTreeMap<(u8, u8), BidSummary> {
+        let mut bids: BTreeMap<(u8, u8), BidSummary> = BTreeMap::new();
+        for state in &self.states {
+            for (action, p) in &state.actions {
+                if let Action::Bid(q, f) = action {
+                    bids.entry((*q, *f)).or_default().made += state.reach * p;
+                }
+            }
+            // The reply to the standing bid, weighted by how often it is faced here.
+            if let Some(Action::Bid(q, f)) = state.history.last() {
+                let summary = bids.entry((*q, *f)).or_default();
+                summary.faced += state.reach;
+                summary.challenge += state.reach * state.probability(&Action::Challenge);
+                summary.calza += state.reach * state.probability(&Action::Calza);
+            }
+        }
+        for summary in bids.values_mut() {
+            summary.challenge /= summary.faced.max(f64::MIN_POSITIVE);
+            summary.calza /= summary.faced.max(f64::MIN_POSITIVE);
+        }
+        bids
+    }
+
+    /// Writes `states.csv` (one row per public state and action), `depths.csv`
+    /// and `bids.csv` into `dir`.
+    pub fn write(&self, dir: &str) -> io::Result<()> {
+        std::fs::create_dir_all(dir)?;
+        write_atomically(&format!("{}/states.csv", dir), |file| {
+            writeln!(file, "Dice,History,Depth,Reach,Action,Probability")?;
+            for state in &self.states {
+                let history: Vec<String> = state.history.iter().map(action_to_str).collect();
+                for (action, p) in &state.actions {
+                    writeln!(
+                        file,
+                        "{}v{},{},{},{},{},{}",
+                        state.dice_p1, state.dice_p2, history.join("/"), state.history.len(), state.reach, action_to_str(action), p
+                    )?;
+                }
+            }
+            Ok(())
+        })?;
+        write_atomically(&format!("{}/depths.csv", dir), |file| {
+            writeln!(file, "Depth,Reach,Challenge,Calza,Face1,Face2,Face3,Face4,Face5,Face6")?;
+            for d in self.by_depth() {
+                let faces: Vec<String> = d.faces.iter().map(|f| f.to_string()).collect();
+                writeln!(file, "{},{},{},{},{}", d.depth, d.reach, d.challenge, d.calza, faces.join(","))?;
+            }
+            Ok(())
+        })?;
+        write_atomically(&format!("{}/bids.csv", dir), |file| {
+            writeln!(file, "Bid,Made,Challenged,Calza")?;
+            for ((q, f), b) in self.by_bid() {
+                writeln!(file, "{}-{},{},{},{}", q, f, b.made, b.challenge, b.calza)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// The per-depth table and the bids made at least `min_reach` of the time.
+    pub fn report(&self, min_reach: f64) -> Report<'_> {
+        Report { aggregates: self, min_reach }
+    }
+}
+
+pub struct Report<'a> {
+    aggregates: &'a Aggregates,
+    min_reach: f64,
+}
+
+impl fmt::Display for Report<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Depth   Reach  Challenge  Calza   Bid faces 1..6")?;
+        for d in self.aggregates.by_depth() {
+            let faces: Vec<String> = d.faces.iter().map(|p| format!("{:5.1}%", p * 100.0)).collect();
+            writeln!(f, "{:>5} {:7.4} {:9.1}% {:5.1}%  {}", d.depth, d.reach, d.challenge * 100.0, d.calza * 100.0, faces.join(" "))?;
+        }
+        writeln!(f)?;
+        writeln!(f, "Bid     Made  Challenged  Calza")?;
+        for ((q, face), b) in self.aggregates.by_bid().into_iter().filter(|(_, b)| b.made >= self.min_reach) {
+            writeln!(f, "{:>4} {:7.4} {:10.1}% {:5.1}%", format!("{}-{}", q, face), b.made, b.challenge * 100.0, b.calza * 100.0)?;
+        }
+        Ok(())
+    }
+}
+
+fn visit<P: Policy>(
+    policy: &P,
+    state: &GameState,
+    hands: &[Vec<(Vec<u8>, f64)>; 2],
+    reach: &[Vec<f64>; 2],
+    max_depth: Option<usize>,
+    out: &mut Vec<PublicState>,
+) {
+    let player = state.current_player as usize;
+    let own_reach: f64 = reach[player].iter().sum();
+    let opp_reach: f64 = reach[1 - player].iter().sum();
+    let actions = state.get_valid_actions();
+
+    let probs: Vec<Vec<f64>> = hands[player]
+        .iter()
+        .map(|(hand, _)| policy.action_probabilities_for(state.current_player, &state.information_set_for(hand), &actions))
+        .collect();
+    // The mover's hands weighted by how likely each is to be here.
+    let marginal: Vec<f64> = (0..actions.len())
+        .map(|i| reach[player].iter().zip(&probs).map(|(r, p)| r * p[i]).sum::<f64>() / own_reach)
+        .collect();
+    out.push(PublicState {
+        dice_p1: state.dice_p1,
+        dice_p2: state.dice_p2,
+        history: state.history.clone(),
+        reach: own_reach * opp_reach,
+        actions: actions.iter().cloned().zip(marginal).collect(),
+    });
+    if max_depth.is_some_and(|max| state.history.len() >= max) {
+        return;
+    }
+
+    for (i, action) in actions.iter().enumerate() {
+        let mut next = state.clone();
+        if next.apply_action(action.clone()) {
+            continue;
+        }
+        let mut child = reach.clone();
+        child[player] = reach[player].iter().zip(&probs).map(|(r, p)| r * p[i]).collect();
+        if child[player].iter().all(|&r| r == 0.0) {
+            continue;
+        }
+        visit(policy, &next, hands, &child, max_depth, out);
+    }
+}
@@ -0,0 +1,157 @@
+use crate::game::{hand_distribution, Action, GameConfig, GameState};
+use crate::record::GameRecord;
+use crate::strategy::Policy;
+use std::collections::BTreeMap;
+
+/// One decision from a record, scored against the solver.
+pub struct Decision {
+    pub record: usize,
+    pub player: String,
+    pub info_set: String,
+    pub chosen: Action,
+    /// Expected payoff, in dice, of playing the solver's mix here.
+    pub solver_ev: f64,
+    /// Expected payoff of the action actually taken, with the solver playing on afterwards.
+    pub chosen_ev: f64,
+}
+
+impl Decision {
+    pub fn loss(&self) -> f64 {
+        self.solver_ev - self.chosen_ev
+    }
+}
+
+/// Decisions and mistakes for one player across all records.
+#[derive(Default)]
+pub struct PlayerSummary {
+    pub decisions: usize,
+    pub mistakes: usize,
+    pub ev_lost: f64,
+}
+
+/// What each player can infer about the other's hand from the bids so far:
+/// the prior over hands, reweighted by how likely the policy would have made
+/// each observed action with that hand, as the mover's seat plays it.
+pub struct Beliefs {
+    hands: [Vec<(Vec<u8>, f64)>; 2],
+    weights: [Vec<f64>; 2],
+}
+
+impl Beliefs {
+    pub fn new(config: &GameConfig) -> Self {
+        let hands = [hand_distribution(config.dice_p1, config), hand_distribution(config.dice_p2, config)];
+        let weights = [
+            hands[0].iter().map(|(_, p)| *p).collect(),
+            hands[1].iter().map(|(_, p)| *p).collect(),
+        ];
+        Beliefs { hands, weights }
+    }
+
+    /// Updates the mover's hand weights for taking `actions[chosen]` at `state`.
+    pub fn observe<P: Policy>(&mut self, policy: &P, state: &GameState, actions: &[Action], chosen: usize) {
+        let player = state.current_player as usize;
+        let updated: Vec<f64> = self.hands[player]
+            .iter()
+            .zip(&self.weights[player])
+            .map(|((hand, _), w)| w * policy.action_probabilities_for(state.current_player, &state.information_set_for(hand), actions)[chosen])
+            .collect();
+        // An action the solver never takes with any hand says nothing usable.
+        if updated.iter().sum::<f64>() > 0.0 {
+            self.weights[player] = updated;
+        }
+    }
+
+    /// EV of each legal action for the player to move, averaged over the
+    /// opponent hands consistent with the bids so far, with the policy playing
+    /// on for both seats. Each EV walks the rest of the bid tree, so this is
+    /// only practical for small dice counts.
+    pub fn action_values<P: Policy>(&self, policy: &P, state: &GameState, actions: &[Action]) -> Vec<f64> {
+        let opponent = 1 - state.current_player as usize;
+        let opp_hands: Vec<(&Vec<u8>, f64)> = self.hands[opponent].iter().map(|(h, _)| h).zip(self.weights[opponent].iter().copied()).collect();
+        actions.iter().map(|a| action_value_against(policy, state, a, &opp_hands)).collect()
+    }
+}
+
+/// Scores every decision in `record` against `policy`.
+pub fn analyze_record<P: Policy>(policy: &P, config: &GameConfig, index: usize, record: &GameRecord) -> Vec<Decision> {
+    let mut state = GameState::from_hands(config, record.hands[0].clone(), record.hands[1].clone());
+    let mut beliefs = Beliefs::new(config);
+    let mut decisions = Vec::new();
+
+    for action in &record.actions {
+        let player = state.current_player as usize;
+        let Some(chosen) = state.action_index(action.clone()) else {
+            break;
+        };
+        let actions = state.get_valid_actions();
+
+        let values = beliefs.action_values(policy, &state, &actions);
+        let probs = policy.action_probabilities(&state.get_information_set(), &actions);
+        decisions.push(Decision {
+            record: index,
+            player: record.players[player].clone(),
+            info_set: state.get_information_set(),
+            chosen: action.clone(),
+            solver_ev: probs.iter().zip(&values).map(|(p, v)| p * v).sum(),
+            chosen_ev: values[chosen],
+        });
+
+        beliefs.observe(policy, &state, &actions, chosen);
+        if state.apply_action(action.clone()) {
+            break;
+        }
+    }
+    decisions
+}
+
+/// Adds `decisions` to per-player totals, counting losses above `threshold` as mistakes.
+pub fn summarize(decisions: &[Decision], threshold: f64, summaries: &mut BTreeMap<String, PlayerSummary>) {
+    for decision in decisions {
+        let summary = summaries.entry(decision.player.clone()).or_default();
+        summary.decisions += 1;
+        summary.ev_lost += decision.loss().max(0.0);
+        if decision.loss() > threshold {
+            summary.mistakes += 1;
+        }
+    }
+}
+
+/// EV of `action` for the player to move, averaged over weighted opponent hands.
+fn action_value_against<P: Policy>(policy: &P, state: &GameState, action: &Action, opp_hands: &[(&Vec<u8>, f64)]) -> f64 {
+    let total: f64 = opp_hands.iter().map(|(_, w)| w).sum();
+    let mut value = 0.0;
+    let mut dealt = state.clone();
+    for (hand, weight) in opp_hands {
+        if *weight == 0.0 {
+            continue;
+        }
+        if state.current_player == 0 {
+            dealt.hand_p2.clone_from(hand);
+        } else {
+            dealt.hand_p1.clone_from(hand);
+        }
+        value += weight * action_value(policy, &dealt, action);
+    }
+    value / total
+}
+
+/// EV of `action` for the player to move with both hands known, the solver playing on.
+fn action_value<P: Policy>(policy: &P, state: &GameState, action: &Action) -> f64 {
+    let mut next = state.clone();
+    if next.apply_action(action.clone()) {
+        next.payoff_for(state.current_player) as f64
+    } else {
+        -state_value(policy, &next)
+    }
+}
+
+fn state_value<P: Policy>(policy: &P, state: &GameState) -> f64 {
+    let actions = state.get_valid_actions();
+    let probs = policy.action_probabilities_for(state.current_player, &state.get_information_set(), &actions);
+    actions
+        .iter()
+        .zip(probs)
+        .filter(|(_, p)| *p > 0.0)
+        .map(|(a, p)| p * action_value(policy, state, a))
+        .sum()
+}
@@ -0,0 +1,128 @@
+use crate::game::Action;
+use crate::strategy::{Policy, StrategyTable};
+use rand::Rng;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+/// How a blend mixes its member strategies.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BlendMode {
+    /// Every decision averages the members' action probabilities by weight.
+    #[default]
+    PerDecision,
+    /// Each game plays one member throughout, drawn by weight at the deal.
+    PerGame,
+}
+
+impl fmt::Display for BlendMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BlendMode::PerDecision => write!(f, "decision"),
+            BlendMode::PerGame => write!(f, "game"),
+        }
+    }
+}
+
+impl FromStr for BlendMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "decision" => Ok(BlendMode::PerDecision),
+            "game" => Ok(BlendMode::PerGame),
+            _ => Err(format!("unknown blend mode '{}'", s)),
+        }
+    }
+}
+
+/// Several strategy files played as a weighted mixture, e.g. a blueprint
+/// with a little of an exploitative counter-strategy mixed in. A single
+/// file is a blend of one.
+pub struct StrategyBlend {
+    members: Vec<(String, StrategyTable, f64)>,
+    pub mode: BlendMode,
+}
+
+impl StrategyBlend {
+    /// Loads `path[:weight],path[:weight],...`; weights default to 1 and are
+    /// normalized.
+    pub fn load(spec: &str, mode: BlendMode) -> io::Result<Self> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+        let mut members = Vec::new();
+        for part in spec.split(',') {
+            let (path, weight) = match part.rsplit_once(':') {
+                Some((path, w)) => (path, w.parse::<f64>().ok().filter(|&w| w >= 0.0).ok_or_else(|| invalid(format!("invalid blend weight in '{}'", part)))?),
+                None => (part, 1.0),
+            };
+            let table = StrategyTable::load(path).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path, e)))?;
+            members.push((path.to_string(), table, weight));
+        }
+        let total: f64 = members.iter().map(|(_, _, w)| w).sum();
+        if total <= 0.0 {
+            return Err(invalid("blend weights are all zero".to_string()));
+        }
+        for member in &mut members {
+            member.2 /= total;
+        }
+        Ok(StrategyBlend { members, mode })
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.members.iter().map(|(path, _, _)| path.as_str())
+    }
+
+    /// Total info sets across the members.
+    pub fn info_sets(&self) -> usize {
+        self.members.iter().map(|(_, table, _)| table.entries.len()).sum()
+    }
+
+    /// Which member plays the next game: one drawn by weight per game, or
+    /// `None` for the per-decision mixture.
+    pub fn pick_member<R: Rng>(&self, rng: &mut R) -> Option<usize> {
+        if self.mode == BlendMode::PerDecision {
+            return None;
+        }
+        let mut x: f64 = rng.gen();
+        for (i, (_, _, weight)) in self.members.iter().enumerate() {
+            if x < *weight {
+                return Some(i);
+            }
+            x -= weight;
+        }
+        Some(self.members.len() - 1)
+    }
+
+    /// The policy for a game `pick_member` chose.
+    pub fn for_game(&self, member: Option<usize>) -> GamePolicy<'_> {
+        GamePolicy { blend: self, member }
+    }
+}
+
+/// The mixture over all members, whatever the mode.
+impl Policy for StrategyBlend {
+    fn action_probabilities(&self, info_set: &str, actions: &[Action]) -> Vec<f64> {
+        let mut probs = vec![0.0; actions.len()];
+        for (_, table, weight) in &self.members {
+            for (p, q) in probs.iter_mut().zip(table.action_probabilities(info_set, actions)) {
+                *p += weight * q;
+            }
+        }
+        probs
+    }
+}
+
+/// One game's strategy from a blend: a single member, or the mixture.
+pub struct GamePolicy<'a> {
+    blend: &'a StrategyBlend,
+    member: Option<usize>,
+}
+
+impl Policy for GamePolicy<'_> {
+    fn action_probabilities(&self, info_set: &str, actions: &[Action]) -> Vec<f64> {
+        match self.member {
+            Some(i) => self.blend.members[i].1.action_probabilities(info_set, actions),
+            None => self.blend.action_probabilities(info_set, actions),
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use crate::game::GameConfig;
+use crate::strategy::{format_metadata, parse_metadata, strategy_basename, write_atomically, StrategyTable};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+pub const BUNDLE_MAGIC: &str = "LIARS_DICE_BUNDLE 1";
+
+/// Strategies for every dice configuration a match can pass through, keyed
+/// by (opener's dice, other player's dice).
+///
+/// On disk a bundle is one file: a magic line, the shared rules as metadata
+/// lines, an `index` section of `NvM <offset> <length>` lines, then `data`
+/// followed by each configuration's strategy CSV at its byte offset.
+#[derive(Default)]
+pub struct StrategyBundle {
+    pub rules: Option<GameConfig>,
+    pub tables: HashMap<(u8, u8), StrategyTable>,
+}
+
+impl StrategyBundle {
+    /// Loads a bundle file, or a directory of `strategy_NvM.csv` files up to `max_dice`.
+    pub fn load<P: AsRef<Path>>(path: P, max_dice: u8) -> io::Result<Self> {
+        if path.as_ref().is_dir() {
+            Self::load_dir(path, max_dice)
+        } else {
+            Self::load_file(path)
+        }
+    }
+
+    /// Loads `strategy_NvM.csv` files from `dir` for every N, M up to `max_dice`.
+    pub fn load_dir<P: AsRef<Path>>(dir: P, max_dice: u8) -> io::Result<Self> {
+        let mut bundle = StrategyBundle::default();
+        for n in 1..=max_dice {
+            for m in 1..=max_dice {
+                let path = dir.as_ref().join(strategy_basename(n, m));
+                let table = StrategyTable::load(&path)
+                    .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+                bundle.tables.insert((n, m), table);
+            }
+        }
+        Ok(bundle)
+    }
+
+    pub fn load_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let bytes = fs::read(path)?;
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, format!("invalid bundle: {}", msg));
+
+        let mut pos = 0;
+        let mut next_line = || -> io::Result<&str> {
+            let end = bytes[pos..].iter().position(|&b| b == b'\n').ok_or_else(|| invalid("truncated header"))?;
+            let line = std::str::from_utf8(&bytes[pos..pos + end]).map_err(|_| invalid("header is not UTF-8"))?;
+            pos += end + 1;
+            Ok(line)
+        };
+
+        if next_line()? != BUNDLE_MAGIC {
+            return Err(invalid("missing magic line"));
+        }
+        let mut metadata = String::new();
+        loop {
+            match next_line()? {
+                "index" => break,
+                line => {
+                    metadata.push_str(line);
+                    metadata.push('\n');
+                }
+            }
+        }
+        let mut index = Vec::new();
+        loop {
+            let line = next_line()?;
+            if line == "data" {
+                break;
+            }
+            let parts: Vec<&str> = line.split(' ').collect();
+            let (n, m) = parts
+                .first()
+                .and_then(|c| c.split_once('v'))
+                .and_then(|(n, m)| Some((n.parse::<u8>().ok()?, m.parse::<u8>().ok()?)))
+                .ok_or_else(|| invalid("bad index entry"))?;
+            let offset: usize = parts.get(1).and_then(|o| o.parse().ok()).ok_or_else(|| invalid("bad offset"))?;
+            let length: usize = parts.get(2).and_then(|l| l.parse().ok()).ok_or_else(|| invalid("bad length"))?;
+            index.push(((n, m), offset, length));
+        }
+
+        let data = &bytes[pos..];
+        let mut bundle = StrategyBundle {
+            rules: Some(parse_metadata(&metadata)?),
+            tables: HashMap::new(),
+        };
+        for (dice, offset, length) in index {
+            let section = data.get(offset..offset + length).ok_or_else(|| invalid("section out of range"))?;
+            bundle.tables.insert(dice, StrategyTable::from_reader(section)?);
+        }
+        Ok(bundle)
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut keys: Vec<&(u8, u8)> = self.tables.keys().collect();
+        keys.sort();
+
+        let mut sections = Vec::with_capacity(keys.len());
+        for key in &keys {
+            let mut csv = Vec::new();
+            self.tables[key].write_csv(&mut csv)?;
+            sections.push(csv);
+        }
+
+        write_atomically(path, |file| {
+            writeln!(file, "{}", BUNDLE_MAGIC)?;
+            if let Some(rules) = &self.rules {
+                write!(file, "{}", format_metadata(rules))?;
+            }
+            writeln!(file, "index")?;
+            let mut offset = 0;
+            for ((n, m), section) in keys.iter().zip(&sections) {
+                writeln!(file, "{}v{} {} {}", n, m, offset, section.len())?;
+                offset += section.len();
+            }
+            writeln!(file, "data")?;
+            for section in &sections {
+                file.write_all(section)?;
+            }
+            Ok(())
+        })
+    }
+
+    pub fn get(&self, opener_dice: u8, other_dice: u8) -> Option<&StrategyTable> {
+        self.tables.get(&(opener_dice, other_dice))
+    }
+}
@@ -1,125 +1,549 @@
-use crate::game::GameState;
+use crate::deals::{ChanceStream, DealScript, DealTargets};
+use crate::exploitability;
+use crate::game::{Action, GameConfig, GameState};
+use crate::simd;
+use crate::strategy::{sample_index, Policy};
+use rand::Rng;
+use crate::symmetry;
 use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// The local learner each info set runs on its cumulative regrets.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum RegretMinimizer {
+    /// Regret matching with regrets floored at zero (CFR+).
+    #[default]
+    RegretMatchingPlus,
+    /// Hedge (multiplicative weights) on the unfloored regrets, with learning
+    /// rate `scale * sqrt(ln(actions) / visits)` so it anneals per node.
+    Hedge { scale: f32 },
+}
+
+impl fmt::Display for RegretMinimizer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RegretMinimizer::RegretMatchingPlus => write!(f, "rm+"),
+            RegretMinimizer::Hedge { scale } => write!(f, "hedge:{}", scale),
+        }
+    }
+}
+
+impl FromStr for RegretMinimizer {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rm+" => Ok(RegretMinimizer::RegretMatchingPlus),
+            "hedge" => Ok(RegretMinimizer::Hedge { scale: 1.0 }),
+            _ => s
+                .strip_prefix("hedge:")
+                .and_then(|k| k.parse().ok())
+                .filter(|&k: &f32| k > 0.0)
+                .map(|scale| RegretMinimizer::Hedge { scale })
+                .ok_or_else(|| format!("unknown regret minimizer '{}'", s)),
+        }
+    }
+}
+
+/// How much of the game tree each training iteration walks.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Sampling {
+    /// Sample the deal, then walk every action (chance-sampled vanilla CFR).
+    #[default]
+    Chance,
+    /// Outcome-sampling MCCFR: sample one path through the bids as well,
+    /// updating one player per iteration with importance-weighted regrets.
+    /// The updating player samples its own actions from its strategy mixed
+    /// with `exploration` uniform play, so rarely chosen lines still get visited.
+    Outcome { exploration: f32 },
+}
+
+impl fmt::Display for Sampling {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Sampling::Chance => write!(f, "chance sampling"),
+            Sampling::Outcome { exploration } => write!(f, "outcome sampling, exploration {}", exploration),
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct CFRNode {
     pub regret_sum: Vec<f32>,
     pub strategy_sum: Vec<f32>,
     pub num_actions: usize,
+    /// The action each slot refers to, so exports never have to rebuild the state.
+    pub actions: Vec<Action>,
+    /// Times training has passed through this info set.
+    pub visits: u64,
+    /// Average-only training: the node's average strategy in 65535ths, kept
+    /// after its regret and strategy sums are discarded. A frozen node plays
+    /// this strategy and learns no further.
+    pub frozen: Option<Box<[u16]>>,
 }
 
 impl CFRNode {
-    pub fn new(num_actions: usize) -> Self {
+    pub fn new(actions: Vec<Action>) -> Self {
+        let num_actions = actions.len();
         CFRNode {
             regret_sum: vec![0.0; num_actions],
             strategy_sum: vec![0.0; num_actions],
             num_actions,
+            actions,
+            visits: 0,
+            frozen: None,
         }
     }
 
-    pub fn get_strategy(&mut self, realization_weight: f32) -> Vec<f32> {
-        let mut strategy = vec![0.0; self.num_actions];
-        let mut normalizing_sum = 0.0;
+    pub fn is_frozen(&self) -> bool {
+        self.frozen.is_some()
+    }
 
-        for i in 0..self.num_actions {
-            strategy[i] = if self.regret_sum[i] > 0.0 {
-                self.regret_sum[i]
-            } else {
-                0.0
-            };
-            normalizing_sum += strategy[i];
+    /// Adds `other`'s sums and visits into this node, as when combining
+    /// training workers' copies of one info set.
+    pub fn merge(&mut self, other: &CFRNode) {
+        if self.is_frozen() || other.is_frozen() {
+            // Frozen nodes keep no sums, so combine the averages weighted by visits.
+            let (w1, w2) = (self.visits as f32, other.visits as f32);
+            let total = (w1 + w2).max(1.0);
+            let merged = self.get_average_strategy().iter().zip(other.get_average_strategy()).map(|(a, b)| (a * w1 + b * w2) / total).collect();
+            self.frozen = None;
+            self.strategy_sum = merged;
+            self.visits += other.visits;
+            self.freeze();
+            return;
         }
 
         for i in 0..self.num_actions {
-            if normalizing_sum > 0.0 {
-                strategy[i] /= normalizing_sum;
-            } else {
-                strategy[i] = 1.0 / self.num_actions as f32;
-            }
-            self.strategy_sum[i] += realization_weight * strategy[i];
+            self.regret_sum[i] += other.regret_sum[i];
+            self.strategy_sum[i] += other.strategy_sum[i];
+        }
+        self.visits += other.visits;
+    }
+
+    /// Replaces the regret and strategy sums by the quantized average strategy.
+    pub fn freeze(&mut self) {
+        let average = self.get_average_strategy();
+        self.frozen = Some(average.iter().map(|&p| (p * u16::MAX as f32).round() as u16).collect());
+        self.regret_sum = Vec::new();
+        self.strategy_sum = Vec::new();
+    }
+
+    fn frozen_strategy(frozen: &[u16]) -> Vec<f32> {
+        let total: f32 = frozen.iter().map(|&p| p as f32).sum();
+        frozen.iter().map(|&p| p as f32 / total).collect()
+    }
+
+    /// Whether the node has settled enough to freeze: visited at least
+    /// `min_visits` times with its current strategy within `tolerance` total
+    /// variation of its average.
+    pub fn has_converged(&self, min_visits: u64, tolerance: f32, minimizer: RegretMinimizer) -> bool {
+        if self.is_frozen() || self.visits < min_visits {
+            return false;
         }
+        let current = self.current_strategy(minimizer);
+        let distance: f32 = current.iter().zip(self.get_average_strategy()).map(|(c, a)| (c - a).abs()).sum();
+        distance / 2.0 <= tolerance
+    }
 
+    pub fn get_strategy(&mut self, realization_weight: f32, minimizer: RegretMinimizer) -> Vec<f32> {
+        let strategy = self.current_strategy(minimizer);
+        if self.is_frozen() {
+            return strategy;
+        }
+        simd::add_scaled(&mut self.strategy_sum, &strategy, realization_weight);
+        strategy
+    }
+
+    /// The strategy the regret minimizer plays next, without accumulating it.
+    pub fn current_strategy(&self, minimizer: RegretMinimizer) -> Vec<f32> {
+        if let Some(frozen) = &self.frozen {
+            return Self::frozen_strategy(frozen);
+        }
+        let mut strategy: Vec<f32> = match minimizer {
+            RegretMinimizer::RegretMatchingPlus => simd::positive_parts(&self.regret_sum),
+            RegretMinimizer::Hedge { scale } => {
+                let eta = scale * ((self.num_actions as f32).ln() / self.visits.max(1) as f32).sqrt();
+                // Shift by the largest regret so exp cannot overflow.
+                let max = self.regret_sum.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+                self.regret_sum.iter().map(|&r| (eta * (r - max)).exp()).collect()
+            }
+        };
+        simd::normalize(&mut strategy);
         strategy
     }
     
+    /// Forgets the average strategy so far and scales the cumulative regrets.
+    pub fn reset_average(&mut self, regret_scale: f32) {
+        self.strategy_sum.iter_mut().for_each(|s| *s = 0.0);
+        self.regret_sum.iter_mut().for_each(|r| *r *= regret_scale);
+    }
+
+    /// Scales the regret and strategy sums and the visits, as when a warm
+    /// start is shared between training workers. Frozen nodes keep their strategy.
+    pub fn scale(&mut self, factor: f32) {
+        self.regret_sum.iter_mut().for_each(|r| *r *= factor);
+        self.strategy_sum.iter_mut().for_each(|s| *s *= factor);
+        self.visits = (self.visits as f64 * factor as f64).round() as u64;
+    }
+
+    /// `strategy`, one of this node's strategies, laid out for `actions`
+    /// by matching actions rather than positions, in case the caller lists
+    /// them differently; actions the node lacks get zero.
+    pub fn probabilities_for(&self, strategy: &[f32], actions: &[Action]) -> Vec<f64> {
+        if self.actions == actions {
+            return strategy.iter().map(|&p| p as f64).collect();
+        }
+        actions
+            .iter()
+            .map(|a| self.actions.iter().position(|b| b == a).map_or(0.0, |i| strategy[i] as f64))
+            .collect()
+    }
+
+    /// As `get_average_strategy`, normalized in f64 for full-precision saves.
+    pub fn average_strategy_f64(&self) -> Vec<f64> {
+        if self.is_frozen() {
+            return self.get_average_strategy().iter().map(|&p| p as f64).collect();
+        }
+        let total: f64 = self.strategy_sum.iter().map(|&s| s as f64).sum();
+        self.strategy_sum
+            .iter()
+            .map(|&s| if total > 0.0 { s as f64 / total } else { 1.0 / self.num_actions as f64 })
+            .collect()
+    }
+
     pub fn get_average_strategy(&self) -> Vec<f32> {
-        let mut avg_strategy = vec![0.0; self.num_actions];
-        let normalizing_sum: f32 = self.strategy_sum.iter().sum();
-        
-        for i in 0..self.num_actions {
-            if normalizing_sum > 0.0 {
-                avg_strategy[i] = self.strategy_sum[i] / normalizing_sum;
-            } else {
-                avg_strategy[i] = 1.0 / self.num_actions as f32;
-            }
+        if let Some(frozen) = &self.frozen {
+            return Self::frozen_strategy(frozen);
         }
-        avg_strategy
+        let mut average = self.strategy_sum.clone();
+        simd::normalize(&mut average);
+        average
     }
 }
 
-pub struct CFRTrainer;
+/// Where training keeps its nodes. Lookups are short-lived, so a store may
+/// page nodes in and out between them.
+pub trait NodeStore {
+    /// The node for `key`, created with `actions` if it is new.
+    fn node(&mut self, key: &str, actions: &[Action]) -> &mut CFRNode;
+
+    /// The node for `key`, which must already exist.
+    fn existing(&mut self, key: &str) -> &mut CFRNode;
+
+    /// Every node at once, for traversals that need the whole map (CFR-BR's
+    /// best response); `None` when the store does not hold them in memory.
+    fn in_memory(&mut self) -> Option<&mut HashMap<String, CFRNode>>;
+}
+
+impl NodeStore for HashMap<String, CFRNode> {
+    fn node(&mut self, key: &str, actions: &[Action]) -> &mut CFRNode {
+        self.entry(key.to_string()).or_insert_with(|| CFRNode::new(actions.to_vec()))
+    }
+
+    fn existing(&mut self, key: &str) -> &mut CFRNode {
+        self.get_mut(key).expect("node was created earlier in the traversal")
+    }
+
+    fn in_memory(&mut self) -> Option<&mut HashMap<String, CFRNode>> {
+        Some(self)
+    }
+}
+
+/// The strategies the nodes would play on the next iteration, as a policy.
+pub struct CurrentStrategy<'a> {
+    pub nodes: &'a HashMap<String, CFRNode>,
+    pub minimizer: RegretMinimizer,
+}
+
+impl Policy for CurrentStrategy<'_> {
+    fn action_probabilities(&self, info_set: &str, actions: &[Action]) -> Vec<f64> {
+        match self.nodes.get(info_set) {
+            Some(node) => node.probabilities_for(&node.current_strategy(self.minimizer), actions),
+            None => vec![1.0 / actions.len() as f64; actions.len()],
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct CFRTrainer {
+    /// Key nodes by canonical info set under face renaming; only sound when
+    /// `symmetry::applies` holds, and the nodes then need `symmetry::expand`.
+    pub face_symmetry: bool,
+    pub minimizer: RegretMinimizer,
+    /// CFR-BR: each iteration one player runs CFR against an exact best
+    /// response to its current strategy, alternating between players.
+    pub best_response_opponent: bool,
+    /// Replay these deals instead of sampling chance.
+    pub deal_script: Option<Arc<DealScript>>,
+    /// Bias deals toward recorded games, weighting each deal so the solved
+    /// game is unchanged.
+    pub deal_targets: Option<Arc<DealTargets>>,
+    pub sampling: Sampling,
+}
 
 impl CFRTrainer {
-    pub fn train(n_dice_p1: u8, n_dice_p2: u8, iterations: usize) -> HashMap<String, CFRNode> {
-        let mut nodes = HashMap::new();
-        for _ in 0..iterations {
-            let game = GameState::new(n_dice_p1, n_dice_p2);
-            Self::cfr(game, 1.0, 1.0, &mut nodes);
+    /// Continues training on an existing node map, so a run can be split into chunks.
+    pub fn train_into<S: NodeStore>(&self, nodes: &mut S, chance: &mut ChanceStream, config: &GameConfig, iterations: usize) {
+        for i in 0..iterations {
+            let (mut game, weight) = chance.deal(config, self.deal_script.as_deref(), self.deal_targets.as_deref());
+            if self.best_response_opponent {
+                let cfr_player = (i % 2) as u8;
+                let nodes = nodes.in_memory().expect("CFR-BR needs the whole node map in memory");
+                let current = CurrentStrategy { nodes, minimizer: self.minimizer };
+                // The deal's dice counts say which seat opened; with equal dice either reading is the same game.
+                let round = config.opening(game.dice_p1 != config.dice_p1);
+                let responses = exploitability::best_response_choices(&current, &round, 1 - cfr_player);
+                self.cfr_br(&mut game, cfr_player, weight, weight, &responses, nodes);
+            } else if let Sampling::Outcome { exploration } = self.sampling {
+                let traverser = (i % 2) as u8;
+                let mut path = OutcomePath { traverser, exploration, rng: chance.rng() };
+                self.outcome_sample(&mut game, &mut path, 1.0, 1.0, 1.0 / weight, nodes);
+            } else {
+                self.cfr(&mut game, weight, weight, nodes);
+            }
         }
-        nodes
     }
 
-    fn cfr(game: GameState, p0_weight: f32, p1_weight: f32, nodes: &mut HashMap<String, CFRNode>) -> f32 {
+    /// One CFR-BR traversal: `cfr_player` explores every action and updates its
+    /// regrets, while the opponent plays its recorded best response. The best
+    /// response is pure, so the opponent's reach along the path is just the
+    /// deal's importance weight, `chance_weight`, which scales every regret.
+    /// Returns the CFR player's value.
+    fn cfr_br(
+        &self,
+        game: &mut GameState,
+        cfr_player: u8,
+        own_weight: f32,
+        chance_weight: f32,
+        responses: &HashMap<String, usize>,
+        nodes: &mut HashMap<String, CFRNode>,
+    ) -> f32 {
+        let valid_actions = game.get_valid_actions();
+
+        if game.current_player != cfr_player {
+            // Best-response choices are missing only where the CFR player never
+            // reaches; any action is a best response there, so take the first.
+            let key = exploitability::full_history_key(game, game.current_hand());
+            let best = responses.get(&key).copied().unwrap_or(0);
+            let mut value = 0.0;
+            for (i, action) in valid_actions.iter().enumerate() {
+                let is_terminal = game.apply_action(action.clone());
+                if i == best {
+                    value = if is_terminal {
+                        game.utility_for(cfr_player)
+                    } else {
+                        self.cfr_br(game, cfr_player, own_weight, chance_weight, responses, nodes)
+                    };
+                } else if !is_terminal {
+                    // The average strategy weighs every node by the CFR player's own
+                    // reach, including those behind actions the best response skips.
+                    self.accumulate_average(game, cfr_player, own_weight, nodes);
+                }
+                game.undo_action();
+            }
+            return value;
+        }
+
+        let info_set = game.get_information_set();
+        let node = nodes.entry(info_set.clone())
+            .or_insert_with(|| CFRNode::new(valid_actions.clone()));
+        node.visits += 1;
+        let strategy = node.get_strategy(own_weight, self.minimizer);
+
+        let mut util = vec![0.0; valid_actions.len()];
+        let mut node_util = 0.0;
+        for (i, action) in valid_actions.iter().enumerate() {
+            util[i] = if game.apply_action(action.clone()) {
+                game.utility_for(cfr_player)
+            } else {
+                self.cfr_br(game, cfr_player, own_weight * strategy[i], chance_weight, responses, nodes)
+            };
+            game.undo_action();
+            node_util += strategy[i] * util[i];
+        }
+
+        let node_ref = nodes.get_mut(&info_set).unwrap();
+        if node_ref.is_frozen() {
+            return node_util;
+        }
+        for (i, u) in util.iter().enumerate() {
+            let cumulative = node_ref.regret_sum[i] + chance_weight * (u - node_util);
+            node_ref.regret_sum[i] = match self.minimizer {
+                RegretMinimizer::RegretMatchingPlus => cumulative.max(0.0),
+                RegretMinimizer::Hedge { .. } => cumulative,
+            };
+        }
+
+        node_util
+    }
+
+    /// One vanilla CFR traversal; returns each player's value of `game`.
+    fn cfr<S: NodeStore>(&self, game: &mut GameState, p0_weight: f32, p1_weight: f32, nodes: &mut S) -> [f32; 2] {
+        // In team play this is the team to move; partners decide as one coalition.
         let player = game.current_player;
         let valid_actions = game.get_valid_actions();
         
         if valid_actions.is_empty() {
-            return 0.0;
+            return [0.0; 2];
         }
 
-        let info_set = game.get_information_set();
+        // Under face symmetry, slots[i] is the node slot holding valid_actions[i].
+        let (info_set, slots) = if self.face_symmetry {
+            let relabel = symmetry::canonical_relabel(
+                game.current_hand(),
+                game.remembered_bids().iter().filter_map(|a| match a {
+                    Action::Bid(_, f) => Some(*f),
+                    _ => None,
+                }),
+            );
+            let slots = symmetry::action_slots(&valid_actions, &relabel);
+            (game.information_set_relabelled(game.current_hand(), &relabel), Some(slots))
+        } else {
+            (game.get_information_set(), None)
+        };
+        let slot = |i: usize| slots.as_ref().map_or(i, |s| s[i]);
         
-        let node = nodes.entry(info_set.clone())
-            .or_insert_with(|| CFRNode::new(valid_actions.len()));
-            
-        let strategy = node.get_strategy(if player == 0 { p0_weight } else { p1_weight });
+        let node = nodes.node(&info_set, &valid_actions);
+        node.visits += 1;
+        let node_strategy = node.get_strategy(if player == 0 { p0_weight } else { p1_weight }, self.minimizer);
+        let strategy: Vec<f32> = (0..valid_actions.len()).map(|i| node_strategy[slot(i)]).collect();
         
         let num_actions = valid_actions.len();
-        let mut util = vec![0.0; num_actions];
-        let mut node_util = 0.0;
+        let mut util = vec![[0.0; 2]; num_actions];
+        let mut node_util = [0.0; 2];
 
-        // Vanilla CFR: Explore ALL actions
+        // Vanilla CFR: Explore ALL actions, on the one state, undoing each after its subtree
         for (i, action) in valid_actions.iter().enumerate() {
-            let mut next_game = game.clone();
-            let is_terminal = next_game.apply_action(action.clone());
+            let is_terminal = game.apply_action(action.clone());
 
             if is_terminal {
-                util[i] = next_game.get_payoff();
+                util[i] = [game.utility_for(0), game.utility_for(1)];
             } else {
                 if player == 0 {
-                    util[i] = -Self::cfr(next_game, p0_weight * strategy[i], p1_weight, nodes);
+                    util[i] = self.cfr(game, p0_weight * strategy[i], p1_weight, nodes);
                 } else {
-                    util[i] = -Self::cfr(next_game, p0_weight, p1_weight * strategy[i], nodes);
+                    util[i] = self.cfr(game, p0_weight, p1_weight * strategy[i], nodes);
                 }
             }
-            node_util += strategy[i] * util[i];
+            game.undo_action();
+            node_util[0] += strategy[i] * util[i][0];
+            node_util[1] += strategy[i] * util[i][1];
         }
 
         // Re-access node to update regrets (CFR+ with regret floor at 0)
-        let node_ref = nodes.get_mut(&info_set).unwrap();
-        
-        for i in 0..num_actions {
-            let regret = util[i] - node_util;
+        let node_ref = nodes.existing(&info_set);
+        if node_ref.is_frozen() {
+            return node_util;
+        }
+
+        for (i, u) in util.iter().enumerate() {
+            let regret = u[player as usize] - node_util[player as usize];
             let weighted_regret = if player == 0 {
                 p1_weight * regret
             } else {
                 p0_weight * regret
             };
             
-            // CFR+: Floor cumulative regret at 0 for faster convergence
-            node_ref.regret_sum[i] = (node_ref.regret_sum[i] + weighted_regret).max(0.0);
+            let cumulative = node_ref.regret_sum[slot(i)] + weighted_regret;
+            node_ref.regret_sum[slot(i)] = match self.minimizer {
+                // CFR+: Floor cumulative regret at 0 for faster convergence
+                RegretMinimizer::RegretMatchingPlus => cumulative.max(0.0),
+                RegretMinimizer::Hedge { .. } => cumulative,
+            };
         }
 
         node_util
     }
+
+    /// One outcome-sampling traversal (Lanctot et al. 2009) from `game`, where
+    /// `own_reach` and `opp_reach` are the traverser's and opponent's reach
+    /// under the unmixed strategies and `sample_prob` the chance of having
+    /// sampled this far. Returns the traverser's sampled utility divided by the
+    /// probability of sampling its terminal, and the probability of playing
+    /// from `game` to that terminal under the unmixed strategies.
+    ///
+    /// Only the unmixed strategy enters the average, weighted by own reach over
+    /// sampling probability, so exploration steers sampling without biasing it.
+    fn outcome_sample<R: Rng, S: NodeStore>(
+        &self,
+        game: &mut GameState,
+        path: &mut OutcomePath<R>,
+        own_reach: f32,
+        opp_reach: f32,
+        sample_prob: f32,
+        nodes: &mut S,
+    ) -> (f32, f32) {
+        let player = game.current_player;
+        let valid_actions = game.get_valid_actions();
+        let info_set = game.get_information_set();
+        let node = nodes.node(&info_set, &valid_actions);
+        node.visits += 1;
+        let strategy = node.current_strategy(self.minimizer);
+
+        let explore = if player == path.traverser { path.exploration } else { 0.0 };
+        let uniform = 1.0 / valid_actions.len() as f32;
+        let sampling: Vec<f64> = strategy.iter().map(|&s| (explore * uniform + (1.0 - explore) * s) as f64).collect();
+        let a = sample_index(&sampling, path.rng);
+        let next_sample_prob = sample_prob * sampling[a] as f32;
+
+        let (utility, tail) = if game.apply_action(valid_actions[a].clone()) {
+            (game.utility_for(path.traverser) / next_sample_prob, 1.0)
+        } else if player == path.traverser {
+            self.outcome_sample(game, path, own_reach * strategy[a], opp_reach, next_sample_prob, nodes)
+        } else {
+            self.outcome_sample(game, path, own_reach, opp_reach * strategy[a], next_sample_prob, nodes)
+        };
+        game.undo_action();
+
+        if player == path.traverser && !nodes.existing(&info_set).is_frozen() {
+            let node = nodes.existing(&info_set);
+            let weight = utility * opp_reach;
+            for (b, &s) in strategy.iter().enumerate() {
+                // Sampled counterfactual regret: W * (pi(z|h,b) - pi(z|h)), where only the sampled action reaches z.
+                let regret = if b == a { weight * tail * (1.0 - s) } else { -weight * tail * strategy[a] };
+                let cumulative = node.regret_sum[b] + regret;
+                node.regret_sum[b] = match self.minimizer {
+                    RegretMinimizer::RegretMatchingPlus => cumulative.max(0.0),
+                    RegretMinimizer::Hedge { .. } => cumulative,
+                };
+                node.strategy_sum[b] += own_reach / sample_prob * s;
+            }
+        }
+        (utility, tail * strategy[a])
+    }
+
+    /// Adds the CFR player's current strategy to its strategy sums below `game`
+    /// without touching regrets.
+    fn accumulate_average(&self, game: &mut GameState, cfr_player: u8, own_weight: f32, nodes: &mut HashMap<String, CFRNode>) {
+        if own_weight == 0.0 {
+            return;
+        }
+        let valid_actions = game.get_valid_actions();
+        let strategy = if game.current_player == cfr_player {
+            let node = nodes.entry(game.get_information_set())
+                .or_insert_with(|| CFRNode::new(valid_actions.clone()));
+            Some(node.get_strategy(own_weight, self.minimizer))
+        } else {
+            None
+        };
+
+        for (i, action) in valid_actions.iter().enumerate() {
+            if !game.apply_action(action.clone()) {
+                let weight = strategy.as_ref().map_or(own_weight, |s| own_weight * s[i]);
+                self.accumulate_average(game, cfr_player, weight, nodes);
+            }
+            game.undo_action();
+        }
+    }
+}
+
+/// What stays fixed along one outcome-sampling traversal.
+struct OutcomePath<'a, R: Rng> {
+    /// The player whose regrets this traversal updates.
+    traverser: u8,
+    exploration: f32,
+    rng: &'a mut R,
 }
@@ -0,0 +1,238 @@
+use crate::cfr::{CFRNode, CFRTrainer, Sampling};
+use crate::cli::Args;
+use crate::deals::ChanceStream;
+use crate::game::GameConfig;
+use crate::sharded::{self, ShardedNodes};
+use crate::symmetry;
+use crate::train;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::panic;
+use std::path::{Path, PathBuf};
+
+// Self-checks for the checkpoints out-of-core training keeps in shard files
+// (see sharded.rs). A node map goes through the shard format and back and
+// must come out bit for bit the same; then training continues from the
+// original in memory and from the reloaded shards, with the same chance
+// stream, and both must again agree bit for bit. The shard store evicts
+// through a small cache while it trains, so every eviction is another round
+// trip. Finally corrupted copies of the shard files are read, and any that
+// make the reader panic rather than fail are reported.
+
+/// What `checkpoint verify` checks, from the command line.
+pub struct VerifyOptions {
+    /// Iterations trained before the round trip, when not starting from `--dir`.
+    pub warmup: usize,
+    /// Iterations trained from both copies after it.
+    pub iterations: usize,
+    pub seed: u64,
+    /// Shards of the checkpoint in `--dir`, and of the copy the checks train.
+    pub shards: usize,
+    pub cache_shards: usize,
+    /// Corrupted shard files to read.
+    pub fuzz_cases: usize,
+}
+
+impl VerifyOptions {
+    pub fn from_args(args: &Args) -> Self {
+        VerifyOptions {
+            warmup: args.parse_value("warmup").unwrap_or(100),
+            iterations: args.parse_value("iterations").unwrap_or(100),
+            seed: args.parse_value("seed").unwrap_or(0),
+            shards: args.parse_value("shards").unwrap_or(if args.has("dir") { sharded::DEFAULT_SHARDS } else { 16 }),
+            cache_shards: args.parse_value("cache-shards").unwrap_or(2),
+            fuzz_cases: args.parse_value("fuzz").unwrap_or(200),
+        }
+    }
+}
+
+/// The first place `a` and `b` differ, comparing sums and strategies by
+/// their bits, or `None` when they are identical.
+pub fn first_difference(a: &HashMap<String, CFRNode>, b: &HashMap<String, CFRNode>) -> Option<String> {
+    let mut keys: Vec<&String> = a.keys().chain(b.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    let bits = |values: &[f32]| values.iter().map(|v| v.to_bits()).collect::<Vec<_>>();
+    keys.into_iter().find_map(|key| {
+        let (x, y) = match (a.get(key), b.get(key)) {
+            (Some(x), Some(y)) => (x, y),
+            (Some(_), None) => return Some(format!("{}: missing after the round trip", key)),
+            _ => return Some(format!("{}: appeared after the round trip", key)),
+        };
+        let field = if x.actions != y.actions {
+            "actions"
+        } else if x.visits != y.visits {
+            "visits"
+        } else if x.frozen != y.frozen {
+            "frozen strategy"
+        } else if bits(&x.regret_sum) != bits(&y.regret_sum) {
+            "regret sums"
+        } else if bits(&x.strategy_sum) != bits(&y.strategy_sum) {
+            "strategy sums"
+        } else {
+            return None;
+        };
+        Some(format!("{}: {} differ", key, field))
+    })
+}
+
+/// Every node in the store's shard files.
+fn load_all(store: &ShardedNodes) -> io::Result<HashMap<String, CFRNode>> {
+    let mut nodes = HashMap::new();
+    store.for_each_shard(|shard| {
+        nodes.extend(shard);
+        Ok(())
+    })?;
+    Ok(nodes)
+}
+
+/// The shard files in `dir`.
+fn shard_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<io::Result<_>>()?;
+    files.retain(|path| path.extension().is_some_and(|ext| ext == "bin"));
+    files.sort();
+    Ok(files)
+}
+
+/// `bytes` with one random byte changed, cut short, or with bytes inserted.
+pub fn corrupt<R: Rng>(mut bytes: Vec<u8>, rng: &mut R) -> Vec<u8> {
+    if bytes.is_empty() {
+        return vec![rng.gen()];
+    }
+    let at = rng.gen_range(0..bytes.len());
+    match rng.gen_range(0..3) {
+        0 => bytes[at] ^= 1 << rng.gen_range(0..8),
+        1 => bytes.truncate(at),
+        _ => {
+            let extra: Vec<u8> = (0..rng.gen_range(1..8)).map(|_| rng.gen()).collect();
+            bytes.splice(at..at, extra);
+        }
+    }
+    bytes
+}
+
+/// Reads `cases` corrupted copies of the shard files in `dir`. Returns how
+/// many the reader rejected, accepted and panicked on.
+fn fuzz_shards(dir: &Path, cases: usize, seed: u64) -> io::Result<(usize, usize, usize)> {
+    let files = shard_files(dir)?;
+    let target = dir.join("fuzz.bin");
+    let mut rng = StdRng::seed_from_u64(seed);
+    if files.is_empty() {
+        return Ok((0, 0, 0));
+    }
+
+    // The reader's panics are the finding; keep their messages out of the report.
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let counts = (|| {
+        let (mut rejected, mut accepted, mut panicked) = (0, 0, 0);
+        for _ in 0..cases {
+            let source = &files[rng.gen_range(0..files.len())];
+            fs::write(&target, corrupt(fs::read(source)?, &mut rng))?;
+            match panic::catch_unwind(|| sharded::read_shard(&target)) {
+                Ok(Err(_)) => rejected += 1,
+                Ok(Ok(_)) => accepted += 1,
+                Err(_) => panicked += 1,
+            }
+        }
+        Ok((rejected, accepted, panicked))
+    })();
+    // Restore the hook and clean up whether or not the loop finished.
+    panic::set_hook(hook);
+    match fs::remove_file(&target) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound && counts.is_ok() => Err(e),
+        _ => counts,
+    }
+}
+
+/// Runs the checks under `config`, starting from the checkpoint in `dir` or
+/// from `options.warmup` fresh iterations, and reports each. Returns whether
+/// all of them passed.
+pub fn verify(args: &Args, config: &GameConfig, dir: Option<&str>, options: &VerifyOptions) -> io::Result<bool> {
+    let sampling = train::sampling_from_args(args);
+    let trainer = CFRTrainer {
+        face_symmetry: symmetry::applies(config) && !args.has("no-symmetry") && sampling == Sampling::Chance,
+        minimizer: args.parse_value("minimizer").unwrap_or_default(),
+        best_response_opponent: false,
+        deal_script: None,
+        deal_targets: None,
+        sampling,
+    };
+    let mut chance = ChanceStream::new(Some(options.seed), 0, 1);
+
+    let original = match dir {
+        Some(dir) => {
+            let nodes = load_all(&ShardedNodes::open(dir, options.shards, options.cache_shards, config)?)?;
+            println!("Loaded {} nodes from the checkpoint in {}.", nodes.len(), dir);
+            nodes
+        }
+        None => {
+            let mut nodes = HashMap::new();
+            trainer.train_into(&mut nodes, &mut chance, config, options.warmup);
+            println!("Trained {} nodes for {} iterations.", nodes.len(), options.warmup);
+            nodes
+        }
+    };
+
+    let work = std::env::temp_dir().join(format!("liars_dice_checkpoint_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&work);
+    let mut store = ShardedNodes::open(&work, options.shards, options.cache_shards, config)?;
+    store.insert_all(original.clone());
+    store.flush()?;
+    let mut passed = true;
+
+    let reloaded = load_all(&store)?;
+    match first_difference(&original, &reloaded) {
+        None => println!("Round trip: {} nodes read back unchanged.", reloaded.len()),
+        Some(difference) => {
+            println!("Round trip FAILED at {}.", difference);
+            passed = false;
+        }
+    }
+
+    let mut in_memory = original;
+    trainer.train_into(&mut in_memory, &mut chance.clone(), config, options.iterations);
+    trainer.train_into(&mut store, &mut chance, config, options.iterations);
+    store.flush()?;
+    let resumed = load_all(&store)?;
+    match first_difference(&in_memory, &resumed) {
+        None => println!(
+            "Resumed training: {} more iterations agree in memory and from shards ({} shard loads, {} writes).",
+            options.iterations, store.loads, store.writes
+        ),
+        Some(difference) => {
+            println!("Resumed training FAILED at {}.", difference);
+            passed = false;
+        }
+    }
+
+    if options.fuzz_cases > 0 {
+        let (rejected, accepted, panicked) = fuzz_shards(&work, options.fuzz_cases, options.seed)?;
+        println!("Corrupted shards: {} rejected, {} read without error, {} panicked.", rejected, accepted, panicked);
+        if panicked > 0 {
+            println!("Fuzzing FAILED: the shard reader should return an error, not panic.");
+            passed = false;
+        }
+    }
+
+    fs::remove_dir_all(&work)?;
+    Ok(passed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::QuantityCap;
+
+    #[test]
+    fn training_resumes_identically_from_shards() {
+        let config = GameConfig { quantity_cap: QuantityCap::Fixed(1), ..GameConfig::new(1, 1) };
+        let options = VerifyOptions { warmup: 20, iterations: 20, seed: 1, shards: 4, cache_shards: 1, fuzz_cases: 0 };
+        assert!(verify(&Args::parse(&[]), &config, None, &options).unwrap());
+    }
+}
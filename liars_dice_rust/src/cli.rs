@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Options that are only ever switches.
+pub const SWITCHES: &[&str] = &[
+    "average-only",
+    "by-history",
+    "cfr-br",
+    "curriculum",
+    "duplicate",
+    "exhaustive-deals",
+    "exploitability",
+    "full",
+    "hints",
+    "no-symmetry",
+    "reserve-core",
+    "resume-deals",
+    "watch",
+    "work-stealing",
+];
+
+/// Command-line arguments split into positionals and `--flag [value]` options.
+///
+/// Options may be written as `--name value` or `--name=value`; an option that is
+/// followed by another option (or nothing) is treated as a boolean switch. The
+/// names in [`SWITCHES`] never take a value, so a positional after them stays put.
+#[derive(Clone)]
+pub struct Args {
+    pub positional: Vec<String>,
+    flags: HashMap<String, Option<String>>,
+}
+
+impl Args {
+    pub fn parse(raw: &[String]) -> Self {
+        let mut positional = Vec::new();
+        let mut flags = HashMap::new();
+        let mut iter = raw.iter().peekable();
+
+        while let Some(arg) = iter.next() {
+            if let Some(name) = arg.strip_prefix("--") {
+                if let Some((key, value)) = name.split_once('=') {
+                    flags.insert(key.to_string(), Some(value.to_string()));
+                } else {
+                    let value = match iter.peek() {
+                        Some(next) if !next.starts_with("--") && !SWITCHES.contains(&name) => iter.next().cloned(),
+                        _ => None,
+                    };
+                    flags.insert(name.to_string(), value);
+                }
+            } else {
+                positional.push(arg.clone());
+            }
+        }
+
+        Args { positional, flags }
+    }
+
+    /// A copy with `--name value` set, replacing any value it had.
+    pub fn with(&self, name: &str, value: &str) -> Self {
+        let mut args = self.clone();
+        args.flags.insert(name.to_string(), Some(value.to_string()));
+        args
+    }
+
+    pub fn has(&self, name: &str) -> bool {
+        self.flags.contains_key(name)
+    }
+
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.flags.get(name).and_then(|v| v.as_deref())
+    }
+
+    /// Parses an option value, exiting with a readable message if it is malformed.
+    pub fn parse_value<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.value(name).map(|v| {
+            v.parse().unwrap_or_else(|_| {
+                eprintln!("Invalid value for --{}: {}", name, v);
+                std::process::exit(2);
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Args {
+        Args::parse(&line.split_whitespace().map(String::from).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn switches_leave_the_next_positional_alone() {
+        let args = parse("train --no-symmetry 2 2 --iterations 100");
+        assert_eq!(args.positional, ["train", "2", "2"]);
+        assert!(args.has("no-symmetry"));
+        assert_eq!(args.value("no-symmetry"), None);
+        assert_eq!(args.parse_value::<usize>("iterations"), Some(100));
+    }
+
+    #[test]
+    fn valued_options_accept_both_spellings() {
+        let args = parse("play --strategy a.csv --seed=7 --full");
+        assert_eq!(args.value("strategy"), Some("a.csv"));
+        assert_eq!(args.value("seed"), Some("7"));
+        assert!(args.has("full"));
+        assert_eq!(args.positional, ["play"]);
+    }
+}
@@ -0,0 +1,189 @@
+use crate::cli::Args;
+use crate::game::{GameConfig, GameState, DICE_FACES};
+use crate::rebel::{self, challenge_values, Hands, LearnedValues, PbsSettings, PerHand, ValueFunction};
+use crate::strategy::{sample_index, save_strategy, strategy_filename, Policy, SavePrecision, StrategyTable};
+use crate::train;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+
+// Curriculum training: solve the small configurations first and carry what
+// they learned to the larger ones. Configurations with at most `full_dice`
+// dice in all are trained in full with the usual CFR options; larger ones are
+// solved by depth-limited public-belief-state search (see rebel.rs), whose
+// frontier values fall back on estimates from the solutions before them.
+//
+// Bids and hands do not carry over between dice counts, so the estimates are
+// keyed by features that do: whether the player is to move, and by how much
+// the bid exceeds the dice they hold of its face plus the opponent's expected
+// share, in half dice. Each solution plays itself and every state's outcome
+// is averaged under its features for both players.
+
+/// Excess beyond this many half dice either way shares one estimate.
+const MAX_EXCESS: i32 = 8;
+
+/// Curriculum options from the command line.
+pub struct Curriculum {
+    /// Stages with at most this many dice in all are trained in full.
+    pub full_dice: u8,
+    /// Self-play rounds of each solved stage that feed the transferred values.
+    pub transfer_rounds: usize,
+    /// Self-play episodes for each searched stage.
+    pub episodes: usize,
+    pub search: PbsSettings,
+}
+
+impl Curriculum {
+    pub fn from_args(args: &Args) -> Self {
+        Curriculum {
+            full_dice: args.parse_value("full-dice").unwrap_or(4),
+            transfer_rounds: args.parse_value("transfer-rounds").unwrap_or(100_000),
+            episodes: args.parse_value("episodes").unwrap_or(200),
+            search: PbsSettings {
+                depth: args.parse_value("depth").unwrap_or(2),
+                iterations: args.parse_value("subgame-iterations").unwrap_or(100),
+                exploration: 0.1,
+            },
+        }
+    }
+}
+
+/// The configurations solved on the way to `p1_dice`v`p2_dice`, smallest
+/// first: one die more at a time for the player with fewer, the second on a
+/// tie, until each reaches their target, as in 1v1, 1v2, 2v2, 2v3, 3v3.
+pub fn stages(p1_dice: u8, p2_dice: u8) -> Vec<(u8, u8)> {
+    let mut stage = (1, 1);
+    let mut stages = vec![stage];
+    while stage != (p1_dice, p2_dice) {
+        if stage.1 < p2_dice && (stage.1 <= stage.0 || stage.0 == p1_dice) {
+            stage.1 += 1;
+        } else {
+            stage.0 += 1;
+        }
+        stages.push(stage);
+    }
+    stages
+}
+
+/// The features `player` holding `hand` sees at `state`, or `None` before
+/// the first bid: whether they are to move, and the bid's quantity less
+/// their dice of its face and the opponent's expected count, in half dice.
+fn features(state: &GameState, player: usize, hand: &[u8], face_weights: &[f64]) -> Option<(bool, i32)> {
+    let (quantity, face) = state.current_bid?;
+    let held = hand.iter().filter(|&&d| d == face).count() as f64;
+    let opponent_dice = if player == 0 { state.dice_p2 } else { state.dice_p1 };
+    let excess = quantity as f64 - held - opponent_dice as f64 * face_weights[face as usize - 1];
+    let excess = ((2.0 * excess).round() as i32).clamp(-MAX_EXCESS, MAX_EXCESS);
+    Some((state.current_player as usize == player, excess))
+}
+
+/// Frontier values estimated from the solutions of smaller configurations.
+#[derive(Clone)]
+pub struct TransferValues {
+    face_weights: [f64; DICE_FACES as usize],
+    /// Payoff sum and count for each feature pair.
+    table: HashMap<(bool, i32), (f64, f64)>,
+}
+
+impl TransferValues {
+    pub fn new(rules: &GameConfig) -> Self {
+        TransferValues { face_weights: rules.face_weights, table: HashMap::new() }
+    }
+
+    /// Plays `rounds` rounds of `policy` against itself under `config` and
+    /// records the outcome of every state for both players.
+    pub fn observe<P: Policy, R: Rng>(&mut self, policy: &P, config: &GameConfig, rounds: usize, rng: &mut R) {
+        let mut seen = Vec::new();
+        for _ in 0..rounds {
+            let mut state = GameState::deal(config, rng);
+            seen.clear();
+            loop {
+                for (player, hand) in [&state.hand_p1, &state.hand_p2].into_iter().enumerate() {
+                    seen.extend(features(&state, player, hand, &self.face_weights).map(|key| (player, key)));
+                }
+                let actions = state.get_valid_actions();
+                let probs = policy.action_probabilities(&state.get_information_set(), &actions);
+                if state.apply_action(actions[sample_index(&probs, rng)].clone()) {
+                    break;
+                }
+            }
+            for &(player, key) in &seen {
+                let entry = self.table.entry(key).or_insert((0.0, 0.0));
+                entry.0 += state.payoff_for(player as u8) as f64;
+                entry.1 += 1.0;
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+}
+
+impl ValueFunction for TransferValues {
+    /// The transferred estimate for each hand whose features were seen, and
+    /// the value of challenging now for the rest.
+    fn values(&self, state: &GameState, hands: &Hands, beliefs: &PerHand) -> PerHand {
+        let mut values = challenge_values(state, hands, beliefs);
+        for (player, player_values) in values.iter_mut().enumerate() {
+            for ((hand, _), value) in hands[player].iter().zip(player_values.iter_mut()) {
+                let key = features(state, player, hand, &self.face_weights);
+                if let Some((sum, count)) = key.and_then(|key| self.table.get(&key)) {
+                    *value = sum / count;
+                }
+            }
+        }
+        values
+    }
+}
+
+/// Solves every stage up to `rules`' dice in order, saving each strategy
+/// where `train` would. Full stages train for `iterations` with the usual
+/// training options; searched stages start from what all earlier stages
+/// taught the transferred values.
+pub fn train_curriculum(args: &Args, rules: &GameConfig, iterations: usize, curriculum: &Curriculum) {
+    let mut rng = match args.parse_value("seed") {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let mut transfer = TransferValues::new(rules);
+
+    for (p1_dice, p2_dice) in stages(rules.dice_p1, rules.dice_p2) {
+        let mut config = rules.clone();
+        (config.dice_p1, config.dice_p2) = (p1_dice, p2_dice);
+        let filename = strategy_filename(p1_dice, p2_dice);
+
+        let table = if p1_dice + p2_dice <= curriculum.full_dice {
+            println!("Curriculum stage {}v{}: training in full.", p1_dice, p2_dice);
+            let nodes = train::train_config(args, &config, iterations);
+            save_strategy(&nodes, &config, &filename, SavePrecision::from_args(args));
+            StrategyTable::from_nodes(&nodes, SavePrecision::from_args(args))
+        } else {
+            println!(
+                "Curriculum stage {}v{}: {} episodes of depth-{} search from {} transferred estimates.",
+                p1_dice, p2_dice, curriculum.episodes, curriculum.search.depth, transfer.len()
+            );
+            let values = LearnedValues::with_prior(Box::new(transfer.clone()));
+            let (table, values) = rebel::self_play_from(&config, curriculum.episodes, &curriculum.search, values, &mut rng);
+            println!("Learned values for {} entries; policy covers {} info sets.", values.len(), table.entries.len());
+            table.save(&filename, &config).expect("Unable to write strategy file");
+            println!("Saved strategy to {}.", filename);
+            table
+        };
+
+        transfer.observe(&table, &config, curriculum.transfer_rounds, &mut rng);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stages_add_one_die_at_a_time() {
+        assert_eq!(stages(1, 1), [(1, 1)]);
+        assert_eq!(stages(3, 3), [(1, 1), (1, 2), (2, 2), (2, 3), (3, 3)]);
+        assert_eq!(stages(2, 4), [(1, 1), (1, 2), (2, 2), (2, 3), (2, 4)]);
+        assert_eq!(stages(3, 1), [(1, 1), (2, 1), (3, 1)]);
+    }
+}
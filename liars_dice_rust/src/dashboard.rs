@@ -0,0 +1,93 @@
+use crate::http;
+use crate::record::json_string;
+use std::io;
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+/// The page served at `/`; it polls `/status` and needs nothing else.
+const PAGE: &str = include_str!("dashboard.html");
+
+/// How far a training run has got, as the dashboard shows it.
+#[derive(Default)]
+pub struct TrainingStatus {
+    /// What is being trained, e.g. "2v2, MCCFR, outcome sampling".
+    pub label: String,
+    pub iterations: usize,
+    pub done: usize,
+    /// Nodes held by the workers (an info set trained by several workers
+    /// counts once per worker) and their estimated memory, when known.
+    pub nodes: Option<usize>,
+    pub memory_bytes: Option<usize>,
+    /// The last exploitability measured and after how many iterations.
+    pub exploitability: Option<(f64, usize)>,
+    pub finished: bool,
+}
+
+/// A training run's status, served over HTTP from a background thread while
+/// the run updates it between chunks:
+///
+/// `GET /` is a page that polls `GET /status`, which answers with JSON:
+/// the fields of `TrainingStatus` plus elapsed time, rate and ETA.
+pub struct Dashboard {
+    status: Arc<Mutex<TrainingStatus>>,
+}
+
+impl Dashboard {
+    /// Starts serving on `port` at once, so a bad port fails before training does.
+    pub fn start(port: u16, label: String, iterations: usize) -> io::Result<Self> {
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        let status = Arc::new(Mutex::new(TrainingStatus { label, iterations, ..TrainingStatus::default() }));
+        let started = Instant::now();
+        let shared = Arc::clone(&status);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let status = Arc::clone(&shared);
+                thread::spawn(move || {
+                    if let Err(e) = handle(stream, &status, started) {
+                        eprintln!("Dashboard connection error: {}", e);
+                    }
+                });
+            }
+        });
+        println!("Dashboard at http://localhost:{}/", port);
+        Ok(Dashboard { status })
+    }
+
+    pub fn update<F: FnOnce(&mut TrainingStatus)>(&self, f: F) {
+        f(&mut self.status.lock().unwrap());
+    }
+}
+
+fn handle(stream: TcpStream, status: &Mutex<TrainingStatus>, started: Instant) -> io::Result<()> {
+    let request = http::read_request(&stream)?;
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/") => http::respond(&stream, 200, "text/html; charset=utf-8", PAGE),
+        ("GET", "/status") => {
+            let json = status_json(&status.lock().unwrap(), started.elapsed().as_secs_f64());
+            http::respond_json(&stream, 200, &json)
+        }
+        _ => http::respond_error(&stream, 404, "no such endpoint"),
+    }
+}
+
+fn status_json(status: &TrainingStatus, elapsed: f64) -> String {
+    let optional = |value: Option<String>| value.unwrap_or_else(|| "null".to_string());
+    let rate = status.done as f64 / elapsed.max(1e-9);
+    let eta = (!status.finished && status.done > 0).then(|| format!("{:.1}", status.iterations.saturating_sub(status.done) as f64 / rate));
+    format!(
+        "{{\"label\":{},\"iterations\":{},\"done\":{},\"nodes\":{},\"memory_bytes\":{},\"exploitability\":{},\"exploitability_at\":{},\"elapsed_seconds\":{:.1},\"iterations_per_second\":{:.1},\"eta_seconds\":{},\"finished\":{}}}",
+        json_string(&status.label),
+        status.iterations,
+        status.done,
+        optional(status.nodes.map(|n| n.to_string())),
+        optional(status.memory_bytes.map(|m| m.to_string())),
+        optional(status.exploitability.map(|(e, _)| e.to_string())),
+        optional(status.exploitability.map(|(_, at)| at.to_string())),
+        elapsed,
+        rate,
+        optional(eta),
+        status.finished,
+    )
+}
@@ -0,0 +1,332 @@
+use crate::game::{hand_distribution, GameConfig, GameState, Opener, DICE_FACES};
+use crate::record::GameRecord;
+use crate::strategy::write_atomically;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// A fixed list of deals to replay instead of rolling dice, read from a text
+/// file with one deal per line: player 1's hand, a space, player 2's hand,
+/// e.g. `125 34`. Blank lines and lines starting with `#` are ignored.
+///
+/// Deals are handed out in order and the list repeats once exhausted.
+pub struct DealScript {
+    deals: Vec<[Vec<u8>; 2]>,
+    cursor: AtomicUsize,
+}
+
+impl DealScript {
+    pub fn load(path: &str) -> io::Result<Self> {
+        let invalid = |line: usize, msg: &str| io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", line, msg));
+        let mut deals = Vec::new();
+        for (i, line) in fs::read_to_string(path)?.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let hands: Vec<Vec<u8>> = line
+                .split_whitespace()
+                .map(|hand| {
+                    hand.chars()
+                        .map(|c| c.to_digit(10).filter(|&d| (1..=DICE_FACES as u32).contains(&d)).map(|d| d as u8))
+                        .collect::<Option<Vec<u8>>>()
+                })
+                .collect::<Option<_>>()
+                .ok_or_else(|| invalid(i + 1, "dice must be faces 1-6"))?;
+            let deal: [Vec<u8>; 2] = hands.try_into().map_err(|_| invalid(i + 1, "expected two hands"))?;
+            deals.push(deal);
+        }
+        if deals.is_empty() {
+            return Err(invalid(0, "no deals"));
+        }
+        Ok(DealScript { deals, cursor: AtomicUsize::new(0) })
+    }
+
+    /// Whether any deal fits the dice counts of `config`.
+    pub fn fits(&self, config: &GameConfig) -> bool {
+        self.deals.iter().any(|deal| Self::matches(deal, config))
+    }
+
+    /// The opening state for the next scripted deal that fits `config`, skipping
+    /// any for other dice counts, or `None` if the script has no deal of that size.
+    pub fn next_state(&self, config: &GameConfig) -> Option<GameState> {
+        self.nth_state(config, self.cursor.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// As `next_state`, for the `n`th fitting deal (cycling) regardless of the cursor.
+    pub fn nth_state(&self, config: &GameConfig, n: usize) -> Option<GameState> {
+        let fitting: Vec<&[Vec<u8>; 2]> = self.deals.iter().filter(|deal| Self::matches(deal, config)).collect();
+        if fitting.is_empty() {
+            return None;
+        }
+        let [hand_p1, hand_p2] = fitting[n % fitting.len()];
+        Some(GameState::from_hands(config, hand_p1.clone(), hand_p2.clone()))
+    }
+
+    fn matches(deal: &[Vec<u8>; 2], config: &GameConfig) -> bool {
+        deal[0].len() == config.dice_p1 as usize && deal[1].len() == config.dice_p2 as usize
+    }
+}
+
+/// Deals from recorded games, for training that concentrates on the hands
+/// people actually get. A `share` of deals is drawn from the records, the rest
+/// at random, and each deal carries the importance weight
+/// P(deal) / P(sampled deal), so training still solves the real game.
+pub struct DealTargets {
+    /// Recorded deals (opener's hand first) by the round's dice counts.
+    deals: HashMap<(u8, u8), Vec<[Vec<u8>; 2]>>,
+    counts: HashMap<[Vec<u8>; 2], usize>,
+    /// Probability of rolling each sorted hand.
+    hand_probs: HashMap<Vec<u8>, f64>,
+    share: f64,
+}
+
+impl DealTargets {
+    /// Keeps the records whose dice counts fit one of `config`'s openings.
+    pub fn from_records(records: &[GameRecord], config: &GameConfig, share: f64) -> Self {
+        let rounds: Vec<(u8, u8)> = config.openings().iter().map(|(round, _)| (round.dice_p1, round.dice_p2)).collect();
+        let mut deals: HashMap<(u8, u8), Vec<[Vec<u8>; 2]>> = HashMap::new();
+        let mut counts = HashMap::new();
+        for record in records {
+            let mut deal = record.hands.clone();
+            deal.iter_mut().for_each(|hand| hand.sort());
+            let dice = (deal[0].len() as u8, deal[1].len() as u8);
+            if rounds.contains(&dice) {
+                *counts.entry(deal.clone()).or_insert(0) += 1;
+                deals.entry(dice).or_default().push(deal);
+            }
+        }
+        let hand_probs = [config.dice_p1, config.dice_p2]
+            .iter()
+            .flat_map(|&n| hand_distribution(n, config))
+            .collect();
+        DealTargets { deals, counts, hand_probs, share }
+    }
+
+    /// Recorded deals that fit the rules.
+    pub fn len(&self) -> usize {
+        self.deals.values().map(Vec::len).sum()
+    }
+
+    /// A deal for `round` (player 0 opening) and its importance weight.
+    fn draw<R: Rng>(&self, round: &GameConfig, rng: &mut R) -> (GameState, f32) {
+        let Some(recorded) = self.deals.get(&(round.dice_p1, round.dice_p2)) else {
+            return (GameState::deal(round, rng), 1.0);
+        };
+        let state = if rng.gen_bool(self.share) {
+            let [hand_p1, hand_p2] = &recorded[rng.gen_range(0..recorded.len())];
+            GameState::from_hands(round, hand_p1.clone(), hand_p2.clone())
+        } else {
+            GameState::deal(round, rng)
+        };
+        let deal = [state.hand_p1.clone(), state.hand_p2.clone()];
+        let p = self.hand_probs[&deal[0]] * self.hand_probs[&deal[1]];
+        let recorded_p = self.counts.get(&deal).map_or(0.0, |&c| c as f64 / recorded.len() as f64);
+        let weight = p / ((1.0 - self.share) * p + self.share * recorded_p);
+        (state, weight as f32)
+    }
+}
+
+/// Sorted hands with their probabilities, as `hand_distribution` gives them.
+type HandDistribution = Vec<(Vec<u8>, f64)>;
+
+/// Every deal the rules allow in a fixed order: each opening, then player 1's
+/// hands, then player 2's, in `hand_distribution` order. Sweeping it deals
+/// each hand pair once, weighted by its probability, so a full sweep is an
+/// exact expectation over chance rather than a sample.
+pub struct DealEnumeration {
+    /// Each opening with its probability and both players' hands.
+    rounds: Vec<(GameConfig, f64, [HandDistribution; 2])>,
+    len: usize,
+}
+
+impl DealEnumeration {
+    pub fn new(config: &GameConfig) -> Self {
+        let rounds: Vec<_> = config
+            .openings()
+            .into_iter()
+            .map(|(round, p)| {
+                let hands = [hand_distribution(round.dice_p1, &round), hand_distribution(round.dice_p2, &round)];
+                (round, p, hands)
+            })
+            .collect();
+        let len = rounds.iter().map(|(_, _, [h1, h2])| h1.len() * h2.len()).sum();
+        DealEnumeration { rounds, len }
+    }
+
+    /// Deals in one sweep.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// The `n`th deal (cycling through sweeps) with its weight: the deal's
+    /// probability times the deals per sweep, so weights average 1 over a
+    /// sweep as sampled deals' do.
+    pub fn nth(&self, n: usize) -> (GameState, f32) {
+        let mut index = n % self.len;
+        for (round, p, [hands_p1, hands_p2]) in &self.rounds {
+            let size = hands_p1.len() * hands_p2.len();
+            if index >= size {
+                index -= size;
+                continue;
+            }
+            let ((hand_p1, q1), (hand_p2, q2)) = (&hands_p1[index / hands_p2.len()], &hands_p2[index % hands_p2.len()]);
+            let weight = p * q1 * q2 * self.len as f64;
+            return (GameState::from_hands(round, hand_p1.clone(), hand_p2.clone()), weight as f32);
+        }
+        unreachable!("index is below the sweep length")
+    }
+}
+
+/// How far an enumeration has got, saved next to the strategy so a sweep can
+/// continue after a restart or be split across machines by deal range.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DealCursor {
+    /// Global index of the next deal to train on.
+    pub next: usize,
+    pub per_sweep: usize,
+}
+
+impl DealCursor {
+    /// `<strategy_file>.deals`.
+    pub fn filename(strategy_file: &str) -> String {
+        format!("{}.deals", strategy_file)
+    }
+
+    pub fn load(path: &str) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path, msg));
+        let (mut next, mut per_sweep) = (None, None);
+        for line in fs::read_to_string(path)?.lines() {
+            match line.split_once('=') {
+                Some(("next_deal", v)) => next = Some(v.trim().parse().map_err(|_| invalid("bad next_deal"))?),
+                Some(("deals_per_sweep", v)) => per_sweep = Some(v.trim().parse().map_err(|_| invalid("bad deals_per_sweep"))?),
+                _ => {}
+            }
+        }
+        match (next, per_sweep) {
+            (Some(next), Some(per_sweep)) => Ok(DealCursor { next, per_sweep }),
+            _ => Err(invalid("missing next_deal or deals_per_sweep")),
+        }
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        write_atomically(path, |file| {
+            writeln!(file, "next_deal={}", self.next)?;
+            writeln!(file, "deals_per_sweep={}", self.per_sweep)
+        })
+    }
+}
+
+/// One training worker's source of deals. Seeded streams are derived from
+/// (seed, worker), so a run with the same seed and worker count deals the
+/// same hands to the same workers; scripted deals are split round-robin,
+/// worker `w` of `n` taking deals `w`, `w + n`, `w + 2n`, ... Alternating
+/// openers follow the same global deal numbering, as do enumerated deals,
+/// offset by the enumeration's starting cursor.
+#[derive(Clone)]
+pub struct ChanceStream {
+    rng: StdRng,
+    worker: usize,
+    workers: usize,
+    dealt: usize,
+    enumeration: Option<(Arc<DealEnumeration>, usize)>,
+}
+
+impl ChanceStream {
+    pub fn new(seed: Option<u64>, worker: usize, workers: usize) -> Self {
+        let rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(worker_seed(seed, worker as u64)),
+            None => StdRng::from_entropy(),
+        };
+        ChanceStream { rng, worker, workers, dealt: 0, enumeration: None }
+    }
+
+    /// Deals from `deals` in order, starting at global deal `start`.
+    pub fn enumerating(mut self, deals: Arc<DealEnumeration>, start: usize) -> Self {
+        self.enumeration = Some((deals, start));
+        self
+    }
+
+    /// The worker's random stream, for sampling beyond the deal.
+    pub fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+
+    /// The next deal, with player 0 opening: when `config.opener` has the
+    /// second seat open, the dice counts and hands are swapped to match.
+    /// Returns the deal's importance weight too, which is 1 unless it was
+    /// drawn from `targets` or an enumeration.
+    pub fn deal(&mut self, config: &GameConfig, script: Option<&DealScript>, targets: Option<&DealTargets>) -> (GameState, f32) {
+        let n = self.worker + self.dealt * self.workers;
+        self.dealt += 1;
+        if let Some((deals, start)) = &self.enumeration {
+            return deals.nth(start + n);
+        }
+        let second_seat_opens = match config.opener {
+            Opener::First => false,
+            Opener::Second => true,
+            Opener::Alternate => n % 2 == 1,
+            Opener::Random => self.rng.gen_bool(0.5),
+        };
+        if let Some(targets) = targets {
+            return targets.draw(&config.opening(second_seat_opens), &mut self.rng);
+        }
+        let state = script
+            .and_then(|s| s.nth_state(config, n))
+            .unwrap_or_else(|| GameState::deal(config, &mut self.rng));
+        if second_seat_opens {
+            (state.with_seats_swapped(&config.opening(true)), 1.0)
+        } else {
+            (state, 1.0)
+        }
+    }
+}
+
+/// Mixes the worker index into the seed (SplitMix64 finalizer) so nearby
+/// seeds and workers still get unrelated streams.
+fn worker_seed(seed: u64, worker: u64) -> u64 {
+    let mut z = seed ^ worker.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursors_round_trip_through_their_file() {
+        let path = std::env::temp_dir().join(format!("liars_dice_cursor_{}.deals", std::process::id()));
+        let path = path.to_str().unwrap();
+        let cursor = DealCursor { next: 1234, per_sweep: 36 };
+        cursor.save(path).unwrap();
+        assert_eq!(DealCursor::load(path).unwrap(), cursor);
+
+        fs::write(path, "next_deal=5\n").unwrap();
+        assert_eq!(DealCursor::load(path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        fs::write(path, "next_deal=five\ndeals_per_sweep=36\n").unwrap();
+        assert!(DealCursor::load(path).is_err());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn enumeration_covers_every_deal_once() {
+        let config = GameConfig::new(1, 1);
+        let deals = DealEnumeration::new(&config);
+        assert_eq!(deals.len(), 36);
+        let mut seen: Vec<(Vec<u8>, Vec<u8>)> = (0..deals.len()).map(|n| deals.nth(n).0).map(|s| (s.hand_p1, s.hand_p2)).collect();
+        seen.sort();
+        seen.dedup();
+        assert_eq!(seen.len(), deals.len());
+        // Weights average 1 over a sweep, as sampled deals' do.
+        let total: f32 = (0..deals.len()).map(|n| deals.nth(n).1).sum();
+        assert!((total / deals.len() as f32 - 1.0).abs() < 1e-5, "mean weight {}", total / deals.len() as f32);
+        // Past the end of a sweep the enumeration starts over.
+        assert_eq!(deals.nth(deals.len()).0.hand_p1, deals.nth(0).0.hand_p1);
+    }
+}
@@ -0,0 +1,152 @@
+use crate::bundle::StrategyBundle;
+use crate::game::{Action, GameConfig, GameState, DICE_FACES};
+use crate::rollout::RolloutFallback;
+use crate::strategy::{apply_temperature, parse_action, sample_index, Policy};
+use rand::Rng;
+use std::io::{self, BufRead, Write};
+
+/// Sent in reply to the handshake and before any other output.
+pub const ENGINE_NAME: &str = "liars_dice_rust";
+
+/// Plays rounds for an arena over a line protocol, in the spirit of UCI:
+///
+/// ```text
+/// > liarsdice                          handshake; replies "id name ...", then "liarsdiceok"
+/// > isready                            replies "readyok"
+/// > newround <my_dice> <opp_dice> <first|second> <hand>
+///                                      starts a round, e.g. "newround 3 2 first 146"
+/// > opponent <action>                  the opponent played 2-5, challenge or calza
+/// > go                                 replies "action <action>" and plays it
+/// > quit
+/// ```
+///
+/// Anything malformed or illegal is answered with `error <reason>` and
+/// otherwise ignored, so the arena can carry on. A round ends at the first
+/// challenge or Calza; the next `newround` starts another.
+pub struct Engine<'a, R> {
+    bundle: &'a StrategyBundle,
+    rules: &'a GameConfig,
+    fallback: Option<&'a RolloutFallback>,
+    /// Sampling temperature for the engine's moves (see `apply_temperature`).
+    temperature: f64,
+    rng: R,
+    /// The round in play, seen from the engine's seat: the engine is player
+    /// `seat` and the opponent's hand is a placeholder it never looks at.
+    round: Option<(GameState, u8)>,
+}
+
+impl<'a, R: Rng> Engine<'a, R> {
+    pub fn new(bundle: &'a StrategyBundle, rules: &'a GameConfig, fallback: Option<&'a RolloutFallback>, temperature: f64, rng: R) -> Self {
+        Engine { bundle, rules, fallback, temperature, rng, round: None }
+    }
+
+    /// Answers commands from `input` on `output` until `quit` or end of input.
+    pub fn run<I: BufRead, O: Write>(&mut self, input: I, output: &mut O) -> io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            let mut words = line.split_whitespace();
+            let Some(command) = words.next() else {
+                continue;
+            };
+            let args: Vec<&str> = words.collect();
+            let reply = match command {
+                "quit" => return Ok(()),
+                "liarsdice" => Ok(format!("id name {}\nid version {}\nliarsdiceok", ENGINE_NAME, env!("CARGO_PKG_VERSION"))),
+                "isready" => Ok("readyok".to_string()),
+                "newround" => self.new_round(&args).map(|()| String::new()),
+                "opponent" => self.opponent(&args).map(|()| String::new()),
+                "go" => self.go().map(|action| format!("action {}", format_action(&action))),
+                _ => Err(format!("unknown command '{}'", command)),
+            };
+            match reply {
+                Ok(reply) if reply.is_empty() => continue,
+                Ok(reply) => writeln!(output, "{}", reply)?,
+                Err(e) => writeln!(output, "error {}", e)?,
+            }
+            output.flush()?;
+        }
+        Ok(())
+    }
+
+    fn new_round(&mut self, args: &[&str]) -> Result<(), String> {
+        let [my_dice, opp_dice, order, hand] = args else {
+            return Err("usage: newround <my_dice> <opp_dice> <first|second> <hand>".to_string());
+        };
+        let my_dice: u8 = my_dice.parse().map_err(|_| format!("invalid dice count '{}'", my_dice))?;
+        let opp_dice: u8 = opp_dice.parse().map_err(|_| format!("invalid dice count '{}'", opp_dice))?;
+        let hand: Vec<u8> = hand
+            .chars()
+            .map(|c| c.to_digit(10).filter(|d| (1..=DICE_FACES as u32).contains(d)).map(|d| d as u8))
+            .collect::<Option<_>>()
+            .ok_or_else(|| format!("invalid hand '{}'", hand))?;
+        if hand.len() != my_dice as usize {
+            return Err(format!("a hand of {} dice does not fit {} dice", hand.len(), my_dice));
+        }
+        let seat = match *order {
+            "first" => 0,
+            "second" => 1,
+            other => return Err(format!("expected first or second, not '{}'", other)),
+        };
+
+        let mut config = self.rules.opening(false);
+        (config.dice_p1, config.dice_p2) = if seat == 0 { (my_dice, opp_dice) } else { (opp_dice, my_dice) };
+        if self.bundle.get(config.dice_p1, config.dice_p2).is_none() {
+            return Err(format!("no strategy for {}v{}", config.dice_p1, config.dice_p2));
+        }
+        let placeholder = vec![1; opp_dice as usize];
+        let state = if seat == 0 { GameState::from_hands(&config, hand, placeholder) } else { GameState::from_hands(&config, placeholder, hand) };
+        self.round = Some((state, seat));
+        Ok(())
+    }
+
+    fn opponent(&mut self, args: &[&str]) -> Result<(), String> {
+        let [action] = args else {
+            return Err("usage: opponent <action>".to_string());
+        };
+        let action = parse_engine_action(action).ok_or_else(|| format!("invalid action '{}'", action))?;
+        let (state, seat) = open_round(&mut self.round)?;
+        if state.current_player == *seat {
+            return Err("it is the engine's turn".to_string());
+        }
+        state.try_apply(action.clone()).map_err(|e| format!("{} is not legal here: {}", format_action(&action), e))?;
+        Ok(())
+    }
+
+    fn go(&mut self) -> Result<Action, String> {
+        let (state, seat) = open_round(&mut self.round)?;
+        if state.current_player != *seat {
+            return Err("it is the opponent's turn".to_string());
+        }
+        let table = self.bundle.get(state.dice_p1, state.dice_p2).expect("checked when the round started");
+        let actions = state.get_valid_actions();
+        let probs = match self.fallback {
+            Some(fallback) => fallback.action_probabilities(table, state, &actions, &mut self.rng),
+            None => table.action_probabilities(&state.get_information_set(), &actions),
+        };
+        let action = actions[sample_index(&apply_temperature(&probs, self.temperature), &mut self.rng)].clone();
+        state.apply_action(action.clone());
+        Ok(action)
+    }
+}
+
+/// The round in play, if it is still open.
+fn open_round(round: &mut Option<(GameState, u8)>) -> Result<&mut (GameState, u8), String> {
+    match round {
+        None => Err("no round in play; send newround".to_string()),
+        Some((state, _)) if state.final_call.is_some() => Err("the round is over; send newround".to_string()),
+        Some(round) => Ok(round),
+    }
+}
+
+/// Actions as the protocol writes them: `2-5`, `challenge`, `calza`.
+fn format_action(action: &Action) -> String {
+    action.to_string().to_lowercase()
+}
+
+fn parse_engine_action(s: &str) -> Option<Action> {
+    match s.to_lowercase().as_str() {
+        "challenge" => Some(Action::Challenge),
+        "calza" => Some(Action::Calza),
+        bid => parse_action(bid),
+    }
+}
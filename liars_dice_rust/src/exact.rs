@@ -0,0 +1,267 @@
+use crate::game::{hand_distribution, Action, GameConfig, GameState, HistoryAbstraction, Utility};
+use crate::lp::{LinearProgram, LpError, Relation};
+use crate::strategy::StrategyTable;
+use std::collections::HashMap;
+
+/// Most sequences either player may have; building the sequence form stops
+/// once a game passes this.
+const MAX_SEQUENCES: usize = 250_000;
+/// Most constraint nonzeros the exact solver will take on; the factors of a
+/// basis hold a few times as many.
+const MAX_NONZEROS: usize = 20_000_000;
+
+/// An exact equilibrium of one round, found by linear programming on the
+/// sequence form of the perfect-recall game.
+pub struct ExactSolution {
+    /// Expected payoff of the opener (player 0) under equilibrium play.
+    pub value: f64,
+    /// The responder's optimal value plus the opener's; zero up to round-off.
+    pub duality_gap: f64,
+    /// Both players' equilibrium strategies, keyed by full-history info sets.
+    pub table: StrategyTable,
+    pub sequences: [usize; 2],
+}
+
+/// An info set of the perfect-recall game and where its sequences sit.
+struct InfoSet {
+    key: String,
+    actions: Vec<Action>,
+    /// The player's sequence leading here (0 is the empty sequence).
+    parent: usize,
+    /// Index of the sequence for `actions[0]`; the rest follow in order.
+    first: usize,
+}
+
+/// The sequence form of a round: each player's info sets and sequences, and
+/// the opener's chance-weighted payoff for every pair of terminal sequences.
+struct SequenceForm {
+    hands: [Vec<(Vec<u8>, f64)>; 2],
+    info_sets: [Vec<InfoSet>; 2],
+    sequences: [usize; 2],
+    payoffs: HashMap<(usize, usize), f64>,
+}
+
+impl SequenceForm {
+    fn build(config: &GameConfig) -> Self {
+        let mut form = SequenceForm {
+            hands: [hand_distribution(config.dice_p1, config), hand_distribution(config.dice_p2, config)],
+            info_sets: [Vec::new(), Vec::new()],
+            sequences: [1, 1],
+            payoffs: HashMap::new(),
+        };
+        let last = [vec![0; form.hands[0].len()], vec![0; form.hands[1].len()]];
+        let root = GameState::from_hands(config, form.hands[0][0].0.clone(), form.hands[1][0].0.clone());
+        form.walk(&root, &last);
+        form
+    }
+
+    /// Registers the info sets at `state` for every hand of the player to move
+    /// and recurses; `last` is each player's latest sequence per hand.
+    fn walk(&mut self, state: &GameState, last: &[Vec<usize>; 2]) {
+        if self.too_large() {
+            return;
+        }
+        let player = state.current_player as usize;
+        let actions = state.get_valid_actions();
+        let mut firsts = Vec::with_capacity(self.hands[player].len());
+        for (h, (hand, _)) in self.hands[player].iter().enumerate() {
+            let first = self.sequences[player];
+            self.sequences[player] += actions.len();
+            self.info_sets[player].push(InfoSet {
+                key: state.information_set_for(hand),
+                actions: actions.clone(),
+                parent: last[player][h],
+                first,
+            });
+            firsts.push(first);
+        }
+
+        for (i, action) in actions.iter().enumerate() {
+            let mut next_last = last.clone();
+            for (seq, first) in next_last[player].iter_mut().zip(&firsts) {
+                *seq = first + i;
+            }
+            let mut next = state.clone();
+            if next.apply_action(action.clone()) {
+                self.add_terminal(next, &next_last);
+            } else {
+                self.walk(&next, &next_last);
+            }
+        }
+    }
+
+    fn too_large(&self) -> bool {
+        self.sequences.iter().any(|&n| n > MAX_SEQUENCES)
+    }
+
+    fn add_terminal(&mut self, mut state: GameState, last: &[Vec<usize>; 2]) {
+        for (h0, (hand0, p0)) in self.hands[0].iter().enumerate() {
+            for (h1, (hand1, p1)) in self.hands[1].iter().enumerate() {
+                state.hand_p1.clone_from(hand0);
+                state.hand_p2.clone_from(hand1);
+                let payoff = p0 * p1 * state.payoff_for(0) as f64;
+                *self.payoffs.entry((last[0][h0], last[1][h1])).or_insert(0.0) += payoff;
+            }
+        }
+    }
+
+    /// The linear program for `player`'s maximin realization plan:
+    ///
+    /// maximize q_root subject to F^T q - A^T x <= 0, E x = e, x >= 0,
+    ///
+    /// where x is `player`'s realization plan, q holds one free value per
+    /// opponent info set plus the root, and A is the payoff matrix from
+    /// `player`'s side. Columns are x, then q.
+    fn program(&self, player: usize) -> LinearProgram {
+        let opponent = 1 - player;
+        let own = self.sequences[player];
+        let values = self.info_sets[opponent].len() + 1;
+        let mut lp = LinearProgram::new(own + values);
+        lp.set_objective(own, 1.0);
+        for q in own..own + values {
+            lp.set_free(q);
+        }
+
+        // One inequality per opponent sequence.
+        let mut rows: Vec<Vec<(usize, f64)>> = vec![Vec::new(); self.sequences[opponent]];
+        rows[0].push((own, 1.0));
+        for (i, info_set) in self.info_sets[opponent].iter().enumerate() {
+            rows[info_set.parent].push((own + i + 1, -1.0));
+            for row in &mut rows[info_set.first..info_set.first + info_set.actions.len()] {
+                row.push((own + i + 1, 1.0));
+            }
+        }
+        let sign = if player == 0 { 1.0 } else { -1.0 };
+        for (&(s0, s1), &payoff) in &self.payoffs {
+            let (own_seq, opp_seq) = if player == 0 { (s0, s1) } else { (s1, s0) };
+            rows[opp_seq].push((own_seq, -sign * payoff));
+        }
+        for row in rows {
+            lp.add_constraint(row, Relation::LessEq, 0.0);
+        }
+
+        // The realization plan: the empty sequence has weight 1 and every info
+        // set splits its parent sequence's weight among its actions.
+        lp.add_constraint(vec![(0, 1.0)], Relation::Equal, 1.0);
+        for info_set in &self.info_sets[player] {
+            let mut row = vec![(info_set.parent, 1.0)];
+            row.extend((info_set.first..info_set.first + info_set.actions.len()).map(|seq| (seq, -1.0)));
+            lp.add_constraint(row, Relation::Equal, 0.0);
+        }
+        lp
+    }
+
+    /// `player`'s behavior strategy from a realization plan. Info sets the plan
+    /// never reaches are played uniformly.
+    fn behavior(&self, player: usize, plan: &[f64], table: &mut StrategyTable) {
+        for info_set in &self.info_sets[player] {
+            let reach = plan[info_set.parent];
+            let n = info_set.actions.len();
+            let probs: Vec<(Action, f64)> = info_set
+                .actions
+                .iter()
+                .enumerate()
+                .map(|(i, action)| {
+                    let p = if reach > 1e-12 { (plan[info_set.first + i] / reach).clamp(0.0, 1.0) } else { 1.0 / n as f64 };
+                    (action.clone(), p)
+                })
+                .filter(|(_, p)| *p > 1e-9)
+                .collect();
+            table.entries.insert(info_set.key.clone(), probs);
+        }
+    }
+}
+
+/// The rules `solve` plays under: as given, but with perfect recall.
+pub fn exact_config(config: &GameConfig) -> GameConfig {
+    let mut exact = config.clone();
+    exact.history_abstraction = HistoryAbstraction::Full;
+    exact
+}
+
+/// Solves one round exactly, with player 0 opening. The linear programs grow
+/// with the full bid tree, so this is only feasible for a handful of dice, or
+/// more with a capped bid range; larger games are refused rather than run out
+/// of memory, as are non-linear utilities, which make the round non-zero-sum.
+pub fn solve(config: &GameConfig) -> Result<ExactSolution, String> {
+    config.require_private_hands("the exact solver")?;
+    if config.seat_dice.is_some() {
+        return Err("the exact solver does not support team play".to_string());
+    }
+    if config.utility != Utility::Linear {
+        // Under a concave utility the round is no longer zero-sum, so one LP value cannot describe it.
+        return Err(format!("the exact solver needs linear utility, not {}", config.utility));
+    }
+    let config = exact_config(config);
+    let form = SequenceForm::build(&config);
+    if form.too_large() {
+        return Err(format!(
+            "{}v{} has over {} sequences, too many for the exact solver; narrow the bids with --max-quantity or --quantity-step",
+            config.dice_p1, config.dice_p2, MAX_SEQUENCES
+        ));
+    }
+    let programs = [form.program(0), form.program(1)];
+    let size = programs.iter().map(LinearProgram::nonzeros).max().unwrap_or(0);
+    if size > MAX_NONZEROS {
+        return Err(format!(
+            "{}v{} has {} and {} sequences, too many for the exact solver; narrow the bids with --max-quantity or --quantity-step",
+            config.dice_p1, config.dice_p2, form.sequences[0], form.sequences[1]
+        ));
+    }
+
+    let mut table = StrategyTable::default();
+    let mut values = [0.0; 2];
+    for (player, program) in programs.iter().enumerate() {
+        let (value, solution) = program.maximize().map_err(|e| match e {
+            LpError::Infeasible => "the sequence-form program is infeasible".to_string(),
+            LpError::Unbounded => "the sequence-form program is unbounded".to_string(),
+            LpError::IterationLimit => "the simplex method hit its iteration limit".to_string(),
+        })?;
+        form.behavior(player, &solution[..form.sequences[player]], &mut table);
+        values[player] = value;
+    }
+
+    Ok(ExactSolution {
+        value: values[0],
+        duality_gap: values[0] + values[1],
+        table,
+        sequences: form.sequences,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::QuantityCap;
+
+    fn three_sided() -> GameConfig {
+        let mut config = GameConfig::new(1, 1).with_face_weights([1.0, 1.0, 1.0, 0.0, 0.0, 0.0]);
+        config.quantity_cap = QuantityCap::Fixed(1);
+        config
+    }
+
+    #[test]
+    fn zero_sum_rounds_close_the_duality_gap() {
+        let solution = solve(&three_sided()).unwrap();
+        assert!(solution.duality_gap.abs() < 1e-6, "gap {}", solution.duality_gap);
+        assert!(solution.value.abs() <= 1.0);
+    }
+
+    #[test]
+    fn non_linear_utilities_are_refused() {
+        let config = GameConfig { utility: Utility::RiskAverse(0.5), ..three_sided() };
+        match solve(&config) {
+            Err(e) => assert!(e.contains("linear utility"), "{}", e),
+            Ok(_) => panic!("a risk-averse round was solved as zero-sum"),
+        }
+    }
+
+    #[test]
+    fn uncapped_one_die_rounds_solve_exactly() {
+        let solution = solve(&GameConfig::new(1, 1)).unwrap();
+        assert!(solution.sequences[0] > 20_000, "sequences {:?}", solution.sequences);
+        assert!((solution.value + 1.0 / 9.0).abs() < 1e-6, "value {}", solution.value);
+        assert!(solution.duality_gap.abs() < 1e-6, "gap {}", solution.duality_gap);
+    }
+}
+
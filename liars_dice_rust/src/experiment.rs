@@ -0,0 +1,167 @@
+use crate::cli::Args;
+use crate::exploitability;
+use crate::game::{GameConfig, GameState};
+use crate::simulate;
+use crate::strategy::{sample_index, save_strategy, write_atomically, Policy, SavePrecision, StrategyTable};
+use crate::train;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rayon::prelude::*;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::time::Instant;
+
+/// One independent training run of an experiment.
+pub struct RunResult {
+    pub seed: u64,
+    pub seconds: f64,
+    pub exploitability: f64,
+    pub table: StrategyTable,
+}
+
+/// Average payoff of one run against another over simulated rounds.
+pub struct HeadToHead {
+    pub run_a: usize,
+    pub run_b: usize,
+    pub games: usize,
+    /// Run A's mean payoff per round, in dice.
+    pub mean: f64,
+    /// Of the mean; per deal pair when scoring duplicate deals.
+    pub std_error: f64,
+}
+
+/// Mean and sample standard deviation.
+pub fn mean_and_stddev(values: &[f64]) -> (f64, f64) {
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n.max(1.0);
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0).max(1.0);
+    (mean, variance.sqrt())
+}
+
+/// Plays `games` rounds between two strategies, alternating which opens, and
+/// scores them from `a`'s side as (mean payoff per round, standard error).
+/// Openings follow the rules' opener setting. With `duplicate`, each deal is
+/// played twice with the strategies' seats swapped and scored as the pair's
+/// average, which cancels most of the luck of the deal.
+pub fn head_to_head(a: &StrategyTable, b: &StrategyTable, config: &GameConfig, games: usize, duplicate: bool, seed: u64) -> (f64, f64) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let openings = config.openings();
+    let weights: Vec<f64> = openings.iter().map(|(_, p)| *p).collect();
+    let deals = if duplicate { games.div_ceil(2) } else { games };
+    let scores: Vec<f64> = (0..deals)
+        .map(|deal| {
+            let round = &openings[sample_index(&weights, &mut rng)].0;
+            let state = GameState::deal(round, &mut rng);
+            if duplicate {
+                let a_opens = simulate::play_out(state.clone(), [a as &dyn Policy, b], &mut rng);
+                let b_opens = simulate::play_out(state, [b as &dyn Policy, a], &mut rng);
+                (a_opens - b_opens) / 2.0
+            } else if deal % 2 == 0 {
+                simulate::play_out(state, [a as &dyn Policy, b], &mut rng)
+            } else {
+                -simulate::play_out(state, [b as &dyn Policy, a], &mut rng)
+            }
+        })
+        .collect();
+    let (mean, stddev) = mean_and_stddev(&scores);
+    (mean, stddev / (deals as f64).sqrt())
+}
+
+/// Everything an experiment produced, for the report and the results directory.
+pub struct Experiment {
+    pub config: GameConfig,
+    pub iterations: usize,
+    pub runs: Vec<RunResult>,
+    pub matches: Vec<HeadToHead>,
+}
+
+impl Experiment {
+    /// Trains `runs` times with seeds `first_seed`, `first_seed + 1`, ...,
+    /// saving each strategy into `dir`, then plays every pair of runs
+    /// head-to-head for `games` rounds (see `head_to_head`).
+    pub fn run(args: &Args, config: &GameConfig, iterations: usize, runs: usize, games: usize, dir: &str) -> io::Result<Self> {
+        let duplicate = args.has("duplicate");
+        fs::create_dir_all(dir)?;
+        let first_seed: u64 = args.parse_value("seed").unwrap_or(0);
+        let mut results = Vec::with_capacity(runs);
+        for run in 0..runs {
+            let seed = first_seed + run as u64;
+            println!();
+            println!("=== Run {} of {} (seed {}) ===", run + 1, runs, seed);
+            let start = Instant::now();
+            let nodes = train::train_config(&args.with("seed", &seed.to_string()), config, iterations);
+            let seconds = start.elapsed().as_secs_f64();
+            let exploitability = exploitability::exploitability(&nodes, config);
+            println!("Run {} exploitability: {:.6}", run + 1, exploitability);
+            let precision = SavePrecision::from_args(args);
+            save_strategy(&nodes, config, &format!("{}/run_{}.csv", dir, run + 1), precision);
+            results.push(RunResult { seed, seconds, exploitability, table: StrategyTable::from_nodes(&nodes, precision) });
+        }
+
+        let pairs: Vec<(usize, usize)> = (0..runs).flat_map(|a| (a + 1..runs).map(move |b| (a, b))).collect();
+        let matches = pairs
+            .par_iter()
+            .map(|&(a, b)| {
+                let (mean, std_error) = head_to_head(&results[a].table, &results[b].table, config, games, duplicate, first_seed ^ (a * runs + b) as u64);
+                HeadToHead { run_a: a, run_b: b, games, mean, std_error }
+            })
+            .collect();
+        Ok(Experiment { config: config.clone(), iterations, runs: results, matches })
+    }
+
+    /// Each run's mean head-to-head payoff against all the others.
+    pub fn run_scores(&self) -> Vec<f64> {
+        let mut totals = vec![0.0; self.runs.len()];
+        for m in &self.matches {
+            totals[m.run_a] += m.mean;
+            totals[m.run_b] -= m.mean;
+        }
+        let opponents = (self.runs.len() - 1).max(1) as f64;
+        totals.iter().map(|t| t / opponents).collect()
+    }
+
+    /// Writes `runs.csv`, `head_to_head.csv` and `summary.txt` into `dir`.
+    pub fn write(&self, dir: &str) -> io::Result<()> {
+        let scores = self.run_scores();
+        write_atomically(&format!("{}/runs.csv", dir), |file| {
+            writeln!(file, "Run,Seed,Iterations,Seconds,Exploitability,HeadToHead")?;
+            for (i, (run, score)) in self.runs.iter().zip(&scores).enumerate() {
+                writeln!(file, "{},{},{},{:.3},{},{}", i + 1, run.seed, self.iterations, run.seconds, run.exploitability, score)?;
+            }
+            Ok(())
+        })?;
+        write_atomically(&format!("{}/head_to_head.csv", dir), |file| {
+            writeln!(file, "RunA,RunB,Games,MeanPayoffA,StdError")?;
+            for m in &self.matches {
+                writeln!(file, "{},{},{},{},{}", m.run_a + 1, m.run_b + 1, m.games, m.mean, m.std_error)?;
+            }
+            Ok(())
+        })?;
+        write_atomically(&format!("{}/summary.txt", dir), |file| write!(file, "{}", self))
+    }
+}
+
+impl fmt::Display for Experiment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let exploitabilities: Vec<f64> = self.runs.iter().map(|r| r.exploitability).collect();
+        let (mean, stddev) = mean_and_stddev(&exploitabilities);
+        let seconds: Vec<f64> = self.runs.iter().map(|r| r.seconds).collect();
+        let (mean_seconds, _) = mean_and_stddev(&seconds);
+        writeln!(f, "{}v{}, {} runs of {} iterations", self.config.dice_p1, self.config.dice_p2, self.runs.len(), self.iterations)?;
+        writeln!(f, "Exploitability: mean {:.6}, stddev {:.6}", mean, stddev)?;
+        writeln!(f, "Training time: mean {:.2}s", mean_seconds)?;
+        if !self.matches.is_empty() {
+            let scores = self.run_scores();
+            let (score_mean, score_stddev) = mean_and_stddev(&scores);
+            writeln!(f, "Head-to-head payoff per round: stddev across runs {:.4} (mean {:.4})", score_stddev, score_mean)?;
+            for m in &self.matches {
+                writeln!(f, "  run {} vs run {}: {:+.4} +/- {:.4} dice over {} rounds", m.run_a + 1, m.run_b + 1, m.mean, 1.96 * m.std_error, m.games)?;
+            }
+        }
+        for (i, (run, score)) in self.runs.iter().zip(self.run_scores()).enumerate() {
+            writeln!(f, "Run {} (seed {}): exploitability {:.6}, head-to-head {:+.4}", i + 1, run.seed, run.exploitability, score)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,314 @@
+use crate::game::{hand_distribution, Action, GameConfig, GameState, DICE_FACES};
+use crate::strategy::{action_to_str, write_atomically, Policy};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io::{self, Write};
+
+/// Exact best-response evaluation against a fixed policy.
+///
+/// Walks the full public bid tree once per best responder, carrying the
+/// opponent's reach probability for every possible opponent hand. The best
+/// responder has perfect recall of the bid sequence, so the result measures
+/// the policy's exploitability in the real game even when the policy itself
+/// was trained on a coarser info set abstraction. The public tree doubles in
+/// size with every extra bid, so this is only practical for small dice counts.
+struct BestResponse<'a, P: Policy> {
+    policy: &'a P,
+    br_player: u8,
+    br_hands: Vec<(Vec<u8>, f64)>,
+    opp_hands: Vec<(Vec<u8>, f64)>,
+}
+
+/// What a best-response walk collects besides the values.
+#[derive(Default)]
+struct Record {
+    /// The best action for every best-responder hand at every node visited,
+    /// keyed by `full_history_key`.
+    choices: Option<HashMap<String, usize>>,
+    /// (history, reach, gain) at every node where the best responder moves;
+    /// see `exploitability_by_history`.
+    gains: Option<Vec<(Vec<Action>, f64, f64)>>,
+}
+
+impl<'a, P: Policy> BestResponse<'a, P> {
+    fn new(policy: &'a P, config: &GameConfig, br_player: u8) -> Self {
+        let (br_dice, opp_dice) = if br_player == 0 {
+            (config.dice_p1, config.dice_p2)
+        } else {
+            (config.dice_p2, config.dice_p1)
+        };
+        BestResponse {
+            policy,
+            br_player,
+            br_hands: hand_distribution(br_dice, config),
+            opp_hands: hand_distribution(opp_dice, config),
+        }
+    }
+
+    fn root_value(&self, root: &GameState) -> f64 {
+        let opp_reach: Vec<f64> = self.opp_hands.iter().map(|(_, p)| *p).collect();
+        let values = self.values(root, &opp_reach, &self.root_reach(), &mut Record::default());
+        self.br_hands.iter().zip(values).map(|((_, p), v)| p * v).sum()
+    }
+
+    /// The chance of each best-responder hand, its reach before anyone acts.
+    fn root_reach(&self) -> Vec<f64> {
+        self.br_hands.iter().map(|(_, p)| *p).collect()
+    }
+
+    /// Best-response value for each best-responder hand, weighted by `opp_reach`.
+    ///
+    /// `br_reach` is how often the best responder's hands get here by
+    /// following the policy; it only matters when `record` collects gains.
+    fn values(&self, state: &GameState, opp_reach: &[f64], br_reach: &[f64], record: &mut Record) -> Vec<f64> {
+        let actions = state.get_valid_actions();
+
+        if state.current_player == self.br_player {
+            // The policy's own play for the best responder, to measure what deviating gains.
+            let own: Option<Vec<Vec<f64>>> = record.gains.is_some().then(|| {
+                self.br_hands.iter().map(|(hand, _)| self.policy.action_probabilities(&state.information_set_for(hand), &actions)).collect()
+            });
+            let mut child_values = Vec::with_capacity(actions.len());
+            for (i, action) in actions.iter().enumerate() {
+                let followed: Vec<f64>;
+                let child_reach = match &own {
+                    Some(own) => {
+                        followed = br_reach.iter().zip(own).map(|(r, p)| r * p[i]).collect();
+                        &followed
+                    }
+                    None => br_reach,
+                };
+                child_values.push(self.child_values(state, action, opp_reach, child_reach, record));
+            }
+
+            let mut best = vec![f64::NEG_INFINITY; self.br_hands.len()];
+            let mut best_action = vec![0; self.br_hands.len()];
+            for (i, child) in child_values.iter().enumerate() {
+                for ((b, &c), a) in best.iter_mut().zip(child).zip(best_action.iter_mut()) {
+                    if c > *b {
+                        *b = c;
+                        *a = i;
+                    }
+                }
+            }
+            if let Some(choices) = &mut record.choices {
+                for ((hand, _), &a) in self.br_hands.iter().zip(&best_action) {
+                    choices.insert(full_history_key(state, hand), a);
+                }
+            }
+            if let (Some(gains), Some(own)) = (&mut record.gains, &own) {
+                let gain: f64 = (0..self.br_hands.len())
+                    .map(|h| {
+                        let followed: f64 = own[h].iter().zip(&child_values).map(|(p, child)| p * child[h]).sum();
+                        br_reach[h] * (best[h] - followed)
+                    })
+                    .sum();
+                let reach = br_reach.iter().sum::<f64>() * opp_reach.iter().sum::<f64>();
+                if reach > 0.0 {
+                    gains.push((state.history.clone(), reach, gain));
+                }
+            }
+            return best;
+        }
+
+        let probs: Vec<Vec<f64>> = self
+            .opp_hands
+            .iter()
+            .map(|(hand, _)| self.policy.action_probabilities(&state.information_set_for(hand), &actions))
+            .collect();
+
+        let mut total = vec![0.0; self.br_hands.len()];
+        for (i, action) in actions.iter().enumerate() {
+            let reach: Vec<f64> = opp_reach.iter().zip(&probs).map(|(r, p)| r * p[i]).collect();
+            if reach.iter().all(|&r| r == 0.0) {
+                continue;
+            }
+            let child = self.child_values(state, action, &reach, br_reach, record);
+            for (t, c) in total.iter_mut().zip(child) {
+                *t += c;
+            }
+        }
+        total
+    }
+
+    fn child_values(&self, state: &GameState, action: &Action, opp_reach: &[f64], br_reach: &[f64], record: &mut Record) -> Vec<f64> {
+        let mut next = state.clone();
+        if next.apply_action(action.clone()) {
+            self.terminal_values(next, opp_reach)
+        } else {
+            self.values(&next, opp_reach, br_reach, record)
+        }
+    }
+
+    fn terminal_values(&self, mut state: GameState, opp_reach: &[f64]) -> Vec<f64> {
+        self.br_hands
+            .iter()
+            .map(|(br_hand, _)| {
+                let mut value = 0.0;
+                for ((opp_hand, _), &reach) in self.opp_hands.iter().zip(opp_reach) {
+                    if reach == 0.0 {
+                        continue;
+                    }
+                    if self.br_player == 0 {
+                        state.hand_p1.clone_from(br_hand);
+                        state.hand_p2.clone_from(opp_hand);
+                    } else {
+                        state.hand_p1.clone_from(opp_hand);
+                        state.hand_p2.clone_from(br_hand);
+                    }
+                    value += reach * state.payoff_for(self.br_player) as f64;
+                }
+                value
+            })
+            .collect()
+    }
+}
+
+/// Expected payoff `br_player` earns by best-responding to `policy`.
+pub fn best_response_value<P: Policy>(policy: &P, config: &GameConfig, br_player: u8) -> f64 {
+    let root = GameState::new(config);
+    BestResponse::new(policy, config, br_player).root_value(&root)
+}
+
+/// The pure best response to `policy`: the action index `br_player` picks for
+/// each hand and full bid history, keyed by `full_history_key`. Histories the
+/// policy never reaches are pruned and have no entry.
+pub fn best_response_choices<P: Policy>(policy: &P, config: &GameConfig, br_player: u8) -> HashMap<String, usize> {
+    let root = GameState::new(config);
+    let br = BestResponse::new(policy, config, br_player);
+    let opp_reach: Vec<f64> = br.opp_hands.iter().map(|(_, p)| *p).collect();
+    let mut record = Record { choices: Some(HashMap::new()), ..Record::default() };
+    br.values(&root, &opp_reach, &br.root_reach(), &mut record);
+    record.choices.expect("the walk was asked for choices")
+}
+
+/// A perfect-recall key for the player to move holding `hand`: the hand and every bid so far.
+pub fn full_history_key(state: &GameState, hand: &[u8]) -> String {
+    let hand_str: String = hand.iter().map(|d| d.to_string()).collect();
+    let bids: Vec<String> = state.history.iter().map(|a| a.to_string()).collect();
+    format!("{}|{}", hand_str, bids.join("/"))
+}
+
+/// Average gain of the two best responses; zero exactly at a Nash equilibrium.
+/// Averaged over the possible openers, which the best responses can tell
+/// apart as the policy can.
+pub fn exploitability<P: Policy>(policy: &P, config: &GameConfig) -> f64 {
+    config
+        .openings()
+        .iter()
+        .map(|(round, p)| {
+            let br0 = best_response_value(policy, round, 0);
+            let br1 = best_response_value(policy, round, 1);
+            p * (br0 + br1) / 2.0
+        })
+        .sum()
+}
+
+/// The exploitability attributed to one public state: the opening and the
+/// bids so far.
+pub struct StateGain {
+    pub dice_p1: u8,
+    pub dice_p2: u8,
+    pub history: Vec<Action>,
+    /// Probability that play under the policy reaches the state, opening included.
+    pub reach: f64,
+    /// This state's part of the exploitability.
+    pub gain: f64,
+}
+
+/// Splits `exploitability` over the public states. A state's gain is what the
+/// player to move wins by best-responding there instead of following the
+/// policy (and best-responding afterwards either way), weighted by how often
+/// their own play under the policy reaches it. Deviations telescope, so the
+/// gains add up to the exploitability exactly: the states with the largest
+/// gains are where the policy is most exploitable. Largest first; states
+/// play never reaches are left out.
+pub fn exploitability_by_history<P: Policy>(policy: &P, config: &GameConfig) -> Vec<StateGain> {
+    let mut states = Vec::new();
+    for (round, p) in config.openings() {
+        let root = GameState::new(&round);
+        for br_player in 0..2 {
+            let br = BestResponse::new(policy, &round, br_player);
+            let opp_reach: Vec<f64> = br.opp_hands.iter().map(|(_, q)| *q).collect();
+            let mut record = Record { gains: Some(Vec::new()), ..Record::default() };
+            br.values(&root, &opp_reach, &br.root_reach(), &mut record);
+            // The exploitability is the mean of the two best responders' gains.
+            states.extend(record.gains.expect("the walk was asked for gains").into_iter().map(|(history, reach, gain)| StateGain {
+                dice_p1: round.dice_p1,
+                dice_p2: round.dice_p2,
+                history,
+                reach: p * reach,
+                gain: p * gain / 2.0,
+            }));
+        }
+    }
+    states.sort_by(|a, b| b.gain.total_cmp(&a.gain));
+    states
+}
+
+/// The gains by the bid the player to move faces, `None` before the first
+/// bid, for a heatmap over (quantity, face).
+pub fn gains_by_bid(states: &[StateGain]) -> BTreeMap<Option<(u8, u8)>, f64> {
+    let mut by_bid = BTreeMap::new();
+    for state in states {
+        let bid = match state.history.last() {
+            Some(Action::Bid(q, f)) => Some((*q, *f)),
+            _ => None,
+        };
+        *by_bid.entry(bid).or_insert(0.0) += state.gain;
+    }
+    by_bid
+}
+
+/// Writes one row per state: `Dice,History,Depth,Reach,Gain,Share`, largest gain first.
+pub fn write_gains(path: &str, states: &[StateGain]) -> io::Result<()> {
+    let total: f64 = states.iter().map(|s| s.gain).sum();
+    write_atomically(path, |file| {
+        writeln!(file, "Dice,History,Depth,Reach,Gain,Share")?;
+        for state in states {
+            let history: Vec<String> = state.history.iter().map(action_to_str).collect();
+            let share = state.gain / total.max(f64::MIN_POSITIVE);
+            writeln!(file, "{}v{},{},{},{},{},{}", state.dice_p1, state.dice_p2, history.join("/"), state.history.len(), state.reach, state.gain, share)?;
+        }
+        Ok(())
+    })
+}
+
+/// The most exploitable states and a heatmap of the gains by the bid faced.
+pub struct GainReport<'a> {
+    pub states: &'a [StateGain],
+    pub top: usize,
+}
+
+impl fmt::Display for GainReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let total: f64 = self.states.iter().map(|s| s.gain).sum();
+        let share = |gain: f64| gain / total.max(f64::MIN_POSITIVE) * 100.0;
+        writeln!(f, "Exploitability {:.6}, by public state (most exploitable first):", total)?;
+        writeln!(f, "Dice  Share      Gain   Reach  History")?;
+        for state in self.states.iter().take(self.top) {
+            let history: Vec<String> = state.history.iter().map(action_to_str).collect();
+            let history = if history.is_empty() { "(opening bid)".to_string() } else { history.join("/") };
+            writeln!(f, "{}v{} {:5.1}% {:9.6} {:7.4}  {}", state.dice_p1, state.dice_p2, share(state.gain), state.gain, state.reach, history)?;
+        }
+
+        let by_bid = gains_by_bid(self.states);
+        writeln!(f)?;
+        writeln!(f, "Share of exploitability by the bid faced (quantity x face):")?;
+        writeln!(f, "Qty  {}", (1..=DICE_FACES).map(|face| format!("{:>6}", face)).collect::<String>())?;
+        let max_quantity = by_bid.keys().flatten().map(|&(q, _)| q).max().unwrap_or(0);
+        for q in 1..=max_quantity {
+            let row: String = (1..=DICE_FACES)
+                .map(|face| match by_bid.get(&Some((q, face))) {
+                    Some(&gain) => format!("{:>5.1}%", share(gain)),
+                    None => format!("{:>6}", "."),
+                })
+                .collect();
+            writeln!(f, "{:>3}  {}", q, row)?;
+        }
+        if let Some(&gain) = by_bid.get(&None) {
+            writeln!(f, "Opening bid: {:.1}%", share(gain))?;
+        }
+        Ok(())
+    }
+}
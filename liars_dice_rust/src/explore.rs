@@ -0,0 +1,292 @@
+use crate::game::{hand_distribution, Action, GameConfig, GameState};
+use crate::strategy::{parse_action, write_atomically, Policy};
+use std::io::{self, BufRead, Write};
+
+/// Actions below this share of the mover's play are left out of listings.
+const SHOWN_PROBABILITY: f64 = 0.005;
+
+/// A public node the explorer has been to, with each player's reach for
+/// every hand they could hold.
+struct Node {
+    state: GameState,
+    reach: [Vec<f64>; 2],
+}
+
+/// A node saved for later, as its opening, bids and a note.
+pub struct Bookmark {
+    pub dice: (u8, u8),
+    pub history: Vec<Action>,
+    pub note: String,
+}
+
+impl Bookmark {
+    fn history_str(&self) -> String {
+        let bids: Vec<String> = self.history.iter().map(|a| a.to_string()).collect();
+        bids.join("/")
+    }
+}
+
+/// Reads bookmarks written by `write_bookmarks`; a missing file has none.
+pub fn read_bookmarks(path: &str) -> io::Result<Vec<Bookmark>> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let invalid = |line: &str| io::Error::new(io::ErrorKind::InvalidData, format!("unreadable bookmark '{}'", line));
+    text.lines()
+        .skip(1)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let mut fields = line.splitn(3, ',');
+            let (dice, history, note) = (fields.next().unwrap_or(""), fields.next().unwrap_or(""), fields.next().unwrap_or(""));
+            let (p1, p2) = dice.split_once('v').ok_or_else(|| invalid(line))?;
+            let history = history
+                .split('/')
+                .filter(|a| !a.is_empty())
+                .map(parse_action)
+                .collect::<Option<Vec<Action>>>()
+                .ok_or_else(|| invalid(line))?;
+            Ok(Bookmark {
+                dice: (p1.parse().map_err(|_| invalid(line))?, p2.parse().map_err(|_| invalid(line))?),
+                history,
+                note: note.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Writes bookmarks as `Dice,History,Note` rows, the note last so it may hold commas.
+pub fn write_bookmarks(path: &str, bookmarks: &[Bookmark]) -> io::Result<()> {
+    write_atomically(path, |file| {
+        writeln!(file, "Dice,History,Note")?;
+        for b in bookmarks {
+            writeln!(file, "{}v{},{},{}", b.dice.0, b.dice.1, b.history_str(), b.note)?;
+        }
+        Ok(())
+    })
+}
+
+/// Walks a strategy's public bid tree one node at a time.
+pub struct Explorer<'a, P> {
+    policy: &'a P,
+    openings: Vec<(GameConfig, f64)>,
+    /// Each player's possible hands in the current opening.
+    hands: [Vec<(Vec<u8>, f64)>; 2],
+    /// The nodes from the opening's root down to the current one.
+    path: Vec<Node>,
+}
+
+impl<'a, P: Policy> Explorer<'a, P> {
+    pub fn new(policy: &'a P, rules: &GameConfig) -> Self {
+        let mut explorer = Explorer { policy, openings: rules.openings(), hands: [Vec::new(), Vec::new()], path: Vec::new() };
+        explorer.open(0);
+        explorer
+    }
+
+    /// Starts over at the root of the `index`th opening.
+    fn open(&mut self, index: usize) {
+        let (round, p) = &self.openings[index];
+        self.hands = [hand_distribution(round.dice_p1, round), hand_distribution(round.dice_p2, round)];
+        let reach = [self.hands[0].iter().map(|(_, q)| p * q).collect(), self.hands[1].iter().map(|(_, q)| *q).collect()];
+        self.path = vec![Node { state: GameState::new(round), reach }];
+    }
+
+    fn current(&self) -> &Node {
+        self.path.last().expect("the path always holds the root")
+    }
+
+    /// The mover's probability of each action, for each of their hands.
+    fn hand_probabilities(&self) -> (Vec<Action>, Vec<Vec<f64>>) {
+        let state = &self.current().state;
+        let actions = state.get_valid_actions();
+        let probs = self.hands[state.current_player as usize]
+            .iter()
+            .map(|(hand, _)| self.policy.action_probabilities_for(state.current_player, &state.information_set_for(hand), &actions))
+            .collect();
+        (actions, probs)
+    }
+
+    /// Plays `action` from the current node. Fails if it is not legal here or
+    /// it ends the round, since there is nothing to explore past a call.
+    fn descend(&mut self, action: &Action) -> Result<(), String> {
+        let (actions, probs) = self.hand_probabilities();
+        let i = actions.iter().position(|a| a == action).ok_or_else(|| format!("{} is not legal here", action))?;
+        let node = self.current();
+        let player = node.state.current_player as usize;
+        let mut state = node.state.clone();
+        if state.apply_action(action.clone()) {
+            return Err(format!("{} ends the round", action));
+        }
+        let mut reach = node.reach.clone();
+        reach[player] = node.reach[player].iter().zip(&probs).map(|(r, p)| r * p[i]).collect();
+        self.path.push(Node { state, reach });
+        Ok(())
+    }
+
+    fn bookmark(&self, note: &str) -> Bookmark {
+        let state = &self.current().state;
+        Bookmark { dice: (state.dice_p1, state.dice_p2), history: state.history.clone(), note: note.to_string() }
+    }
+
+    /// Returns to a bookmarked node, replaying its bids.
+    fn goto(&mut self, bookmark: &Bookmark) -> Result<(), String> {
+        let index = self
+            .openings
+            .iter()
+            .position(|(round, _)| (round.dice_p1, round.dice_p2) == bookmark.dice)
+            .ok_or_else(|| format!("the rules have no {}v{} opening", bookmark.dice.0, bookmark.dice.1))?;
+        self.open(index);
+        for action in &bookmark.history {
+            self.descend(action)?;
+        }
+        Ok(())
+    }
+
+    /// Prints the node: how likely play is to get here, the mover's play
+    /// over all their hands, and with `per_hand` each hand's own mix.
+    fn show(&self, per_hand: bool) {
+        let node = self.current();
+        let state = &node.state;
+        let player = state.current_player as usize;
+        let own_reach: f64 = node.reach[player].iter().sum();
+        let opp_reach: f64 = node.reach[1 - player].iter().sum();
+        let history: Vec<String> = state.history.iter().map(|a| a.to_string()).collect();
+        println!();
+        println!(
+            "{}v{} | bids: {} | player {} to move | reach {:.4}",
+            state.dice_p1,
+            state.dice_p2,
+            if history.is_empty() { "none".to_string() } else { history.join(" ") },
+            player + 1,
+            own_reach * opp_reach
+        );
+        if own_reach <= 0.0 {
+            println!("The strategy never plays into this node.");
+            return;
+        }
+
+        let (actions, probs) = self.hand_probabilities();
+        let marginal: Vec<f64> =
+            (0..actions.len()).map(|i| node.reach[player].iter().zip(&probs).map(|(r, p)| r * p[i]).sum::<f64>() / own_reach).collect();
+        println!("Overall: {}", format_mix(&actions, &marginal));
+        if per_hand {
+            println!("{:>8} {:>8}  Mix", "Hand", "P(hand)");
+            for (((hand, _), r), p) in self.hands[player].iter().zip(&node.reach[player]).zip(&probs) {
+                if *r > 0.0 {
+                    let hand: String = hand.iter().map(|d| d.to_string()).collect();
+                    println!("{:>8} {:>7.2}%  {}", hand, r / own_reach * 100.0, format_mix(&actions, p));
+                }
+            }
+        }
+    }
+}
+
+fn format_mix(actions: &[Action], probs: &[f64]) -> String {
+    let mut shown: Vec<(&Action, f64)> = actions.iter().zip(probs.iter().copied()).filter(|(_, p)| *p >= SHOWN_PROBABILITY).collect();
+    shown.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let mix: Vec<String> = shown.iter().map(|(a, p)| format!("{} {:.1}%", a, p * 100.0)).collect();
+    mix.join(", ")
+}
+
+const HELP: &str = "\
+Commands:
+  <bid>               play a bid, e.g. 2-5
+  undo                go back one bid
+  root                go back to the start of the round
+  open <n>            start over in opening n (see 'openings')
+  openings            list the rules' openings
+  hands               toggle the per-hand breakdown
+  bookmark [note]     save this node
+  bookmarks           list saved nodes
+  goto <n>            jump to saved node n
+  help, quit";
+
+/// Runs the explorer on stdin until `quit` or end of input. Bookmarks are
+/// loaded from and saved to `bookmarks_path` when one is given.
+pub fn explore_session<P: Policy>(policy: &P, rules: &GameConfig, bookmarks_path: Option<&str>) -> io::Result<()> {
+    let mut explorer = Explorer::new(policy, rules);
+    let mut bookmarks = match bookmarks_path {
+        Some(path) => read_bookmarks(path)?,
+        None => Vec::new(),
+    };
+    let mut per_hand = true;
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    println!("{}", HELP);
+
+    explorer.show(per_hand);
+    loop {
+        print!("explore> ");
+        io::stdout().flush()?;
+        let Some(line) = lines.next() else {
+            return Ok(());
+        };
+        let line = line?;
+        let (command, rest) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+        let rest = rest.trim();
+        let result = match command {
+            "" => continue,
+            "quit" | "exit" => return Ok(()),
+            "help" => {
+                println!("{}", HELP);
+                continue;
+            }
+            "undo" => {
+                if explorer.path.len() > 1 {
+                    explorer.path.pop();
+                    Ok(())
+                } else {
+                    Err("already at the root".to_string())
+                }
+            }
+            "root" => {
+                explorer.path.truncate(1);
+                Ok(())
+            }
+            "openings" => {
+                for (i, (round, p)) in explorer.openings.iter().enumerate() {
+                    println!("{}: {}v{} ({:.0}%)", i, round.dice_p1, round.dice_p2, p * 100.0);
+                }
+                continue;
+            }
+            "open" => match rest.parse::<usize>() {
+                Ok(i) if i < explorer.openings.len() => {
+                    explorer.open(i);
+                    Ok(())
+                }
+                _ => Err("no such opening".to_string()),
+            },
+            "hands" => {
+                per_hand = !per_hand;
+                Ok(())
+            }
+            "bookmark" => {
+                bookmarks.push(explorer.bookmark(rest));
+                println!("Saved as bookmark {}.", bookmarks.len() - 1);
+                if let Some(path) = bookmarks_path {
+                    write_bookmarks(path, &bookmarks)?;
+                }
+                continue;
+            }
+            "bookmarks" => {
+                for (i, b) in bookmarks.iter().enumerate() {
+                    println!("{}: {}v{} {} {}", i, b.dice.0, b.dice.1, b.history_str(), b.note);
+                }
+                continue;
+            }
+            "goto" => match rest.parse::<usize>().ok().and_then(|i| bookmarks.get(i)) {
+                Some(bookmark) => explorer.goto(bookmark),
+                None => Err("no such bookmark".to_string()),
+            },
+            _ => match parse_action(command) {
+                Some(action) => explorer.descend(&action),
+                None => Err(format!("unknown command '{}'; type help", command)),
+            },
+        };
+        match result {
+            Ok(()) => explorer.show(per_hand),
+            Err(e) => println!("{}", e),
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use crate::cfr::CFRNode;
+use crate::game::{Action, GameConfig};
+use crate::parquet::{self, Column};
+use crate::record::json_string;
+use crate::strategy::{action_to_str, format_metadata, InfoSetKey, StrategyTable};
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+/// A structured format a strategy table can be exported to besides CSV.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Parquet,
+}
+
+impl ExportFormat {
+    /// The format named by a file's extension.
+    pub fn from_path(path: &str) -> Option<Self> {
+        match path.rsplit_once('.')?.1 {
+            "json" => Some(ExportFormat::Json),
+            "parquet" => Some(ExportFormat::Parquet),
+            _ => None,
+        }
+    }
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(ExportFormat::Json),
+            "parquet" => Ok(ExportFormat::Parquet),
+            _ => Err(format!("unknown export format '{}'", s)),
+        }
+    }
+}
+
+/// Info sets in sorted order, as the CSV writer emits them.
+fn sorted_info_sets(table: &StrategyTable) -> Vec<&String> {
+    let mut info_sets: Vec<&String> = table.entries.keys().collect();
+    info_sets.sort();
+    info_sets
+}
+
+/// The metadata's key/value pairs as a JSON object, or `null`.
+fn rules_json(rules: Option<&GameConfig>) -> String {
+    match rules {
+        Some(rules) => {
+            let pairs: Vec<String> = format_metadata(rules)
+                .lines()
+                .filter_map(|line| line.split_once('='))
+                .map(|(key, value)| format!("{}:{}", json_string(key), json_string(value)))
+                .collect();
+            format!("{{{}}}", pairs.join(","))
+        }
+        None => "null".to_string(),
+    }
+}
+
+/// An info set's parquet columns: hand, current bid, earlier bids and bid count.
+fn key_columns(info_set: &str) -> io::Result<(String, String, String, i32)> {
+    let key = InfoSetKey::parse(info_set)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("unrecognized info set '{}'", info_set)))?;
+    let hand = key.hand.iter().map(|d| d.to_string()).collect();
+    let bid = key.current_bid.map_or("None".to_string(), |(q, f)| format!("{}-{}", q, f));
+    let history: Vec<String> = key.earlier_bids.iter().map(|(q, f)| format!("{}-{}", q, f)).collect();
+    Ok((hand, bid, history.join("/"), key.history_len as i32))
+}
+
+/// `{"rules": {...}, "strategy": {"<info set>": {"<action>": p, ...}, ...}}`,
+/// with the rules as the metadata's key/value pairs when they are known.
+pub fn write_json<W: Write>(out: &mut W, table: &StrategyTable, rules: Option<&GameConfig>) -> io::Result<()> {
+    write!(out, "{{\"rules\":{},\"strategy\":{{", rules_json(rules))?;
+    for (i, info_set) in sorted_info_sets(table).into_iter().enumerate() {
+        let actions: Vec<String> = table.entries[info_set]
+            .iter()
+            .map(|(action, p)| format!("{}:{}", json_string(&action_to_str(action)), p))
+            .collect();
+        let separator = if i == 0 { "" } else { "," };
+        write!(out, "{}\n{}:{{{}}}", separator, json_string(info_set), actions.join(","))?;
+    }
+    writeln!(out, "\n}}}}")
+}
+
+/// One row per (info set, action), with the info set split into columns:
+/// `hand`, the current `bid` ("None" before the first), the earlier remembered
+/// bids as `history` ("/"-separated, empty when none), `bids` (how many bids
+/// have been made, which the history abstraction may not remember), `action`
+/// and `probability`. The rules go in the file's key-value metadata.
+pub fn write_parquet<W: Write>(out: &mut W, table: &StrategyTable, rules: Option<&GameConfig>) -> io::Result<()> {
+    let (mut hands, mut bids, mut histories, mut counts, mut actions, mut probabilities) =
+        (Vec::new(), Vec::new(), Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    for info_set in sorted_info_sets(table) {
+        let (hand, bid, history, count) = key_columns(info_set)?;
+        for (action, p) in &table.entries[info_set] {
+            hands.push(hand.clone());
+            bids.push(bid.clone());
+            histories.push(history.clone());
+            counts.push(count);
+            actions.push(action_to_str(action));
+            probabilities.push(*p);
+        }
+    }
+
+    let metadata: Vec<(&str, String)> = rules.map(|r| ("liars_dice.rules", format_metadata(r))).into_iter().collect();
+    parquet::write(
+        out,
+        &[
+            ("hand", Column::Utf8(hands)),
+            ("bid", Column::Utf8(bids)),
+            ("history", Column::Utf8(histories)),
+            ("bids", Column::Int32(counts)),
+            ("action", Column::Utf8(actions)),
+            ("probability", Column::Double(probabilities)),
+        ],
+        &metadata,
+    )
+}
+
+/// Each action of `node` with its cumulative regret and strategy sum.
+fn regret_rows(node: &CFRNode) -> impl Iterator<Item = (&Action, (&f32, &f32))> {
+    node.actions.iter().zip(node.regret_sum.iter().zip(&node.strategy_sum))
+}
+
+/// Writes each node's cumulative regret and strategy sum per action, the
+/// state of the regret minimizer rather than the average strategy it yields,
+/// for studying regret dynamics outside the crate. Under CFR+ the regrets are
+/// floored at zero. Frozen nodes keep no sums and are left out.
+///
+/// - CSV: `InfoSet,Action,Regret,StrategySum,Visits`.
+/// - JSON: `{"rules": {...}, "regrets": {"<info set>": {"visits": n,
+///   "actions": {"<action>": [regret, strategy_sum], ...}}, ...}}`.
+/// - Parquet: the strategy export's key columns, then `action`, `regret`,
+///   `strategy_sum` and `visits` (as a double, since counts outgrow 32 bits).
+pub fn write_regrets<W: Write>(out: &mut W, nodes: &HashMap<String, CFRNode>, format: Option<ExportFormat>, rules: Option<&GameConfig>) -> io::Result<()> {
+    let mut info_sets: Vec<&String> = nodes.keys().filter(|key| !nodes[*key].is_frozen()).collect();
+    info_sets.sort();
+
+    match format {
+        None => {
+            writeln!(out, "InfoSet,Action,Regret,StrategySum,Visits")?;
+            for info_set in info_sets {
+                let node = &nodes[info_set];
+                for (action, (regret, sum)) in regret_rows(node) {
+                    writeln!(out, "{},{},{},{},{}", info_set, action_to_str(action), regret, sum, node.visits)?;
+                }
+            }
+            Ok(())
+        }
+        Some(ExportFormat::Json) => {
+            write!(out, "{{\"rules\":{},\"regrets\":{{", rules_json(rules))?;
+            for (i, info_set) in info_sets.into_iter().enumerate() {
+                let node = &nodes[info_set];
+                let actions: Vec<String> = regret_rows(node)
+                    .map(|(action, (regret, sum))| format!("{}:[{},{}]", json_string(&action_to_str(action)), regret, sum))
+                    .collect();
+                let separator = if i == 0 { "" } else { "," };
+                write!(out, "{}\n{}:{{\"visits\":{},\"actions\":{{{}}}}}", separator, json_string(info_set), node.visits, actions.join(","))?;
+            }
+            writeln!(out, "\n}}}}")
+        }
+        Some(ExportFormat::Parquet) => {
+            let (mut hands, mut bids, mut histories, mut counts) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+            let (mut actions, mut regrets, mut sums, mut visits) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+            for info_set in info_sets {
+                let (hand, bid, history, count) = key_columns(info_set)?;
+                let node = &nodes[info_set];
+                for (action, (regret, sum)) in regret_rows(node) {
+                    hands.push(hand.clone());
+                    bids.push(bid.clone());
+                    histories.push(history.clone());
+                    counts.push(count);
+                    actions.push(action_to_str(action));
+                    regrets.push(*regret as f64);
+                    sums.push(*sum as f64);
+                    visits.push(node.visits as f64);
+                }
+            }
+            let metadata: Vec<(&str, String)> = rules.map(|r| ("liars_dice.rules", format_metadata(r))).into_iter().collect();
+            parquet::write(
+                out,
+                &[
+                    ("hand", Column::Utf8(hands)),
+                    ("bid", Column::Utf8(bids)),
+                    ("history", Column::Utf8(histories)),
+                    ("bids", Column::Int32(counts)),
+                    ("action", Column::Utf8(actions)),
+                    ("regret", Column::Double(regrets)),
+                    ("strategy_sum", Column::Double(sums)),
+                    ("visits", Column::Double(visits)),
+                ],
+                &metadata,
+            )
+        }
+    }
+}
@@ -1,12 +1,596 @@
 use rand::Rng;
-use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
 
 pub const DICE_FACES: u8 = 6;
 
+/// Which bids may follow the current one; households disagree on this.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BidRules {
+    /// Raise the face at the same quantity, or raise the quantity with any face.
+    #[default]
+    Standard,
+    /// Every bid must raise the quantity; the face is free.
+    QuantityOnly,
+    /// As standard, but raising the quantity may not lower the face.
+    NoFaceReset,
+    /// Perudo ladder: moving onto ones needs at least half the quantity
+    /// (rounded up), moving off ones needs more than double it.
+    AcesLadder,
+}
+
+impl BidRules {
+    /// Whether `next` is a legal bid on top of `current`.
+    pub fn is_raise(self, current: (u8, u8), next: (u8, u8)) -> bool {
+        let ((cq, cf), (nq, nf)) = (current, next);
+        match self {
+            BidRules::Standard => (nq, nf) > (cq, cf),
+            BidRules::QuantityOnly => nq > cq,
+            BidRules::NoFaceReset => (nq == cq && nf > cf) || (nq > cq && nf >= cf),
+            BidRules::AcesLadder => match (cf == 1, nf == 1) {
+                (false, false) => (nq, nf) > (cq, cf),
+                (true, true) => nq > cq,
+                (false, true) => nq >= cq.div_ceil(2),
+                (true, false) => nq > cq * 2,
+            },
+        }
+    }
+}
+
+impl fmt::Display for BidRules {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            BidRules::Standard => "standard",
+            BidRules::QuantityOnly => "quantity-only",
+            BidRules::NoFaceReset => "no-face-reset",
+            BidRules::AcesLadder => "aces-ladder",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for BidRules {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "standard" => Ok(BidRules::Standard),
+            "quantity-only" => Ok(BidRules::QuantityOnly),
+            "no-face-reset" => Ok(BidRules::NoFaceReset),
+            "aces-ladder" => Ok(BidRules::AcesLadder),
+            _ => Err(format!("unknown bid rules '{}'", s)),
+        }
+    }
+}
+
+/// Highest quantity anyone may bid; implausibly high bids dominate the branching factor.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum QuantityCap {
+    /// Up to the total number of dice in play.
+    #[default]
+    Total,
+    /// A fixed maximum, clamped to the total.
+    Fixed(u8),
+    /// Half the total (rounded down) plus a margin, clamped to the total.
+    HalfPlus(u8),
+}
+
+impl QuantityCap {
+    pub fn max_quantity(self, total_dice: u8) -> u8 {
+        match self {
+            QuantityCap::Total => total_dice,
+            QuantityCap::Fixed(q) => q.min(total_dice),
+            QuantityCap::HalfPlus(k) => (total_dice / 2).saturating_add(k).min(total_dice),
+        }
+    }
+}
+
+impl fmt::Display for QuantityCap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QuantityCap::Total => write!(f, "total"),
+            QuantityCap::Fixed(q) => write!(f, "{}", q),
+            QuantityCap::HalfPlus(k) => write!(f, "half+{}", k),
+        }
+    }
+}
+
+impl FromStr for QuantityCap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid quantity cap '{}'", s);
+        if s == "total" {
+            Ok(QuantityCap::Total)
+        } else if let Some(k) = s.strip_prefix("half+") {
+            k.parse().map(QuantityCap::HalfPlus).map_err(|_| invalid())
+        } else {
+            s.parse().map(QuantityCap::Fixed).map_err(|_| invalid())
+        }
+    }
+}
+
+/// How much of the bid sequence an info set remembers, trading memory for solution quality.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HistoryAbstraction {
+    /// The last k bids plus the number of bids so far; k = 1 is the original encoding.
+    LastBids(usize),
+    /// The whole bid sequence (perfect recall).
+    Full,
+    /// The current bid and the number of bids so far, plus features of how
+    /// the bidding got there: how many bids raised the quantity, the
+    /// quantity jump of the current bid, and whether it switched face.
+    Pressure,
+}
+
+impl Default for HistoryAbstraction {
+    fn default() -> Self {
+        HistoryAbstraction::LastBids(1)
+    }
+}
+
+impl fmt::Display for HistoryAbstraction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HistoryAbstraction::LastBids(k) => write!(f, "last:{}", k),
+            HistoryAbstraction::Full => write!(f, "full"),
+            HistoryAbstraction::Pressure => write!(f, "pressure"),
+        }
+    }
+}
+
+impl FromStr for HistoryAbstraction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "length" => Ok(HistoryAbstraction::LastBids(1)),
+            "full" => Ok(HistoryAbstraction::Full),
+            "pressure" => Ok(HistoryAbstraction::Pressure),
+            _ => s
+                .strip_prefix("last:")
+                .and_then(|k| k.parse().ok())
+                .filter(|&k| k >= 1)
+                .map(HistoryAbstraction::LastBids)
+                .ok_or_else(|| format!("invalid history abstraction '{}'", s)),
+        }
+    }
+}
+
+/// How many dice the loser of a challenge gives up, which is what payoffs measure.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DiceLoss {
+    /// Always one die, so payoffs are +-1 (the original model).
+    #[default]
+    One,
+    /// The distance between the bid and the true count, at least one and
+    /// at most the loser's remaining dice.
+    Difference,
+}
+
+impl fmt::Display for DiceLoss {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DiceLoss::One => write!(f, "one"),
+            DiceLoss::Difference => write!(f, "difference"),
+        }
+    }
+}
+
+impl FromStr for DiceLoss {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "one" => Ok(DiceLoss::One),
+            "difference" => Ok(DiceLoss::Difference),
+            _ => Err(format!("unknown dice loss model '{}'", s)),
+        }
+    }
+}
+
+/// How training values a round's payoff in dice.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Utility {
+    /// Dice won or lost as they are: strategies maximize expected dice.
+    #[default]
+    Linear,
+    /// Exponential utility (1 - e^(-a x)) / a with risk aversion `a`, so a
+    /// die lost costs more than a die won is worth and strategies trade
+    /// some EV for survival.
+    RiskAverse(f32),
+}
+
+impl Utility {
+    pub fn apply(self, payoff: f32) -> f32 {
+        match self {
+            Utility::Linear => payoff,
+            Utility::RiskAverse(a) => (1.0 - (-a * payoff).exp()) / a,
+        }
+    }
+}
+
+impl fmt::Display for Utility {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Utility::Linear => write!(f, "linear"),
+            Utility::RiskAverse(a) => write!(f, "risk:{}", a),
+        }
+    }
+}
+
+impl FromStr for Utility {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "linear" => Ok(Utility::Linear),
+            _ => s
+                .strip_prefix("risk:")
+                .and_then(|a| a.parse::<f32>().ok())
+                .filter(|&a| a > 0.0)
+                .map(Utility::RiskAverse)
+                .ok_or_else(|| format!("invalid utility '{}'", s)),
+        }
+    }
+}
+
+/// Who makes the first bid. Player 0 of a `GameState` always opens, so a
+/// round the second seat opens is played with the dice counts swapped; info
+/// sets tell the positions apart by hand size and bid parity, and merge them
+/// only when both seats hold the same number of dice and are interchangeable.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Opener {
+    /// The seat holding `dice_p1` (the original model).
+    #[default]
+    First,
+    /// The seat holding `dice_p2`.
+    Second,
+    /// Each seat opens every other deal.
+    Alternate,
+    /// A fair coin decides before each deal.
+    Random,
+}
+
+impl fmt::Display for Opener {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Opener::First => "first",
+            Opener::Second => "second",
+            Opener::Alternate => "alternate",
+            Opener::Random => "random",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Opener {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "first" => Ok(Opener::First),
+            "second" => Ok(Opener::Second),
+            "alternate" => Ok(Opener::Alternate),
+            "random" => Ok(Opener::Random),
+            _ => Err(format!("unknown opener '{}'", s)),
+        }
+    }
+}
+
+/// Rules that differ between the two players, as handicaps. Indexed by
+/// player, like `dice_p1` and `dice_p2`: entry 0 is the player holding
+/// `dice_p1`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Handicaps {
+    /// Players barred from calling Calza where the variant has it.
+    pub no_calza: [bool; 2],
+    /// Dice of the opponent's hand each player sees, chosen at random in the deal.
+    pub peek: [u8; 2],
+}
+
+impl Handicaps {
+    /// Whether a player sees any of the other's dice, so their info sets
+    /// depend on more than their own hand and the bids.
+    pub fn has_peek(&self) -> bool {
+        self.peek != [0, 0]
+    }
+
+    /// The same handicaps with the players' roles exchanged.
+    pub fn swapped(self) -> Self {
+        Handicaps { no_calza: [self.no_calza[1], self.no_calza[0]], peek: [self.peek[1], self.peek[0]] }
+    }
+}
+
+/// `none`, or a comma-separated list of `<player>:no-calza` and
+/// `<player>:peek:<n>`, players numbered 1 and 2.
+impl fmt::Display for Handicaps {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut parts = Vec::new();
+        for player in 0..2 {
+            if self.no_calza[player] {
+                parts.push(format!("{}:no-calza", player + 1));
+            }
+            if self.peek[player] > 0 {
+                parts.push(format!("{}:peek:{}", player + 1, self.peek[player]));
+            }
+        }
+        if parts.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", parts.join(","))
+        }
+    }
+}
+
+impl FromStr for Handicaps {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut handicaps = Handicaps::default();
+        if s == "none" {
+            return Ok(handicaps);
+        }
+        for part in s.split(',') {
+            let invalid = || format!("unknown handicap '{}' (expected <player>:no-calza or <player>:peek:<n>)", part);
+            let (player, rule) = part.trim().split_once(':').ok_or_else(invalid)?;
+            let player = match player {
+                "1" => 0,
+                "2" => 1,
+                _ => return Err(invalid()),
+            };
+            match rule.split_once(':') {
+                None if rule == "no-calza" => handicaps.no_calza[player] = true,
+                Some(("peek", n)) => handicaps.peek[player] = n.parse().map_err(|_| invalid())?,
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(handicaps)
+    }
+}
+
+/// Public histories whose info sets remember one bid more than the history
+/// abstraction keeps, where a coarse abstraction merged histories that call
+/// for different play (see `refine`). Each entry is what an info set
+/// remembers of the bidding, as in its key without the hand: `2-3/3-5|4` is
+/// the remembered bids and the bid count. A refined history can be split
+/// again, one bid further back each time.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Refinement {
+    splits: HashSet<String>,
+}
+
+impl Refinement {
+    /// The entry for remembered `bids` after `history_len` actions.
+    pub fn public_key<I: Iterator<Item = String>>(bids: I, history_len: usize) -> String {
+        let bids: Vec<String> = bids.collect();
+        let bid_str = if bids.is_empty() { "None".to_string() } else { bids.join("/") };
+        format!("{}|{}", bid_str, history_len)
+    }
+
+    /// Whether info sets remembering `bids` after `history_len` actions look one bid further back.
+    pub fn splits(&self, bids: &[Action], history_len: usize) -> bool {
+        self.contains(&Self::public_key(bids.iter().map(|a| a.to_string()), history_len))
+    }
+
+    pub fn contains(&self, public_key: &str) -> bool {
+        self.splits.contains(public_key)
+    }
+
+    /// Adds a split; false if `public_key` was already split.
+    pub fn insert(&mut self, public_key: String) -> bool {
+        self.splits.insert(public_key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.splits.len()
+    }
+}
+
+impl fmt::Display for Refinement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut splits: Vec<&String> = self.splits.iter().collect();
+        splits.sort();
+        let splits: Vec<&str> = splits.into_iter().map(|s| s.as_str()).collect();
+        write!(f, "{}", splits.join(","))
+    }
+}
+
+impl FromStr for Refinement {
+    type Err = String;
+
+    /// Parses `bids|count` entries separated by commas, as `Display` writes
+    /// them, or by whitespace, as in a refinement file.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut refinement = Refinement::default();
+        for entry in s.split(|c: char| c == ',' || c.is_whitespace()).filter(|e| !e.is_empty()) {
+            let (bids, count) = entry.split_once('|').ok_or_else(|| format!("invalid refinement entry '{}'", entry))?;
+            let well_formed = count.parse::<usize>().is_ok() && (bids == "None" || bids.split('/').all(|b| matches!(crate::strategy::parse_action(b), Some(Action::Bid(..)))));
+            if !well_formed {
+                return Err(format!("invalid refinement entry '{}'", entry));
+            }
+            refinement.insert(entry.to_string());
+        }
+        Ok(refinement)
+    }
+}
+
+/// Rules and chance model for one game configuration.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameConfig {
+    pub dice_p1: u8,
+    pub dice_p2: u8,
+    /// Probability of rolling each face, indexed by face - 1; always sums to 1.
+    pub face_weights: [f64; DICE_FACES as usize],
+    pub bid_rules: BidRules,
+    pub quantity_cap: QuantityCap,
+    /// Only every `quantity_step`-th quantity (1, 1 + step, ...) may be bid.
+    pub quantity_step: u8,
+    pub history_abstraction: HistoryAbstraction,
+    pub dice_loss: DiceLoss,
+    /// Dice gained by a correct Calza call; `None` disables the Calza action.
+    pub calza_reward: Option<f32>,
+    /// Team play: dice per seat in turn order (A1, B1, A2, B2). Partners see
+    /// each other's dice, so each team acts as one player holding the pooled hand.
+    pub seat_dice: Option<[u8; 4]>,
+    pub opener: Opener,
+    /// What training maximizes; payoffs reported elsewhere stay in dice.
+    pub utility: Utility,
+    pub handicaps: Handicaps,
+    /// Histories remembered further back than `history_abstraction` says.
+    pub refinement: Option<Arc<Refinement>>,
+}
+
+impl GameConfig {
+    pub fn new(dice_p1: u8, dice_p2: u8) -> Self {
+        GameConfig {
+            dice_p1,
+            dice_p2,
+            face_weights: [1.0 / DICE_FACES as f64; DICE_FACES as usize],
+            bid_rules: BidRules::Standard,
+            quantity_cap: QuantityCap::Total,
+            quantity_step: 1,
+            history_abstraction: HistoryAbstraction::default(),
+            dice_loss: DiceLoss::One,
+            calza_reward: None,
+            seat_dice: None,
+            opener: Opener::First,
+            utility: Utility::Linear,
+            handicaps: Handicaps::default(),
+            refinement: None,
+        }
+    }
+
+    /// The rules for a round with player 0 opening: as given when the first
+    /// seat opens, with the dice counts and handicaps swapped when the second
+    /// seat does.
+    pub fn opening(&self, second_seat_opens: bool) -> GameConfig {
+        let mut round = self.clone();
+        if second_seat_opens {
+            std::mem::swap(&mut round.dice_p1, &mut round.dice_p2);
+            round.handicaps = round.handicaps.swapped();
+        }
+        round.opener = Opener::First;
+        round
+    }
+
+    /// Every round configuration a deal can start in, with its probability.
+    /// Alternating openers count as an even split, as they are in the long run.
+    pub fn openings(&self) -> Vec<(GameConfig, f64)> {
+        match self.opener {
+            Opener::First => vec![(self.opening(false), 1.0)],
+            Opener::Second => vec![(self.opening(true), 1.0)],
+            // With equal dice and rules the seats are interchangeable, so both openings are the same game.
+            _ if self.dice_p1 == self.dice_p2 && self.handicaps == self.handicaps.swapped() => vec![(self.opening(false), 1.0)],
+            _ => vec![(self.opening(false), 0.5), (self.opening(true), 0.5)],
+        }
+    }
+
+    /// Two teams of two, seated alternately; player 0 is team A and player 1 team B.
+    pub fn with_teams(mut self, seat_dice: [u8; 4]) -> Self {
+        self.dice_p1 = seat_dice[0] + seat_dice[2];
+        self.dice_p2 = seat_dice[1] + seat_dice[3];
+        self.seat_dice = Some(seat_dice);
+        self
+    }
+
+    pub fn max_quantity(&self) -> u8 {
+        self.quantity_cap.max_quantity(self.dice_p1 + self.dice_p2)
+    }
+
+    pub fn quantity_allowed(&self, q: u8) -> bool {
+        q >= 1 && q <= self.max_quantity() && (q - 1).is_multiple_of(self.quantity_step)
+    }
+
+    /// Uses loaded dice; `weights` are relative and get normalized.
+    pub fn with_face_weights(mut self, weights: [f64; DICE_FACES as usize]) -> Self {
+        let total: f64 = weights.iter().sum();
+        assert!(total > 0.0 && weights.iter().all(|&w| w >= 0.0), "face weights must be non-negative and not all zero");
+        self.face_weights = weights.map(|w| w / total);
+        self
+    }
+
+    /// Fails with a message naming `what` when a player peeks: tools that
+    /// enumerate a player's possible hands to build their info sets cannot
+    /// also know which of the opponent's dice they see.
+    pub fn require_private_hands(&self, what: &str) -> Result<(), String> {
+        match self.handicaps.has_peek() {
+            true => Err(format!("{} needs info sets that depend only on the player's own dice; it does not support peek handicaps", what)),
+            false => Ok(()),
+        }
+    }
+
+    pub fn roll_face<R: Rng>(&self, rng: &mut R) -> u8 {
+        let mut x: f64 = rng.gen();
+        for (i, w) in self.face_weights.iter().enumerate() {
+            if x < *w {
+                return i as u8 + 1;
+            }
+            x -= w;
+        }
+        DICE_FACES
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Action {
     Bid(u8, u8), // Quantity, Face
     Challenge,
+    Calza, // The current bid is exactly right
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Action::Bid(q, face) => write!(f, "{}-{}", q, face),
+            Action::Challenge => write!(f, "Challenge"),
+            Action::Calza => write!(f, "Calza"),
+        }
+    }
+}
+
+/// Why `GameState::try_apply` refused an action.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IllegalAction {
+    /// The round has already been called.
+    RoundOver,
+    /// A challenge or Calza before anyone has bid.
+    NothingToCall(Action),
+    /// Calza is not in the rules, or the player to move is handicapped out of it.
+    CalzaNotAllowed,
+    /// A bid on a face that is not 1 to `DICE_FACES`.
+    InvalidFace(u8),
+    /// A quantity above the cap or off the quantity step.
+    QuantityNotAllowed(u8),
+    /// A bid that does not raise the current one under the bid rules.
+    NotARaise { bid: (u8, u8), current: (u8, u8) },
+}
+
+impl fmt::Display for IllegalAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IllegalAction::RoundOver => write!(f, "the round is over"),
+            IllegalAction::NothingToCall(action) => write!(f, "{} needs a bid to call", action),
+            IllegalAction::CalzaNotAllowed => write!(f, "Calza is not allowed here"),
+            IllegalAction::InvalidFace(face) => write!(f, "there is no face {}", face),
+            IllegalAction::QuantityNotAllowed(q) => write!(f, "a quantity of {} is not allowed", q),
+            IllegalAction::NotARaise { bid, current } => write!(f, "{}-{} does not raise {}-{}", bid.0, bid.1, current.0, current.1),
+        }
+    }
+}
+
+impl std::error::Error for IllegalAction {}
+
+/// What a legal action did to the round.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// A bid; the other player is to move.
+    Continue,
+    /// A challenge or Calza ended the round: `count` dice showed the bid
+    /// face and `winner` (0 or 1) won the call.
+    ChallengeResolved { count: u8, winner: u8 },
 }
 
 #[derive(Clone, Debug)]
@@ -18,67 +602,128 @@ pub struct GameState {
     pub current_bid: Option<(u8, u8)>,
     pub history: Vec<Action>,
     pub current_player: u8, // 0 or 1
+    pub bid_rules: BidRules,
+    pub quantity_cap: QuantityCap,
+    pub quantity_step: u8,
+    pub history_abstraction: HistoryAbstraction,
+    pub dice_loss: DiceLoss,
+    pub calza_reward: Option<f32>,
+    /// The challenge or Calza that ended the round, once it has been called.
+    pub final_call: Option<Action>,
+    pub seat_dice: Option<[u8; 4]>,
+    pub utility: Utility,
+    pub handicaps: Handicaps,
+    /// The dice of each player's hand that their opponent sees, sorted.
+    pub revealed: [Vec<u8>; 2],
+    pub refinement: Option<Arc<Refinement>>,
 }
 
 impl GameState {
-    pub fn new(dice_p1: u8, dice_p2: u8) -> Self {
-        let mut rng = rand::thread_rng();
-        let mut hand_p1 = Vec::with_capacity(dice_p1 as usize);
-        let mut hand_p2 = Vec::with_capacity(dice_p2 as usize);
+    pub fn new(config: &GameConfig) -> Self {
+        Self::deal(config, &mut rand::thread_rng())
+    }
 
-        for _ in 0..dice_p1 {
-            hand_p1.push(rng.gen_range(1..=DICE_FACES));
-        }
-        for _ in 0..dice_p2 {
-            hand_p2.push(rng.gen_range(1..=DICE_FACES));
-        }
-        
+    /// The opening state for a deal rolled with `rng`.
+    pub fn deal<R: Rng>(config: &GameConfig, rng: &mut R) -> Self {
+        let hand_p1 = (0..config.dice_p1).map(|_| config.roll_face(rng)).collect();
+        let hand_p2 = (0..config.dice_p2).map(|_| config.roll_face(rng)).collect();
+        Self::from_hands(config, hand_p1, hand_p2)
+    }
+
+    /// The opening state with the given hands instead of a random deal. Under
+    /// peek handicaps the opponent sees the first dice of each hand as given,
+    /// so a random deal should pass its dice in the order they were rolled.
+    pub fn from_hands(config: &GameConfig, mut hand_p1: Vec<u8>, mut hand_p2: Vec<u8>) -> Self {
+        assert!(
+            hand_p1.len() == config.dice_p1 as usize && hand_p2.len() == config.dice_p2 as usize,
+            "hands of {} and {} dice do not fit a {}v{} game",
+            hand_p1.len(), hand_p2.len(), config.dice_p1, config.dice_p2
+        );
+        let reveal = |hand: &[u8], seen_by: usize| {
+            let mut shown = hand[..(config.handicaps.peek[seen_by] as usize).min(hand.len())].to_vec();
+            shown.sort();
+            shown
+        };
+        let revealed = [reveal(&hand_p1, 1), reveal(&hand_p2, 0)];
         hand_p1.sort();
         hand_p2.sort();
 
         GameState {
-            dice_p1,
-            dice_p2,
+            dice_p1: config.dice_p1,
+            dice_p2: config.dice_p2,
             hand_p1,
             hand_p2,
             current_bid: None,
             history: Vec::new(),
             current_player: 0,
+            bid_rules: config.bid_rules,
+            quantity_cap: config.quantity_cap,
+            quantity_step: config.quantity_step,
+            history_abstraction: config.history_abstraction,
+            dice_loss: config.dice_loss,
+            calza_reward: config.calza_reward,
+            final_call: None,
+            seat_dice: config.seat_dice,
+            utility: config.utility,
+            handicaps: config.handicaps,
+            revealed,
+            refinement: config.refinement.clone(),
         }
     }
 
-    pub fn get_valid_actions(&self) -> Vec<Action> {
-        let mut actions = Vec::new();
-        let total_dice = self.dice_p1 + self.dice_p2;
+    /// The same deal with the players' roles exchanged, for the round where
+    /// the other seat opens under `config` (see `GameConfig::opening`). Unlike
+    /// `from_hands` it keeps the dice each player has revealed.
+    pub fn with_seats_swapped(&self, config: &GameConfig) -> Self {
+        let mut swapped = Self::from_hands(config, self.hand_p2.clone(), self.hand_p1.clone());
+        swapped.revealed = [self.revealed[1].clone(), self.revealed[0].clone()];
+        swapped
+    }
 
-        if let Some((curr_q, curr_f)) = self.current_bid {
-            // 1. Challenge
-            actions.push(Action::Challenge);
+    /// The state after playing `history` from a deal of the given hands. The
+    /// history may end with the final call; every action must be legal.
+    pub fn from_history(config: &GameConfig, hand_p1: Vec<u8>, hand_p2: Vec<u8>, history: &[Action]) -> Result<Self, String> {
+        let mut state = Self::from_hands(config, hand_p1, hand_p2);
+        for (i, action) in history.iter().enumerate() {
+            state.try_apply(action.clone()).map_err(|e| format!("action {} ({}) is not legal: {}", i + 1, action, e))?;
+        }
+        Ok(state)
+    }
 
-            // 2. Raise Face
-            for f in (curr_f + 1)..=DICE_FACES {
-                actions.push(Action::Bid(curr_q, f));
-            }
+    pub fn get_valid_actions(&self) -> Vec<Action> {
+        self.valid_actions_iter().collect()
+    }
 
-            // 3. Raise Quantity
-            for q in (curr_q + 1)..=total_dice {
-                for f in 1..=DICE_FACES {
-                    actions.push(Action::Bid(q, f));
-                }
-            }
-        } else {
-            // First bid
-            for q in 1..=total_dice {
-                for f in 1..=DICE_FACES {
-                    actions.push(Action::Bid(q, f));
-                }
-            }
-        }
-        actions
+    /// The legal actions in `get_valid_actions` order, without allocating:
+    /// the calls (Challenge, then Calza when the variant allows it) once there
+    /// is a bid, then every bid the rule set allows, in (quantity, face) order.
+    /// Nothing is legal once the round has been called.
+    pub fn valid_actions_iter(&self) -> impl Iterator<Item = Action> + '_ {
+        let open = self.final_call.is_none();
+        let calza = self.calza_reward.is_some() && !self.handicaps.no_calza[self.current_player as usize];
+        let calls: &[Action] = match (self.current_bid, calza) {
+            _ if !open => &[],
+            (None, _) => &[],
+            (Some(_), false) => &[Action::Challenge],
+            (Some(_), true) => &[Action::Challenge, Action::Calza],
+        };
+        let max_quantity = if open { self.quantity_cap.max_quantity(self.dice_p1 + self.dice_p2) } else { 0 };
+        let bids = (1..=max_quantity)
+            .step_by(self.quantity_step as usize)
+            .flat_map(|q| (1..=DICE_FACES).map(move |f| (q, f)))
+            .filter(|&bid| self.current_bid.is_none_or(|current| self.bid_rules.is_raise(current, bid)))
+            .map(|(q, f)| Action::Bid(q, f));
+        calls.iter().cloned().chain(bids)
+    }
+
+    /// Where `action` sits in `get_valid_actions`, or `None` if it is not legal here.
+    pub fn action_index(&self, action: Action) -> Option<usize> {
+        self.valid_actions_iter().position(|a| a == action)
     }
 
     pub fn apply_action(&mut self, action: Action) -> bool {
-        if action == Action::Challenge {
+        if action == Action::Challenge || action == Action::Calza {
+            self.final_call = Some(action);
             return true; // Terminal
         }
 
@@ -91,8 +736,59 @@ impl GameState {
         false
     }
 
-    pub fn get_payoff(&self) -> f32 {
-        // Payoff for the CHALLENGER (current_player)
+    /// `apply_action` for callers that do not trust the action: it must be
+    /// one of `get_valid_actions`, and a call is settled on the spot.
+    pub fn try_apply(&mut self, action: Action) -> Result<Outcome, IllegalAction> {
+        if self.action_index(action.clone()).is_none() {
+            return Err(self.illegality(&action));
+        }
+        if !self.apply_action(action) {
+            return Ok(Outcome::Continue);
+        }
+        let (bid_q, bid_f) = self.current_bid.expect("a call follows a bid");
+        let count = self.hand_p1.iter().chain(&self.hand_p2).filter(|&&d| d == bid_f).count() as u8;
+        let caller_wins = match self.final_call {
+            Some(Action::Calza) => count == bid_q,
+            _ => count < bid_q,
+        };
+        let winner = if caller_wins { self.current_player } else { 1 - self.current_player };
+        Ok(Outcome::ChallengeResolved { count, winner })
+    }
+
+    /// Why `action`, which is not among the legal actions, is illegal.
+    fn illegality(&self, action: &Action) -> IllegalAction {
+        match (action, self.current_bid) {
+            _ if self.final_call.is_some() => IllegalAction::RoundOver,
+            (Action::Challenge | Action::Calza, None) => IllegalAction::NothingToCall(action.clone()),
+            (Action::Calza, Some(_)) => IllegalAction::CalzaNotAllowed,
+            // A bid can be challenged for as long as the round lasts.
+            (Action::Challenge, Some(_)) => IllegalAction::RoundOver,
+            (&Action::Bid(_, face), _) if !(1..=DICE_FACES).contains(&face) => IllegalAction::InvalidFace(face),
+            (&Action::Bid(q, face), Some(current)) if self.bid_rules.is_raise(current, (q, face)) => IllegalAction::QuantityNotAllowed(q),
+            (&Action::Bid(q, face), Some(current)) => IllegalAction::NotARaise { bid: (q, face), current },
+            (&Action::Bid(q, _), None) => IllegalAction::QuantityNotAllowed(q),
+        }
+    }
+
+    /// Takes back the last `apply_action`, so a traversal can walk the tree
+    /// on one state instead of cloning it for every action. The history
+    /// holds only bids, so the state before is known without saving it.
+    pub fn undo_action(&mut self) {
+        if self.final_call.take().is_some() {
+            return;
+        }
+        if self.history.pop().is_some() {
+            self.current_bid = match self.history.last() {
+                Some(Action::Bid(q, f)) => Some((*q, *f)),
+                _ => None,
+            };
+            self.current_player = 1 - self.current_player;
+        }
+    }
+
+    /// What the player who called the round (still `current_player`) wins,
+    /// in dice; negative when the call fails.
+    pub fn challenger_payoff(&self) -> f32 {
         if let Some((bid_q, bid_f)) = self.current_bid {
             let mut count = 0;
             for &d in self.hand_p1.iter().chain(self.hand_p2.iter()) {
@@ -101,36 +797,267 @@ impl GameState {
                 }
             }
 
+            if self.final_call == Some(Action::Calza) {
+                return if count == bid_q {
+                    self.calza_reward.unwrap_or(1.0)
+                } else {
+                    -1.0
+                };
+            }
+
             let bidder_wins = count >= bid_q;
+            let challenger_seat = self.current_seat();
+            let bidder_seat = self.seat_before(challenger_seat);
+            let (challenger_dice, bidder_dice) = (self.seat_dice_count(challenger_seat), self.seat_dice_count(bidder_seat));
             
             if bidder_wins {
                 // Bidder (1 - current) wins. Challenger (current) loses.
-                -1.0
+                -self.dice_lost(count, bid_q, challenger_dice)
             } else {
                 // Bidder lied. Challenger wins.
-                1.0
+                self.dice_lost(count, bid_q, bidder_dice)
             }
         } else {
             0.0 // Should not happen
         }
     }
 
-    pub fn get_information_set(&self) -> String {
-        let my_hand = if self.current_player == 0 {
+    /// `player`'s payoff, in dice, once the round has been called. Values for
+    /// a fixed player should come from here rather than by negating the
+    /// challenger's, so the sign never depends on who called.
+    pub fn payoff_for(&self, player: u8) -> f32 {
+        let payoff = self.challenger_payoff();
+        if player == self.current_player { payoff } else { -payoff }
+    }
+
+    /// `player`'s payoff under the rules' utility, which is what training maximizes.
+    pub fn utility_for(&self, player: u8) -> f32 {
+        self.utility.apply(self.payoff_for(player))
+    }
+
+    /// The seat about to act: the player index, or in team play the team member.
+    pub fn current_seat(&self) -> usize {
+        match self.seat_dice {
+            Some(_) => self.history.len() % 4,
+            None => self.current_player as usize,
+        }
+    }
+
+    fn seat_before(&self, seat: usize) -> usize {
+        match self.seat_dice {
+            Some(_) => (seat + 3) % 4,
+            None => 1 - seat,
+        }
+    }
+
+    fn seat_dice_count(&self, seat: usize) -> u8 {
+        match self.seat_dice {
+            Some(dice) => dice[seat],
+            None if seat == 0 => self.dice_p1,
+            None => self.dice_p2,
+        }
+    }
+
+    fn dice_lost(&self, count: u8, bid_q: u8, loser_dice: u8) -> f32 {
+        match self.dice_loss {
+            DiceLoss::One => 1.0,
+            DiceLoss::Difference => count.abs_diff(bid_q).clamp(1, loser_dice.max(1)) as f32,
+        }
+    }
+
+    pub fn current_hand(&self) -> &[u8] {
+        if self.current_player == 0 {
             &self.hand_p1
         } else {
             &self.hand_p2
+        }
+    }
+
+    pub fn get_information_set(&self) -> String {
+        self.information_set_for(self.current_hand())
+    }
+
+    /// The bids an info set remembers under the history abstraction and
+    /// its refinement, oldest first.
+    pub fn remembered_bids(&self) -> &[Action] {
+        let len = self.history.len();
+        let mut remembered = match self.history_abstraction {
+            HistoryAbstraction::LastBids(k) => k.min(len),
+            HistoryAbstraction::Full => len,
+            HistoryAbstraction::Pressure => 1.min(len),
+        };
+        if let Some(refinement) = &self.refinement {
+            while remembered < len && refinement.splits(&self.history[len - remembered..], len) {
+                remembered += 1;
+            }
+        }
+        &self.history[len - remembered..]
+    }
+
+    /// The pressure features of the bidding so far, as `r<raises>j<jump><switch>`:
+    /// bids that raised the quantity, the current bid's quantity jump over the
+    /// one before it (negative when aces halve it), and `s` if it switched face, `k` if it kept it, `-`
+    /// before there are two bids. None of them names a face, so face
+    /// relabelling leaves them alone.
+    pub fn pressure_features(&self) -> String {
+        let bids: Vec<(u8, u8)> = self
+            .history
+            .iter()
+            .filter_map(|a| match a {
+                Action::Bid(q, f) => Some((*q, *f)),
+                _ => None,
+            })
+            .collect();
+        let raises = bids.windows(2).filter(|w| w[1].0 > w[0].0).count();
+        let (jump, switch) = match bids.as_slice() {
+            [.., (q0, f0), (q1, f1)] => (*q1 as i16 - *q0 as i16, if f1 != f0 { 's' } else { 'k' }),
+            _ => (0, '-'),
+        };
+        format!("r{}j{}{}", raises, jump, switch)
+    }
+
+    /// Under peek handicaps, the dice the player to move shows and sees as
+    /// `s<shown>v<seen>`, each face renamed by `relabel`.
+    pub fn peek_features(&self, relabel: impl Fn(u8) -> u8) -> String {
+        let faces = |dice: &[u8]| {
+            let mut dice: Vec<u8> = dice.iter().map(|&d| relabel(d)).collect();
+            dice.sort();
+            dice.iter().map(|d| d.to_string()).collect::<String>()
         };
+        let me = self.current_player as usize;
+        format!("s{}v{}", faces(&self.revealed[me]), faces(&self.revealed[1 - me]))
+    }
 
+    /// The info set the player to move would be in if they held `my_hand`.
+    /// Under peek handicaps the shown and seen dice are the actual deal's.
+    pub fn information_set_for(&self, my_hand: &[u8]) -> String {
         let hand_str: String = my_hand.iter().map(|d| d.to_string()).collect();
-        
-        let bid_str = match self.current_bid {
-            Some((q, f)) => format!("{}-{}", q, f),
-            None => "None".to_string(),
+        self.format_information_set(hand_str, self.remembered_bids().iter().map(|a| a.to_string()), |d| d)
+    }
+
+    /// As `information_set_for`, with every face renamed to `relabel[face]`.
+    pub fn information_set_relabelled(&self, my_hand: &[u8], relabel: &[u8]) -> String {
+        let mut hand: Vec<u8> = my_hand.iter().map(|&d| relabel[d as usize]).collect();
+        hand.sort();
+        let hand_str: String = hand.iter().map(|d| d.to_string()).collect();
+        let bids = self.remembered_bids().iter().map(|a| match a {
+            Action::Bid(q, f) => Action::Bid(*q, relabel[*f as usize]).to_string(),
+            other => other.to_string(),
+        });
+        self.format_information_set(hand_str, bids, |d| relabel[d as usize])
+    }
+
+    fn format_information_set<I: Iterator<Item = String>>(&self, hand_str: String, bids: I, relabel: impl Fn(u8) -> u8) -> String {
+        let bids: Vec<String> = bids.collect();
+        let bid_str = if bids.is_empty() {
+            "None".to_string()
+        } else {
+            bids.join("/")
         };
 
         let count_str = self.history.len().to_string();
 
-        format!("{}|{}|{}", hand_str, bid_str, count_str)
+        let mut key = format!("{}|{}|{}", hand_str, bid_str, count_str);
+        if self.history_abstraction == HistoryAbstraction::Pressure {
+            key.push('|');
+            key.push_str(&self.pressure_features());
+        }
+        if self.handicaps.has_peek() {
+            key.push('|');
+            key.push_str(&self.peek_features(relabel));
+        }
+        key
+    }
+}
+
+/// Every sorted hand of `n_dice` dice together with its probability of being rolled.
+pub fn hand_distribution(n_dice: u8, config: &GameConfig) -> Vec<(Vec<u8>, f64)> {
+    let mut hands = Vec::new();
+    let mut hand = Vec::with_capacity(n_dice as usize);
+    collect_hands(n_dice, 1, &mut hand, &mut hands);
+
+    hands
+        .into_iter()
+        .map(|h| {
+            let roll_prob: f64 = h.iter().map(|&d| config.face_weights[d as usize - 1]).product();
+            let prob = multinomial(&h) * roll_prob;
+            (h, prob)
+        })
+        .collect()
+}
+
+fn collect_hands(remaining: u8, min_face: u8, hand: &mut Vec<u8>, out: &mut Vec<Vec<u8>>) {
+    if remaining == 0 {
+        out.push(hand.clone());
+        return;
+    }
+    for f in min_face..=DICE_FACES {
+        hand.push(f);
+        collect_hands(remaining - 1, f, hand, out);
+        hand.pop();
+    }
+}
+
+/// Number of distinct roll orders that sort to `hand`.
+fn multinomial(hand: &[u8]) -> f64 {
+    let factorial = |n: usize| (1..=n).map(|k| k as f64).product::<f64>();
+    let mut result = factorial(hand.len());
+    for f in 1..=DICE_FACES {
+        result /= factorial(hand.iter().filter(|&&d| d == f).count());
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round() -> GameState {
+        GameState::from_hands(&GameConfig::new(2, 2), vec![3, 5], vec![3, 6])
+    }
+
+    #[test]
+    fn nothing_is_legal_after_a_call() {
+        let mut state = round();
+        assert_eq!(state.try_apply(Action::Bid(2, 3)), Ok(Outcome::Continue));
+        assert_eq!(state.try_apply(Action::Challenge), Ok(Outcome::ChallengeResolved { count: 2, winner: 0 }));
+        assert_eq!(state.valid_actions_iter().count(), 0);
+        assert_eq!(state.try_apply(Action::Bid(3, 3)), Err(IllegalAction::RoundOver));
+        assert_eq!(state.history, [Action::Bid(2, 3)]);
+    }
+
+    #[test]
+    fn a_round_is_challenged_once() {
+        let mut state = round();
+        state.try_apply(Action::Bid(3, 6)).unwrap();
+        assert_eq!(state.try_apply(Action::Challenge), Ok(Outcome::ChallengeResolved { count: 1, winner: 1 }));
+        assert_eq!(state.try_apply(Action::Challenge), Err(IllegalAction::RoundOver));
+        assert!(GameState::from_history(&GameConfig::new(2, 2), vec![3, 5], vec![3, 6], &[Action::Bid(3, 6), Action::Challenge, Action::Challenge]).is_err());
+    }
+
+    #[test]
+    fn faces_outside_the_die_are_rejected() {
+        let mut state = round();
+        assert_eq!(state.try_apply(Action::Bid(1, 7)), Err(IllegalAction::InvalidFace(7)));
+        assert_eq!(state.try_apply(Action::Bid(1, 0)), Err(IllegalAction::InvalidFace(0)));
+        assert!(state.history.is_empty());
+    }
+
+    #[test]
+    fn bids_must_raise() {
+        let mut state = round();
+        state.try_apply(Action::Bid(2, 4)).unwrap();
+        assert_eq!(state.try_apply(Action::Bid(1, 6)), Err(IllegalAction::NotARaise { bid: (1, 6), current: (2, 4) }));
+        assert_eq!(state.try_apply(Action::Bid(2, 4)), Err(IllegalAction::NotARaise { bid: (2, 4), current: (2, 4) }));
+        assert_eq!(state.current_player, 1);
+    }
+
+    #[test]
+    fn pressure_jump_can_fall_when_aces_halve_the_bid() {
+        let config = GameConfig { bid_rules: BidRules::AcesLadder, ..GameConfig::new(2, 2) };
+        let mut state = GameState::from_hands(&config, vec![1, 5], vec![3, 6]);
+        state.try_apply(Action::Bid(4, 3)).unwrap();
+        state.try_apply(Action::Bid(2, 1)).unwrap();
+        assert_eq!(state.pressure_features(), "r0j-2s");
     }
 }
@@ -0,0 +1,138 @@
+use crate::record::json_string;
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+
+// Just enough HTTP/1.1 for the server's JSON endpoints and event streams:
+// one request per connection, parameters in the query string or a form body.
+
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    /// Query-string and form-body parameters.
+    pub params: HashMap<String, String>,
+    /// The raw body, for endpoints that take JSON.
+    pub body: String,
+}
+
+impl Request {
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params.get(name).map(String::as_str)
+    }
+}
+
+/// Largest request body accepted; the endpoints only take short parameters.
+const MAX_BODY: usize = 1 << 20;
+
+pub fn read_request(stream: &TcpStream) -> io::Result<Request> {
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().ok_or_else(|| invalid("empty request"))?.to_string();
+    let target = parts.next().ok_or_else(|| invalid("missing request target"))?;
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().map_err(|_| invalid("bad content length"))?;
+            }
+        }
+    }
+    if content_length > MAX_BODY {
+        return Err(invalid("request body too large"));
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    let body = String::from_utf8(body).map_err(|_| invalid("body is not UTF-8"))?;
+
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let mut params = parse_params(query);
+    if !body.trim_start().starts_with(['[', '{']) {
+        params.extend(parse_params(&body));
+    }
+    Ok(Request { method, path: path.to_string(), params, body })
+}
+
+fn parse_params(encoded: &str) -> HashMap<String, String> {
+    encoded
+        .trim()
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+            (url_decode(k), url_decode(v))
+        })
+        .collect()
+}
+
+pub fn respond(mut stream: &TcpStream, status: u16, content_type: &str, body: &str) -> io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        409 => "Conflict",
+        _ => "Error",
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+pub fn respond_json(stream: &TcpStream, status: u16, body: &str) -> io::Result<()> {
+    respond(stream, status, "application/json", body)
+}
+
+pub fn respond_error(stream: &TcpStream, status: u16, message: &str) -> io::Result<()> {
+    respond_json(stream, status, &format!("{{\"error\":{}}}", json_string(message)))
+}
+
+/// Starts a server-sent event stream; follow with `send_event` calls.
+pub fn start_event_stream(mut stream: &TcpStream) -> io::Result<()> {
+    write!(stream, "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n")?;
+    stream.flush()
+}
+
+pub fn send_event(mut stream: &TcpStream, event: &str, data: &str) -> io::Result<()> {
+    write!(stream, "event: {}\ndata: {}\n\n", event, data)?;
+    stream.flush()
+}
+
+fn url_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                match std::str::from_utf8(&bytes[i + 1..i + 3]).ok().and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                    Some(b) => {
+                        out.push(b);
+                        i += 2;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
@@ -0,0 +1,226 @@
+use crate::cfr::CFRNode;
+use crate::game::{hand_distribution, Action, GameConfig, GameState, HistoryAbstraction};
+use crate::strategy::StrategyTable;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::mem::size_of;
+
+/// What a loaded strategy table holds and what it costs in memory.
+pub struct TableSummary {
+    pub info_sets: usize,
+    /// Stored (info set, action) rows; saved tables drop near-zero actions.
+    pub rows: usize,
+    /// Info sets by number of stored actions.
+    pub action_counts: BTreeMap<usize, usize>,
+    /// Estimated heap and table footprint of the loaded `StrategyTable`.
+    pub memory_bytes: usize,
+}
+
+impl TableSummary {
+    pub fn collect(table: &StrategyTable) -> Self {
+        let mut action_counts = BTreeMap::new();
+        let mut memory_bytes = 0;
+        for (key, actions) in &table.entries {
+            *action_counts.entry(actions.len()).or_insert(0) += 1;
+            memory_bytes += size_of::<(String, Vec<(Action, f64)>)>() + key.capacity() + actions.capacity() * size_of::<(Action, f64)>();
+        }
+        let entries = &table.entries;
+        memory_bytes += (entries.capacity() - entries.len()) * size_of::<(String, Vec<(Action, f64)>)>() + entries.capacity();
+
+        TableSummary {
+            info_sets: entries.len(),
+            rows: entries.values().map(Vec::len).sum(),
+            action_counts,
+            memory_bytes,
+        }
+    }
+}
+
+impl fmt::Display for TableSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Info sets: {}", self.info_sets)?;
+        writeln!(f, "Action rows: {} ({:.2} per info set)", self.rows, self.rows as f64 / self.info_sets.max(1) as f64)?;
+        writeln!(f, "Stored actions per info set:")?;
+        for (actions, count) in &self.action_counts {
+            writeln!(f, "  {:>3}: {}", actions, count)?;
+        }
+        writeln!(f, "Estimated memory to load: {}", mib(self.memory_bytes as f64))
+    }
+}
+
+/// Where the bytes of a strategy CSV go.
+#[derive(Default)]
+pub struct CsvBreakdown {
+    pub header: usize,
+    pub info_sets: usize,
+    pub actions: usize,
+    pub probabilities: usize,
+    /// Commas and line breaks.
+    pub separators: usize,
+}
+
+impl CsvBreakdown {
+    pub fn collect(contents: &str) -> Self {
+        let mut breakdown = CsvBreakdown::default();
+        let mut lines = contents.split_inclusive('\n');
+        breakdown.header = lines.next().map_or(0, str::len);
+        for line in lines {
+            let fields: Vec<&str> = line.trim_end_matches(['\r', '\n']).split(',').collect();
+            breakdown.info_sets += fields.first().map_or(0, |s| s.len());
+            breakdown.actions += fields.get(1).map_or(0, |s| s.len());
+            breakdown.probabilities += fields.get(2).map_or(0, |s| s.len());
+            breakdown.separators += line.len() - fields.iter().map(|s| s.len()).sum::<usize>();
+        }
+        breakdown
+    }
+
+    pub fn total(&self) -> usize {
+        self.header + self.info_sets + self.actions + self.probabilities + self.separators
+    }
+}
+
+impl fmt::Display for CsvBreakdown {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let total = self.total().max(1) as f64;
+        writeln!(f, "File size: {} bytes", self.total())?;
+        for (name, bytes) in [
+            ("header", self.header),
+            ("info set keys", self.info_sets),
+            ("actions", self.actions),
+            ("probabilities", self.probabilities),
+            ("separators", self.separators),
+        ] {
+            writeln!(f, "  {:<14} {:>12} bytes ({:.1}%)", name, bytes, bytes as f64 / total * 100.0)?;
+        }
+        Ok(())
+    }
+}
+
+/// Predicted size of training a configuration, from the shape of its bid
+/// tree alone. Counts are f64 because full-history trees outgrow integers.
+pub struct SizeEstimate {
+    pub info_sets: f64,
+    /// Legal actions summed over info sets, i.e. regret and strategy slots.
+    pub action_slots: f64,
+    /// Average info set key length in bytes.
+    pub key_bytes: f64,
+}
+
+impl SizeEstimate {
+    /// Sums the info sets of every opening the rules allow.
+    pub fn for_config(config: &GameConfig) -> Self {
+        let mut estimate = SizeEstimate { info_sets: 0.0, action_slots: 0.0, key_bytes: 0.0 };
+        let mut key_total = 0.0;
+        for (round, _) in config.openings() {
+            let hands = [hand_distribution(round.dice_p1, &round).len() as f64, hand_distribution(round.dice_p2, &round).len() as f64];
+            let dice = [round.dice_p1 as f64, round.dice_p2 as f64];
+            for shape in public_shapes(&round) {
+                let p = shape.player;
+                estimate.info_sets += shape.histories * hands[p];
+                estimate.action_slots += shape.actions * hands[p];
+                key_total += (shape.key_chars + shape.histories * dice[p]) * hands[p];
+            }
+        }
+        estimate.key_bytes = key_total / estimate.info_sets.max(1.0);
+        estimate
+    }
+
+    /// A trained node map: keys, regret and strategy sums, actions and hash slots.
+    pub fn node_map_bytes(&self) -> f64 {
+        let per_info_set = size_of::<(String, CFRNode)>() as f64 + self.key_bytes;
+        let per_action = (2 * size_of::<f32>() + size_of::<Action>()) as f64;
+        // Hash tables stay at most 7/8 full and keep one control byte per slot.
+        let slots = self.info_sets * 8.0 / 7.0;
+        self.info_sets * per_info_set + self.action_slots * per_action + (slots - self.info_sets) * size_of::<(String, CFRNode)>() as f64 + slots
+    }
+
+    /// The saved CSV if every action were kept; pruned actions make it smaller.
+    pub fn csv_bytes(&self) -> f64 {
+        // Key, "Q-F" action and a probability of about ten digits, with separators.
+        self.action_slots * (self.key_bytes + 4.0 + 10.0 + 3.0)
+    }
+}
+
+/// The remembered histories one player acts at, in one round.
+struct PublicShape {
+    player: usize,
+    histories: f64,
+    /// Legal actions summed over the histories.
+    actions: f64,
+    /// Key characters besides the hand, summed over the histories.
+    key_chars: f64,
+}
+
+/// Every distinct remembered history in the round, grouped by player.
+/// Full recall counts bid paths by (current bid, bids so far), since legal
+/// raises depend only on the current bid; windowed and pressure recall
+/// enumerate the distinct remembered histories instead.
+fn public_shapes(config: &GameConfig) -> Vec<PublicShape> {
+    let root = GameState::from_hands(config, vec![1; config.dice_p1 as usize], vec![1; config.dice_p2 as usize]);
+    let mut shapes = vec![
+        PublicShape { player: 0, histories: 0.0, actions: 0.0, key_chars: 0.0 },
+        PublicShape { player: 1, histories: 0.0, actions: 0.0, key_chars: 0.0 },
+    ];
+
+    match config.history_abstraction {
+        HistoryAbstraction::Full => {
+            // (paths, bid characters summed over paths), by current bid, one layer per bid count.
+            let mut layer: HashMap<Option<(u8, u8)>, (f64, f64, GameState)> = HashMap::new();
+            layer.insert(None, (1.0, 0.0, root));
+            let mut len = 0usize;
+            while !layer.is_empty() {
+                let mut next_layer: HashMap<Option<(u8, u8)>, (f64, f64, GameState)> = HashMap::new();
+                for (paths, chars, state) in layer.into_values() {
+                    let actions = state.get_valid_actions();
+                    let shape = &mut shapes[state.current_player as usize];
+                    shape.histories += paths;
+                    shape.actions += paths * actions.len() as f64;
+                    // "|" separators, "None" for the empty history, and the bid count.
+                    let fixed = 2.0 + if len == 0 { 4.0 } else { 0.0 } + digits(len);
+                    shape.key_chars += chars + paths * fixed;
+                    for action in actions {
+                        let mut next = state.clone();
+                        let label = action.to_string().len() as f64 + if len == 0 { 0.0 } else { 1.0 };
+                        if !next.apply_action(action) {
+                            let entry = next_layer.entry(next.current_bid).or_insert((0.0, 0.0, next));
+                            entry.0 += paths;
+                            entry.1 += chars + paths * label;
+                        }
+                    }
+                }
+                layer = next_layer;
+                len += 1;
+            }
+        }
+        HistoryAbstraction::LastBids(_) | HistoryAbstraction::Pressure => {
+            let mut seen = HashSet::new();
+            let mut stack = vec![root];
+            while let Some(state) = stack.pop() {
+                // The key without a hand is exactly what the abstraction remembers.
+                if !seen.insert(state.information_set_for(&[])) {
+                    continue;
+                }
+                let actions = state.get_valid_actions();
+                let shape = &mut shapes[state.current_player as usize];
+                shape.histories += 1.0;
+                shape.actions += actions.len() as f64;
+                shape.key_chars += (state.information_set_for(&[]).len()) as f64;
+                for action in actions {
+                    let mut next = state.clone();
+                    if !next.apply_action(action) {
+                        stack.push(next);
+                    }
+                }
+            }
+        }
+    }
+    shapes
+}
+
+fn digits(n: usize) -> f64 {
+    n.to_string().len() as f64
+}
+
+pub fn mib(bytes: f64) -> String {
+    format!("{:.2} MiB", bytes / (1024.0 * 1024.0))
+}
@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+
+// Kuhn poker: a three-card deck (0 < 1 < 2), one card each, an ante of 1 and
+// a single bet of 1. The first player moves, then the second; passing after a
+// bet folds and betting after a bet calls. Its equilibrium value is known in
+// closed form, so it checks the CFR+ update on a game whose answer does not
+// come from this crate's own exact solver. The CFR+ here is written for Kuhn
+// alone and shares no code with `cfr.rs`: `verify` reports it as a
+// standalone sanity check of the algorithm, not of the trainer.
+
+/// The first player's expected payoff at any equilibrium.
+pub const GAME_VALUE: f64 = -1.0 / 18.0;
+
+/// Pass (check or fold) and bet (bet or call), as history letters.
+const ACTIONS: [char; 2] = ['p', 'b'];
+
+/// Every deal, each with probability 1/6.
+const DEALS: [(u8, u8); 6] = [(0, 1), (0, 2), (1, 0), (1, 2), (2, 0), (2, 1)];
+
+/// One info set's cumulative regrets and average-strategy weights.
+#[derive(Clone, Debug, Default)]
+pub struct KuhnNode {
+    pub regret_sum: [f64; 2],
+    pub strategy_sum: [f64; 2],
+}
+
+impl KuhnNode {
+    fn current_strategy(&self) -> [f64; 2] {
+        let positive = self.regret_sum.map(|r| r.max(0.0));
+        let total: f64 = positive.iter().sum();
+        if total > 0.0 { positive.map(|r| r / total) } else { [0.5; 2] }
+    }
+
+    pub fn average_strategy(&self) -> [f64; 2] {
+        let total: f64 = self.strategy_sum.iter().sum();
+        if total > 0.0 { self.strategy_sum.map(|s| s / total) } else { [0.5; 2] }
+    }
+}
+
+/// The first player's payoff if `history` ends the hand.
+fn terminal_payoff(cards: (u8, u8), history: &str) -> Option<f64> {
+    let showdown = |stake: f64| if cards.0 > cards.1 { stake } else { -stake };
+    match history {
+        "pp" => Some(showdown(1.0)),
+        "bb" | "pbb" => Some(showdown(2.0)),
+        "bp" => Some(1.0),
+        "pbp" => Some(-1.0),
+        _ => None,
+    }
+}
+
+fn info_set(card: u8, history: &str) -> String {
+    format!("{}|{}", card, history)
+}
+
+/// Trains `iterations` iterations of CFR+, each walking every deal.
+pub fn train(iterations: usize) -> HashMap<String, KuhnNode> {
+    let mut nodes = HashMap::new();
+    for _ in 0..iterations {
+        for cards in DEALS {
+            cfr(cards, &mut String::new(), [1.0, 1.0], &mut nodes);
+        }
+    }
+    nodes
+}
+
+/// One CFR+ traversal; returns the first player's value of `history`.
+fn cfr(cards: (u8, u8), history: &mut String, reach: [f64; 2], nodes: &mut HashMap<String, KuhnNode>) -> f64 {
+    if let Some(payoff) = terminal_payoff(cards, history) {
+        return payoff;
+    }
+    let player = history.len() % 2;
+    let card = if player == 0 { cards.0 } else { cards.1 };
+    let key = info_set(card, history);
+    let strategy = nodes.entry(key.clone()).or_default().current_strategy();
+
+    let mut util = [0.0; 2];
+    for (i, &action) in ACTIONS.iter().enumerate() {
+        let mut next_reach = reach;
+        next_reach[player] *= strategy[i];
+        history.push(action);
+        util[i] = cfr(cards, history, next_reach, nodes);
+        history.pop();
+    }
+    let node_util = strategy[0] * util[0] + strategy[1] * util[1];
+
+    // Regrets are the mover's, so the second player's are negated.
+    let sign = if player == 0 { 1.0 } else { -1.0 };
+    let node = nodes.get_mut(&key).expect("created above");
+    for i in 0..2 {
+        node.regret_sum[i] = (node.regret_sum[i] + reach[1 - player] * sign * (util[i] - node_util)).max(0.0);
+        node.strategy_sum[i] += reach[player] * strategy[i];
+    }
+    node_util
+}
+
+/// The first player's expected payoff when both play the average strategies
+/// in `nodes`, except that `deviation` replaces one player's strategy.
+fn expected_value(nodes: &HashMap<String, KuhnNode>, deviation: Option<(usize, &HashMap<String, usize>)>) -> f64 {
+    fn walk(cards: (u8, u8), history: &mut String, nodes: &HashMap<String, KuhnNode>, deviation: Option<(usize, &HashMap<String, usize>)>) -> f64 {
+        if let Some(payoff) = terminal_payoff(cards, history) {
+            return payoff;
+        }
+        let player = history.len() % 2;
+        let key = info_set(if player == 0 { cards.0 } else { cards.1 }, history);
+        let strategy = match deviation {
+            Some((deviator, choices)) if deviator == player => {
+                let mut pure = [0.0; 2];
+                pure[choices[&key]] = 1.0;
+                pure
+            }
+            _ => nodes.get(&key).map_or([0.5; 2], KuhnNode::average_strategy),
+        };
+        let mut value = 0.0;
+        for (i, &action) in ACTIONS.iter().enumerate() {
+            if strategy[i] > 0.0 {
+                history.push(action);
+                value += strategy[i] * walk(cards, history, nodes, deviation);
+                history.pop();
+            }
+        }
+        value
+    }
+    DEALS.iter().map(|&cards| walk(cards, &mut String::new(), nodes, deviation)).sum::<f64>() / DEALS.len() as f64
+}
+
+/// `player`'s best-response value against the other's average strategy. Each
+/// player has six info sets, so every pure strategy is tried.
+pub fn best_response_value(nodes: &HashMap<String, KuhnNode>, player: usize) -> f64 {
+    let histories: [&str; 2] = if player == 0 { ["", "pb"] } else { ["p", "b"] };
+    let keys: Vec<String> = (0..3).flat_map(|card| histories.map(|h| info_set(card, h))).collect();
+    (0..1usize << keys.len())
+        .map(|bits| {
+            let choices: HashMap<String, usize> = keys.iter().enumerate().map(|(i, k)| (k.clone(), (bits >> i) & 1)).collect();
+            let value = expected_value(nodes, Some((player, &choices)));
+            if player == 0 { value } else { -value }
+        })
+        .fold(f64::NEG_INFINITY, f64::max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exploitability(nodes: &HashMap<String, KuhnNode>) -> f64 {
+        (best_response_value(nodes, 0) + best_response_value(nodes, 1)) / 2.0
+    }
+
+    #[test]
+    fn uniform_play_is_exploitable() {
+        assert!(exploitability(&HashMap::new()) > 0.1);
+    }
+
+    #[test]
+    fn cfr_plus_reaches_the_known_value() {
+        let iterations = 2000;
+        let nodes = train(iterations);
+        // CFR+ on Kuhn converges at least as fast as 1/sqrt(T).
+        let tolerance = 1.0 / (iterations as f64).sqrt();
+        let exploitability = exploitability(&nodes);
+        assert!(exploitability < tolerance, "exploitability {}", exploitability);
+        let value = expected_value(&nodes, None);
+        assert!((value - GAME_VALUE).abs() < tolerance, "value {}", value);
+        // Holding the lowest card, the first player folds to a bet.
+        assert!(nodes["0|pb"].average_strategy()[1] < 0.01);
+    }
+}
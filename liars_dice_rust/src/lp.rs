@@ -0,0 +1,672 @@
+/// A sparse revised simplex solver: maximize `c . x` subject to linear
+/// constraints, with every variable non-negative unless marked free. The
+/// basis is held as a sparse LU factorization updated in product form and
+/// refactorized every so often, so a pivot costs about as much as the
+/// factors have nonzeros. Meant for the exact solver's sequence-form
+/// programs, which reach tens of thousands of rows but have only a handful
+/// of nonzeros in each, not for general use.
+pub struct LinearProgram {
+    num_vars: usize,
+    objective: Vec<f64>,
+    free: Vec<bool>,
+    constraints: Vec<Constraint>,
+}
+
+/// `sum(coefficient * x[var]) relation rhs`, with the terms as (var, coefficient).
+type Constraint = (Vec<(usize, f64)>, Relation, f64);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Relation {
+    LessEq,
+    Equal,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum LpError {
+    Infeasible,
+    Unbounded,
+    IterationLimit,
+}
+
+/// Primal feasibility tolerance.
+const FEASIBILITY: f64 = 1e-9;
+/// Reduced costs above this do not enter.
+const OPTIMALITY: f64 = 1e-9;
+/// Smallest pivot element the ratio test accepts.
+const PIVOT: f64 = 1e-9;
+/// Scale of the right-hand side perturbation that keeps pivots from stalling
+/// on the many degenerate vertices of sequence-form programs.
+const PERTURBATION: f64 = 1e-7;
+/// Degenerate pivots in a row before switching to Bland's rule, which cannot cycle.
+const DEGENERATE_LIMIT: usize = 50;
+/// Product-form updates between refactorizations.
+const REFACTOR_EVERY: usize = 100;
+/// A factorization pivot must be at least this share of the largest entry
+/// in its column, which bounds the growth of the factors.
+const PIVOT_THRESHOLD: f64 = 0.01;
+
+impl LinearProgram {
+    pub fn new(num_vars: usize) -> Self {
+        LinearProgram { num_vars, objective: vec![0.0; num_vars], free: vec![false; num_vars], constraints: Vec::new() }
+    }
+
+    pub fn set_objective(&mut self, var: usize, coefficient: f64) {
+        self.objective[var] = coefficient;
+    }
+
+    /// Lets `var` take either sign.
+    pub fn set_free(&mut self, var: usize) {
+        self.free[var] = true;
+    }
+
+    /// Adds `sum(coefficient * x[var]) relation rhs`.
+    pub fn add_constraint(&mut self, terms: Vec<(usize, f64)>, relation: Relation, rhs: f64) {
+        self.constraints.push((terms, relation, rhs));
+    }
+
+    /// Nonzero coefficients in the constraints.
+    pub fn nonzeros(&self) -> usize {
+        self.constraints.iter().map(|(terms, _, _)| terms.len()).sum()
+    }
+
+    /// The optimal value and a solution attaining it.
+    pub fn maximize(&self) -> Result<(f64, Vec<f64>), LpError> {
+        let mut simplex = Simplex::new(self);
+
+        // Pivot on a slightly perturbed right-hand side, then restore the true
+        // one and let the final basis (rarely more pivots) settle it exactly.
+        let rows = self.constraints.len();
+        let exact: Vec<f64> = self.constraints.iter().map(|(_, _, rhs)| *rhs).collect();
+        let perturbed: Vec<f64> = (0..rows)
+            .map(|row| {
+                if self.constraints[row].1 == Relation::Equal {
+                    return exact[row];
+                }
+                // A fixed pseudo-random spread, so no two rows tie.
+                let spread = (row as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 40;
+                exact[row] + PERTURBATION * (1.0 + exact[row].abs()) * (1.0 + spread as f64 / (1u64 << 24) as f64)
+            })
+            .collect();
+        simplex.rhs = perturbed;
+        simplex.refactor();
+        simplex.run()?;
+        simplex.rhs = exact;
+        simplex.refactor();
+        simplex.run()?;
+
+        let mut solution = vec![0.0; self.num_vars];
+        for (position, &var) in simplex.basis.iter().enumerate() {
+            if var < self.num_vars {
+                solution[var] = simplex.values[position];
+            }
+        }
+        for (var, value) in solution.iter_mut().enumerate() {
+            if !self.free[var] {
+                *value = value.max(0.0);
+            }
+        }
+        let value = self.objective.iter().zip(&solution).map(|(c, x)| c * x).sum();
+        Ok((value, solution))
+    }
+}
+
+/// What values a variable may take. Structural variables are `Lower` or
+/// `Free`; each row's logical variable is `Lower` (a slack) or `Fixed` at zero.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Bound {
+    Lower,
+    Fixed,
+    Free,
+}
+
+/// The program as minimize `cost . x` subject to `A x + logicals = rhs`.
+/// Variables `0..n` are the structural ones, `n + row` the logical of `row`.
+/// Nonbasic variables all sit at zero, so the basic ones solve `B x_B = rhs`.
+struct Simplex {
+    rows: usize,
+    structurals: usize,
+    /// The structural columns, compressed.
+    col_start: Vec<usize>,
+    col_rows: Vec<usize>,
+    col_values: Vec<f64>,
+    bounds: Vec<Bound>,
+    cost: Vec<f64>,
+    rhs: Vec<f64>,
+    /// The variable at each basis position, and each variable's position.
+    basis: Vec<usize>,
+    position: Vec<Option<usize>>,
+    values: Vec<f64>,
+    factor: Factor,
+}
+
+impl Simplex {
+    fn new(lp: &LinearProgram) -> Self {
+        let rows = lp.constraints.len();
+        let n = lp.num_vars;
+        let mut counts = vec![0; n + 1];
+        for (terms, _, _) in &lp.constraints {
+            for &(var, _) in terms {
+                counts[var + 1] += 1;
+            }
+        }
+        let mut col_start = counts;
+        for var in 0..n {
+            col_start[var + 1] += col_start[var];
+        }
+        let mut next = col_start.clone();
+        let mut col_rows = vec![0; col_start[n]];
+        let mut col_values = vec![0.0; col_start[n]];
+        for (row, (terms, _, _)) in lp.constraints.iter().enumerate() {
+            for &(var, coefficient) in terms {
+                col_rows[next[var]] = row;
+                col_values[next[var]] = coefficient;
+                next[var] += 1;
+            }
+        }
+
+        let mut bounds: Vec<Bound> = lp.free.iter().map(|&free| if free { Bound::Free } else { Bound::Lower }).collect();
+        bounds.extend(lp.constraints.iter().map(|(_, relation, _)| match relation {
+            Relation::LessEq => Bound::Lower,
+            Relation::Equal => Bound::Fixed,
+        }));
+        let mut cost: Vec<f64> = lp.objective.iter().map(|c| -c).collect();
+        cost.resize(n + rows, 0.0);
+        let mut position = vec![None; n + rows];
+        for row in 0..rows {
+            position[n + row] = Some(row);
+        }
+
+        // Crash: give each equality row, in order, a structural column that is
+        // nonzero there and in no equality row crashed before it, so the
+        // starting basis is triangular. Equalities otherwise each cost a
+        // phase-one pivot to bring their fixed logical out.
+        let mut crashed = vec![false; rows];
+        let mut basis: Vec<usize> = (n..n + rows).collect();
+        for (row, (terms, relation, _)) in lp.constraints.iter().enumerate() {
+            if *relation != Relation::Equal {
+                continue;
+            }
+            let largest = terms.iter().map(|t| t.1.abs()).fold(0.0, f64::max);
+            let candidate = terms.iter().find(|&&(var, coefficient)| {
+                coefficient.abs() >= 0.5 * largest
+                    && position[var].is_none()
+                    && (col_start[var]..col_start[var + 1]).all(|e| !crashed[col_rows[e]])
+            });
+            if let Some(&(var, _)) = candidate {
+                crashed[row] = true;
+                position[n + row] = None;
+                position[var] = Some(row);
+                basis[row] = var;
+            }
+        }
+
+        let mut simplex = Simplex {
+            rows,
+            structurals: n,
+            col_start,
+            col_rows,
+            col_values,
+            bounds,
+            cost,
+            rhs: vec![0.0; rows],
+            basis,
+            position,
+            values: vec![0.0; rows],
+            factor: Factor::default(),
+        };
+        simplex.refactor();
+        simplex
+    }
+
+    /// The column of `var` as (row, coefficient) pairs.
+    fn column(&self, var: usize) -> Vec<(usize, f64)> {
+        if var < self.structurals {
+            let range = self.col_start[var]..self.col_start[var + 1];
+            self.col_rows[range.clone()].iter().copied().zip(self.col_values[range].iter().copied()).collect()
+        } else {
+            vec![(var - self.structurals, 1.0)]
+        }
+    }
+
+    /// `y . column(var)` for a dense `y` over rows.
+    fn dot_column(&self, var: usize, y: &[f64]) -> f64 {
+        if var < self.structurals {
+            (self.col_start[var]..self.col_start[var + 1]).map(|e| self.col_values[e] * y[self.col_rows[e]]).sum()
+        } else {
+            y[var - self.structurals]
+        }
+    }
+
+    /// Factorizes the current basis afresh and recomputes the basic values.
+    /// A numerically singular basis has its dependent columns swapped for
+    /// the logicals of the rows left without a pivot.
+    fn refactor(&mut self) {
+        let columns: Vec<Vec<(usize, f64)>> = self.basis.iter().map(|&var| self.column(var)).collect();
+        let (factor, replaced) = Factor::new(self.rows, columns);
+        self.factor = factor;
+        for (position, row) in replaced {
+            let var = self.basis[position];
+            self.position[var] = None;
+            let logical = self.structurals + row;
+            self.basis[position] = logical;
+            self.position[logical] = Some(position);
+        }
+        let mut values = self.rhs.clone();
+        self.factor.solve(&mut values);
+        self.values = values;
+    }
+
+    /// How far the basic variable at `position` is outside its bounds:
+    /// negative below, positive above.
+    fn violation(&self, position: usize) -> f64 {
+        let value = self.values[position];
+        match self.bounds[self.basis[position]] {
+            Bound::Free => 0.0,
+            Bound::Lower => value.min(0.0),
+            Bound::Fixed => value,
+        }
+    }
+
+    /// Pivots until the basis is feasible and optimal for `rhs`. While any
+    /// basic variable is out of bounds the cost is the total infeasibility.
+    fn run(&mut self) -> Result<(), LpError> {
+        let mut degenerate = 0;
+        let mut since_refactor = 0;
+        for _ in 0..50 * (self.rows + self.structurals) + 1000 {
+            if since_refactor >= REFACTOR_EVERY {
+                self.refactor();
+                since_refactor = 0;
+            }
+            let infeasible = (0..self.rows).any(|p| self.violation(p).abs() > FEASIBILITY);
+            let basic_cost: Vec<f64> = (0..self.rows)
+                .map(|p| match infeasible {
+                    true => {
+                        let violation = self.violation(p);
+                        if violation < -FEASIBILITY {
+                            -1.0
+                        } else if violation > FEASIBILITY {
+                            1.0
+                        } else {
+                            0.0
+                        }
+                    }
+                    false => self.cost[self.basis[p]],
+                })
+                .collect();
+            let mut duals = basic_cost;
+            self.factor.solve_transposed(&mut duals);
+
+            // Dantzig's rule, or Bland's once pivots keep stalling.
+            let bland = degenerate >= DEGENERATE_LIMIT;
+            let mut entering: Option<(usize, f64)> = None;
+            for var in 0..self.structurals + self.rows {
+                if self.position[var].is_some() || self.bounds[var] == Bound::Fixed {
+                    continue;
+                }
+                let cost = if infeasible { 0.0 } else { self.cost[var] };
+                let reduced = cost - self.dot_column(var, &duals);
+                let improves = reduced < -OPTIMALITY || (self.bounds[var] == Bound::Free && reduced > OPTIMALITY);
+                if improves && entering.is_none_or(|(_, best)| !bland && reduced.abs() > best.abs()) {
+                    entering = Some((var, reduced));
+                    if bland {
+                        break;
+                    }
+                }
+            }
+            let Some((var, reduced)) = entering else {
+                return if infeasible { Err(LpError::Infeasible) } else { Ok(()) };
+            };
+
+            // Moving `var` by t in `direction` moves basic position p by -t * direction * alpha[p].
+            let direction = if reduced < 0.0 { 1.0 } else { -1.0 };
+            let mut alpha = vec![0.0; self.rows];
+            for (row, coefficient) in self.column(var) {
+                alpha[row] = coefficient;
+            }
+            self.factor.solve(&mut alpha);
+
+            let Some((leaving, step)) = self.ratio_test(&alpha, direction, bland) else {
+                if infeasible {
+                    // Total infeasibility is bounded below, so this is round-off; start over from a fresh factorization.
+                    self.refactor();
+                    since_refactor = 0;
+                    continue;
+                }
+                return Err(LpError::Unbounded);
+            };
+            degenerate = if step < FEASIBILITY { degenerate + 1 } else { 0 };
+
+            for (value, a) in self.values.iter_mut().zip(&alpha) {
+                *value -= step * direction * a;
+            }
+            self.values[leaving] = step * direction;
+            let left = self.basis[leaving];
+            self.position[left] = None;
+            self.basis[leaving] = var;
+            self.position[var] = Some(leaving);
+            self.factor.update(leaving, &alpha);
+            since_refactor += 1;
+        }
+        Err(LpError::IterationLimit)
+    }
+
+    /// The basis position that leaves and the step the entering variable
+    /// takes, by Harris's two passes: find the longest step that keeps every
+    /// variable within a tolerance of its bounds, then among the positions
+    /// blocking within it take the largest pivot. Variables outside their
+    /// bounds block where they reach them. `None` if nothing blocks.
+    fn ratio_test(&self, alpha: &[f64], direction: f64, bland: bool) -> Option<(usize, f64)> {
+        // For each blocking position: the rate it moves at and its distance to the bound it moves toward.
+        let blocking = |p: usize| -> Option<(f64, f64)> {
+            let rate = -direction * alpha[p];
+            if rate.abs() < PIVOT {
+                return None;
+            }
+            let value = self.values[p];
+            match self.bounds[self.basis[p]] {
+                Bound::Free => None,
+                Bound::Lower if value < -FEASIBILITY => (rate > 0.0).then_some((rate, -value)),
+                Bound::Lower => (rate < 0.0).then_some((rate, value.max(0.0))),
+                Bound::Fixed if value > FEASIBILITY => (rate < 0.0).then_some((rate, value)),
+                Bound::Fixed if value < -FEASIBILITY => (rate > 0.0).then_some((rate, -value)),
+                Bound::Fixed => Some((rate, 0.0)),
+            }
+        };
+
+        let mut limit = f64::INFINITY;
+        for p in 0..self.rows {
+            if let Some((rate, distance)) = blocking(p) {
+                limit = limit.min((distance + FEASIBILITY) / rate.abs());
+            }
+        }
+        if limit == f64::INFINITY {
+            return None;
+        }
+        let mut best: Option<(usize, f64, f64)> = None;
+        for p in 0..self.rows {
+            if let Some((rate, distance)) = blocking(p) {
+                let ratio = distance / rate.abs();
+                if ratio > limit {
+                    continue;
+                }
+                let better = match best {
+                    None => true,
+                    // Bland: the nearest bound, ties to the lowest variable.
+                    Some((q, best_ratio, _)) if bland => ratio < best_ratio || (ratio == best_ratio && self.basis[p] < self.basis[q]),
+                    Some((_, _, best_rate)) => rate.abs() > best_rate,
+                };
+                if better {
+                    best = Some((p, ratio, rate.abs()));
+                }
+            }
+        }
+        best.map(|(p, ratio, _)| (p, ratio))
+    }
+}
+
+/// `B = L U` up to row and column order, kept as the row operations that
+/// eliminated `B` and the rows of `U` in pivot order, followed by the
+/// product-form updates of every pivot since. Vectors are dense: over rows
+/// going in, over basis positions coming out of `solve`, and the other way
+/// round for `solve_transposed`.
+#[derive(Default)]
+struct Factor {
+    /// Elimination steps: pivot row, then the (row, multiplier) pairs in `lower_entries[start..end]`.
+    lower: Vec<(usize, usize, usize)>,
+    lower_entries: Vec<(usize, f64)>,
+    /// `U` rows in pivot order: pivot row, basis position, diagonal, and the
+    /// off-diagonal (position, value) pairs in `upper_entries[start..end]`.
+    upper: Vec<(usize, usize, f64, usize, usize)>,
+    upper_entries: Vec<(usize, f64)>,
+    /// Updates: leaving position, its pivot, and the other (position, alpha) pairs.
+    etas: Vec<(usize, f64, usize, usize)>,
+    eta_entries: Vec<(usize, f64)>,
+}
+
+impl Factor {
+    /// Factorizes the basis whose position `k` holds `columns[k]`, choosing
+    /// pivots by Markowitz count under a threshold. Returns the factors and,
+    /// if the basis is singular, the (position, row) pairs left unpivoted,
+    /// whose columns it has replaced by the rows' unit columns.
+    fn new(rows: usize, mut columns: Vec<Vec<(usize, f64)>>) -> (Self, Vec<(usize, usize)>) {
+        let mut factor = Factor::default();
+        // The active submatrix: exact row lists, and column lists that may name rows already pivoted.
+        let mut row_lists: Vec<Vec<(usize, f64)>> = vec![Vec::new(); rows];
+        let mut col_lists: Vec<Vec<usize>> = vec![Vec::new(); rows];
+        for (position, column) in columns.iter().enumerate() {
+            for &(row, value) in column {
+                if value != 0.0 {
+                    row_lists[row].push((position, value));
+                    col_lists[position].push(row);
+                }
+            }
+        }
+        let mut row_done = vec![false; rows];
+        let mut col_done = vec![false; rows];
+        let mut col_count: Vec<usize> = col_lists.iter().map(Vec::len).collect();
+        // Positions by active count; entries go stale as counts change and are skipped.
+        let mut buckets: Vec<Vec<usize>> = vec![Vec::new(); rows + 2];
+        for position in 0..rows {
+            buckets[col_count[position].min(rows + 1)].push(position);
+        }
+        let mut slot = vec![usize::MAX; rows];
+        let mut replaced = Vec::new();
+        let mut lowest = 0;
+
+        for _ in 0..rows {
+            // The sparsest active column, and in it the acceptable pivot whose row is shortest.
+            let mut pivot = None;
+            let mut searched = 0;
+            let mut count = lowest;
+            'search: while count <= rows + 1 {
+                let mut i = 0;
+                while i < buckets[count].len() {
+                    let position = buckets[count][i];
+                    if col_done[position] || col_count[position] != count {
+                        buckets[count].swap_remove(i);
+                        continue;
+                    }
+                    i += 1;
+                    if count == 0 {
+                        continue;
+                    }
+                    let entries: Vec<(usize, f64)> = col_lists[position]
+                        .iter()
+                        .filter(|&&row| !row_done[row])
+                        .filter_map(|&row| row_lists[row].iter().find(|e| e.0 == position).map(|e| (row, e.1)))
+                        .collect();
+                    let largest = entries.iter().map(|e| e.1.abs()).fold(0.0, f64::max);
+                    let choice = entries
+                        .iter()
+                        .filter(|e| e.1.abs() >= PIVOT_THRESHOLD * largest && e.1.abs() > 1e-11)
+                        .min_by_key(|e| row_lists[e.0].len());
+                    if let Some(&(row, value)) = choice {
+                        let merit = (row_lists[row].len() - 1) * (count - 1);
+                        if pivot.is_none_or(|(_, _, _, best)| merit < best) {
+                            pivot = Some((row, position, value, merit));
+                        }
+                        searched += 1;
+                        if merit == 0 || searched >= 4 {
+                            break 'search;
+                        }
+                    }
+                }
+                if pivot.is_some() {
+                    break;
+                }
+                count += 1;
+            }
+            lowest = if pivot.is_some() { count.saturating_sub(1).max(1) } else { lowest };
+            let Some((row, position, value, _)) = pivot else {
+                break;
+            };
+
+            // Record the U row, then eliminate the column from every other active row.
+            row_done[row] = true;
+            col_done[position] = true;
+            let pivot_row: Vec<(usize, f64)> = row_lists[row].iter().copied().filter(|e| e.0 != position).collect();
+            let start = factor.upper_entries.len();
+            factor.upper_entries.extend_from_slice(&pivot_row);
+            factor.upper.push((row, position, value, start, factor.upper_entries.len()));
+            for &(other, _) in &pivot_row {
+                col_count[other] -= 1;
+                buckets[col_count[other]].push(other);
+                lowest = lowest.min(col_count[other].max(1));
+            }
+
+            let start = factor.lower_entries.len();
+            let targets: Vec<usize> = col_lists[position].iter().copied().filter(|&r| !row_done[r]).collect();
+            for target in targets {
+                let list = &mut row_lists[target];
+                let Some(at) = list.iter().position(|e| e.0 == position) else {
+                    continue;
+                };
+                let multiplier = list.swap_remove(at).1 / value;
+                factor.lower_entries.push((target, multiplier));
+                for (i, e) in list.iter().enumerate() {
+                    slot[e.0] = i;
+                }
+                for &(other, entry) in &pivot_row {
+                    match slot[other] {
+                        usize::MAX => {
+                            list.push((other, -multiplier * entry));
+                            col_lists[other].push(target);
+                            col_count[other] += 1;
+                            buckets[col_count[other].min(rows + 1)].push(other);
+                        }
+                        i => list[i].1 -= multiplier * entry,
+                    }
+                }
+                for e in list.iter() {
+                    slot[e.0] = usize::MAX;
+                }
+            }
+            factor.lower.push((row, start, factor.lower_entries.len()));
+            row_lists[row].clear();
+        }
+
+        // A singular basis: pair each unpivoted position with an unpivoted row's unit column.
+        let free_rows: Vec<usize> = (0..rows).filter(|&r| !row_done[r]).collect();
+        let free_positions: Vec<usize> = (0..rows).filter(|&p| !col_done[p]).collect();
+        for (&row, &position) in free_rows.iter().zip(&free_positions) {
+            columns[position] = vec![(row, 1.0)];
+            replaced.push((position, row));
+        }
+        if !replaced.is_empty() {
+            let (factor, more) = Factor::new(rows, columns);
+            replaced.extend(more);
+            return (factor, replaced);
+        }
+        (factor, replaced)
+    }
+
+    /// Replaces `x` (over rows) with `B^-1 x` (over positions).
+    fn solve(&self, x: &mut Vec<f64>) {
+        for &(row, start, end) in &self.lower {
+            let pivot = x[row];
+            if pivot != 0.0 {
+                for &(target, multiplier) in &self.lower_entries[start..end] {
+                    x[target] -= multiplier * pivot;
+                }
+            }
+        }
+        let mut out = vec![0.0; x.len()];
+        for &(row, position, diagonal, start, end) in self.upper.iter().rev() {
+            let mut sum = x[row];
+            for &(other, value) in &self.upper_entries[start..end] {
+                sum -= value * out[other];
+            }
+            out[position] = sum / diagonal;
+        }
+        for &(leaving, pivot, start, end) in &self.etas {
+            let moved = out[leaving] / pivot;
+            out[leaving] = moved;
+            if moved != 0.0 {
+                for &(position, a) in &self.eta_entries[start..end] {
+                    out[position] -= a * moved;
+                }
+            }
+        }
+        *x = out;
+    }
+
+    /// Replaces `x` (over positions) with `B^-T x` (over rows).
+    fn solve_transposed(&self, x: &mut Vec<f64>) {
+        for &(leaving, pivot, start, end) in self.etas.iter().rev() {
+            let dot: f64 = self.eta_entries[start..end].iter().map(|&(position, a)| a * x[position]).sum();
+            x[leaving] = (x[leaving] - dot) / pivot;
+        }
+        let mut out = vec![0.0; x.len()];
+        for &(row, position, diagonal, start, end) in &self.upper {
+            let z = x[position] / diagonal;
+            out[row] = z;
+            if z != 0.0 {
+                for &(other, value) in &self.upper_entries[start..end] {
+                    x[other] -= value * z;
+                }
+            }
+        }
+        for &(row, start, end) in self.lower.iter().rev() {
+            let dot: f64 = self.lower_entries[start..end].iter().map(|&(target, multiplier)| multiplier * out[target]).sum();
+            out[row] -= dot;
+        }
+        *x = out;
+    }
+
+    /// Records the pivot that put a new column, `alpha = B^-1 a` over
+    /// positions, at position `leaving`.
+    fn update(&mut self, leaving: usize, alpha: &[f64]) {
+        let start = self.eta_entries.len();
+        self.eta_entries.extend(alpha.iter().enumerate().filter(|&(p, a)| p != leaving && *a != 0.0).map(|(p, &a)| (p, a)));
+        self.etas.push((leaving, alpha[leaving], start, self.eta_entries.len()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solves_a_two_by_two_matrix_game() {
+        // The row player of [[2, 0], [0, 1]] mixes 1/3, 2/3 for a value of 2/3:
+        // maximize v subject to v <= 2 x0, v <= x1, x0 + x1 = 1.
+        let (x0, x1, v) = (0, 1, 2);
+        let mut lp = LinearProgram::new(3);
+        lp.set_objective(v, 1.0);
+        lp.add_constraint(vec![(v, 1.0), (x0, -2.0)], Relation::LessEq, 0.0);
+        lp.add_constraint(vec![(v, 1.0), (x1, -1.0)], Relation::LessEq, 0.0);
+        lp.add_constraint(vec![(x0, 1.0), (x1, 1.0)], Relation::Equal, 1.0);
+
+        let (value, solution) = lp.maximize().unwrap();
+        assert!((value - 2.0 / 3.0).abs() < 1e-6, "value {}", value);
+        assert!((solution[x0] - 1.0 / 3.0).abs() < 1e-6 && (solution[x1] - 2.0 / 3.0).abs() < 1e-6, "{:?}", solution);
+    }
+
+    #[test]
+    fn free_variables_take_negative_values() {
+        // [[-1, 0], [0, -2]] is worth -2/3 to the row player, so v must be free.
+        let (x0, x1, v) = (0, 1, 2);
+        let mut lp = LinearProgram::new(3);
+        lp.set_objective(v, 1.0);
+        lp.set_free(v);
+        lp.add_constraint(vec![(v, 1.0), (x0, 1.0)], Relation::LessEq, 0.0);
+        lp.add_constraint(vec![(v, 1.0), (x1, 2.0)], Relation::LessEq, 0.0);
+        lp.add_constraint(vec![(x0, 1.0), (x1, 1.0)], Relation::Equal, 1.0);
+
+        let (value, solution) = lp.maximize().unwrap();
+        assert!((value + 2.0 / 3.0).abs() < 1e-6, "value {}", value);
+        assert!((solution[x0] - 2.0 / 3.0).abs() < 1e-6, "{:?}", solution);
+    }
+
+    #[test]
+    fn reports_infeasible_and_unbounded_programs() {
+        let mut infeasible = LinearProgram::new(1);
+        infeasible.add_constraint(vec![(0, 1.0)], Relation::Equal, -1.0);
+        assert_eq!(infeasible.maximize().unwrap_err(), LpError::Infeasible);
+
+        let mut unbounded = LinearProgram::new(2);
+        unbounded.set_objective(0, 1.0);
+        unbounded.add_constraint(vec![(1, 1.0)], Relation::LessEq, 1.0);
+        assert_eq!(unbounded.maximize().unwrap_err(), LpError::Unbounded);
+    }
+}
@@ -1,101 +1,1287 @@
 mod game;
+mod aggregate;
+mod analyze;
+mod blend;
+mod bundle;
 mod cfr;
+mod checkpoint;
+mod cli;
+mod curriculum;
+mod dashboard;
+mod deals;
+mod engine;
+mod exact;
+mod experiment;
+mod explore;
+mod exploitability;
+mod export;
+mod http;
+mod inspect;
+mod kuhn;
+mod lp;
+mod match_game;
+mod metrics;
+mod openspiel;
+mod opponent;
+mod parquet;
+mod play;
+mod probability;
+mod profile;
+mod query;
+mod reach;
+mod rebel;
+mod record;
+mod refine;
+mod rollout;
+mod server;
+mod sharded;
+mod simd;
+mod simulate;
+mod stats;
+mod strategy;
+mod symmetry;
+mod train;
+mod validate;
+mod verify;
 
-use crate::cfr::{CFRTrainer, CFRNode};
-use crate::game::{Action, GameState};
+use crate::bundle::StrategyBundle;
+use crate::cli::Args;
+use crate::game::{GameConfig, GameState, HistoryAbstraction, Opener, Utility, DICE_FACES};
+use crate::rollout::RolloutFallback;
+use crate::strategy::{read_metadata, save_strategy, strategy_filename, write_atomically, SavePrecision, StrategyTable};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use rayon::prelude::*;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::env;
-use std::fs::File;
 use std::io::Write;
-use std::time::Instant;
+use std::sync::Arc;
 
-fn action_to_str(action: &Action) -> String {
-    match action {
-        Action::Challenge => "Challenge".to_string(),
-        Action::Bid(q, f) => format!("{}-{}", q, f),
+/// Builds the game rules for `p1_dice`v`p2_dice` from the shared rule options.
+fn game_config(args: &Args, p1_dice: u8, p2_dice: u8) -> GameConfig {
+    let mut config = GameConfig::new(p1_dice, p2_dice);
+
+    if let Some(weights) = args.value("face-weights") {
+        let parsed: Vec<f64> = weights
+            .split(',')
+            .map(|w| w.trim().parse().expect("Invalid --face-weights entry"))
+            .collect();
+        let weights: [f64; DICE_FACES as usize] = parsed
+            .try_into()
+            .unwrap_or_else(|_| panic!("--face-weights needs exactly {} comma-separated values", DICE_FACES));
+        config = config.with_face_weights(weights);
+    }
+    if let Some(rules) = args.parse_value("bid-rules") {
+        config.bid_rules = rules;
+    }
+    if let Some(cap) = args.parse_value("max-quantity") {
+        config.quantity_cap = cap;
+    }
+    if let Some(step) = args.parse_value::<u8>("quantity-step") {
+        config.quantity_step = step.max(1);
+    }
+    if let Some(abstraction) = args.parse_value("history") {
+        config.history_abstraction = abstraction;
+    }
+    if let Some(dice_loss) = args.parse_value("dice-loss") {
+        config.dice_loss = dice_loss;
+    }
+    config.calza_reward = args.parse_value("calza");
+    if let Some(opener) = args.parse_value("opener") {
+        config.opener = opener;
+    }
+    if let Some(a) = args.parse_value::<f32>("risk-aversion") {
+        assert!(a >= 0.0, "--risk-aversion must not be negative");
+        config.utility = if a > 0.0 { Utility::RiskAverse(a) } else { Utility::Linear };
+    }
+    if let Some(handicaps) = args.value("handicap") {
+        config.handicaps = handicaps.parse().unwrap_or_else(|e| {
+            eprintln!("Invalid value for --handicap: {}", e);
+            std::process::exit(2);
+        });
+    }
+    if let Some(path) = args.value("refinement") {
+        let refinement = std::fs::read_to_string(path).map_err(|e| e.to_string()).and_then(|contents| contents.parse());
+        config.refinement = Some(Arc::new(refinement.unwrap_or_else(|e| {
+            eprintln!("Invalid refinement file {}: {}", path, e);
+            std::process::exit(2);
+        })));
+    }
+    if let Some(seats) = args.value("teams") {
+        let dice: Vec<u8> = seats
+            .split(',')
+            .map(|d| d.trim().parse().expect("Invalid --teams entry"))
+            .collect();
+        let dice: [u8; 4] = dice.try_into().expect("--teams needs 4 comma-separated dice counts (A1,B1,A2,B2)");
+        assert!(config.opener == Opener::First, "--teams seats team A first; --opener is not supported with teams");
+        config = config.with_teams(dice);
+        assert!(
+            config.dice_p1 == p1_dice && config.dice_p2 == p2_dice,
+            "--teams gives team dice {}v{}, expected {}v{}",
+            config.dice_p1, config.dice_p2, p1_dice, p2_dice
+        );
+    }
+
+    config
+}
+
+/// The one round configuration a fixed opener plays, for commands that walk a
+/// single game tree rooted at player 0's opening bid.
+fn single_opening(config: GameConfig, command: &str) -> GameConfig {
+    let mut openings = config.openings();
+    if openings.len() != 1 {
+        eprintln!("{} needs a fixed opener; --opener {} gives two game trees.", command, config.opener);
+        std::process::exit(2);
+    }
+    openings.remove(0).0
+}
+
+/// Exits when peek handicaps give `command` info sets it cannot build from a
+/// player's own dice (see `GameConfig::require_private_hands`).
+fn require_private_hands(config: &GameConfig, command: &str) {
+    if let Err(e) = config.require_private_hands(command) {
+        eprintln!("{}.", e);
+        std::process::exit(2);
+    }
+}
+
+/// Sizes rayon's global pool from `--threads <n>`, or rayon's default, less
+/// one with `--reserve-core` so the main thread's progress reports, saves and
+/// exploitability checks are not competing with the workers for a core.
+fn configure_threads(args: &Args) {
+    let requested: Option<usize> = args.parse_value("threads");
+    if requested == Some(0) {
+        eprintln!("--threads must be at least 1.");
+        std::process::exit(2);
+    }
+    if requested.is_none() && !args.has("reserve-core") {
+        return;
+    }
+    // Asking rayon for its default would start the global pool, so work it out as rayon does.
+    let default = || {
+        env::var("RAYON_NUM_THREADS")
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .filter(|&n| n > 0)
+            .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()))
+    };
+    let mut threads = requested.unwrap_or_else(default);
+    if args.has("reserve-core") {
+        threads = threads.saturating_sub(1).max(1);
+    }
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build_global()
+        .expect("Unable to configure the thread pool");
+}
+
+fn main() {
+    let raw: Vec<String> = env::args().skip(1).collect();
+    let args = Args::parse(&raw);
+    configure_threads(&args);
+
+    match args.positional.first().map(String::as_str) {
+        Some("validate") => run_validate(&args),
+        Some("openspiel") => run_openspiel(&args),
+        Some("exploitability") => run_exploitability(&args),
+        Some("simulate-match") => run_simulate_match(&args),
+        Some("train-all") => run_train_all(&args),
+        Some("train-match") => run_train_match(&args),
+        Some("rebel") => run_rebel(&args),
+        Some("analyze") => run_analyze(&args),
+        Some("play") => run_play(&args),
+        Some("serve") => run_serve(&args),
+        Some("odds") => run_odds(&args),
+        Some("solve-exact") => run_solve_exact(&args),
+        Some("verify") => run_verify(&args),
+        Some("inspect") => run_inspect(&args),
+        Some("export") => run_export(&args),
+        Some("fit-opponent") => run_fit_opponent(&args),
+        Some("experiment") => run_experiment(&args),
+        Some("refine") => run_refine(&args),
+        Some("query") => run_query(&args),
+        Some("aggregate") => run_aggregate(&args),
+        Some("diff") => run_diff(&args),
+        Some("explore") => run_explore(&args),
+        Some("engine") => run_engine(&args),
+        Some("stats") => run_stats(&args),
+        Some("bench-kernels") => run_bench_kernels(&args),
+        Some("checkpoint") => run_checkpoint(&args),
+        _ => run_train(&args),
+    }
+}
+
+fn run_validate(args: &Args) {
+    if args.positional.len() < 4 {
+        println!("Usage: cargo run validate <strategy_file> <p1_dice> <p2_dice> [--tolerance <sum_tolerance>]");
+        return;
+    }
+
+    let path = &args.positional[1];
+    let p1_dice: u8 = args.positional[2].parse().expect("Invalid p1 dice");
+    let p2_dice: u8 = args.positional[3].parse().expect("Invalid p2 dice");
+    let tolerance: f64 = args.parse_value("tolerance").unwrap_or(0.02);
+
+    println!("Validating {} as a {}v{} strategy...", path, p1_dice, p2_dice);
+    let config = game_config(args, p1_dice, p2_dice);
+    let problems = validate::validate_strategy_file(path, &config, tolerance)
+        .unwrap_or_else(|e| {
+            eprintln!("Unable to read {}: {}", path, e);
+            std::process::exit(2);
+        });
+
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    if problems.is_empty() {
+        println!("No problems found.");
+    } else {
+        println!("{} problem(s) found.", problems.len());
+        std::process::exit(1);
+    }
+}
+
+fn run_exploitability(args: &Args) {
+    if args.positional.len() < 4 {
+        println!("Usage: cargo run exploitability <strategy_file> <p1_dice> <p2_dice> [--by-history [--top <n>] [--output <file.csv>]]");
+        println!("           [rule options]");
+        return;
+    }
+
+    let path = &args.positional[1];
+    let p1_dice: u8 = args.positional[2].parse().expect("Invalid p1 dice");
+    let p2_dice: u8 = args.positional[3].parse().expect("Invalid p2 dice");
+    let config = game_config(args, p1_dice, p2_dice);
+    require_private_hands(&config, "exploitability");
+    let table = StrategyTable::load(path).expect("Unable to read strategy file");
+
+    let mut total = 0.0;
+    for (round, p) in config.openings() {
+        if round.dice_p1 != config.dice_p1 {
+            println!("With the second seat opening ({:.0}% of deals):", p * 100.0);
+        } else if p < 1.0 {
+            println!("With the first seat opening ({:.0}% of deals):", p * 100.0);
+        }
+        let br0 = exploitability::best_response_value(&table, &round, 0);
+        let br1 = exploitability::best_response_value(&table, &round, 1);
+        println!("Best response value for the opener ({} dice): {:.6}", round.dice_p1, br0);
+        println!("Best response value for the responder ({} dice): {:.6}", round.dice_p2, br1);
+        total += p * (br0 + br1) / 2.0;
+    }
+    println!("Exploitability: {:.6}", total);
+
+    if args.has("by-history") || args.has("output") {
+        let states = exploitability::exploitability_by_history(&table, &config);
+        println!();
+        print!("{}", exploitability::GainReport { states: &states, top: args.parse_value("top").unwrap_or(20) });
+        if let Some(output) = args.value("output") {
+            exploitability::write_gains(output, &states).expect("Unable to write exploitability by public state");
+            println!("Wrote {} public states to {}.", states.len(), output);
+        }
+    }
+}
+
+fn run_solve_exact(args: &Args) {
+    if args.positional.len() < 3 {
+        println!("Usage: cargo run solve-exact <p1_dice> <p2_dice> [--output <file>] [--compare <strategy_file>] [rule options]");
+        return;
+    }
+
+    let p1_dice: u8 = args.positional[1].parse().expect("Invalid p1 dice");
+    let p2_dice: u8 = args.positional[2].parse().expect("Invalid p2 dice");
+    let config = game_config(args, p1_dice, p2_dice);
+    let compare = args.value("compare").map(|path| StrategyTable::load(path).expect("Unable to read strategy file"));
+
+    let mut table = StrategyTable::default();
+    for (round, p) in config.openings() {
+        if p < 1.0 {
+            println!("With the {} dice opening ({:.0}% of deals):", round.dice_p1, p * 100.0);
+        }
+        let start_time = std::time::Instant::now();
+        let solution = exact::solve(&round).unwrap_or_else(|e| {
+            eprintln!("Unable to solve {}v{} exactly: {}", round.dice_p1, round.dice_p2, e);
+            std::process::exit(2);
+        });
+        println!(
+            "Solved {}v{} ({} and {} sequences) in {:.2?}.",
+            round.dice_p1, round.dice_p2, solution.sequences[0], solution.sequences[1], start_time.elapsed()
+        );
+        println!("Game value for the opener: {:.6} (duality gap {:.2e})", solution.value, solution.duality_gap);
+
+        // A strategy's worst case in each seat can only fall short of the game value;
+        // the two shortfalls add up to twice its exploitability.
+        if let Some(compared) = &compare {
+            let as_opener = -exploitability::best_response_value(compared, &round, 1);
+            let as_responder = -exploitability::best_response_value(compared, &round, 0);
+            println!("Compared strategy as opener: worst case {:.6}, {:.6} short of the game value", as_opener, solution.value - as_opener);
+            println!("Compared strategy as responder: worst case {:.6}, {:.6} short of the game value", as_responder, -solution.value - as_responder);
+        }
+        table.entries.extend(solution.table.entries);
+    }
+
+    let filename = args.value("output").map_or_else(|| strategy_filename(p1_dice, p2_dice), str::to_string);
+    table.save(&filename, &exact::exact_config(&config)).expect("Unable to write strategy file");
+    println!("Saved the equilibrium ({} full-history info sets) to {}.", table.entries.len(), filename);
+}
+
+fn run_inspect(args: &Args) {
+    if args.positional.len() < 2 || (args.positional[1] == "estimate" && args.positional.len() < 4) {
+        println!("Usage: cargo run inspect <strategy_or_bundle_file>");
+        println!("       cargo run inspect estimate <p1_dice> <p2_dice> [rule options]");
+        return;
     }
+
+    if args.positional[1] == "estimate" {
+        let p1_dice: u8 = args.positional[2].parse().expect("Invalid p1 dice");
+        let p2_dice: u8 = args.positional[3].parse().expect("Invalid p2 dice");
+        let config = game_config(args, p1_dice, p2_dice);
+        print!("{}", strategy::format_metadata(&config));
+        let estimate = inspect::SizeEstimate::for_config(&config);
+        let threads = rayon::current_num_threads();
+        println!("Info sets: {:.0}", estimate.info_sets);
+        println!("Action slots: {:.0} ({:.2} per info set)", estimate.action_slots, estimate.action_slots / estimate.info_sets.max(1.0));
+        println!("Node map: {}", inspect::mib(estimate.node_map_bytes()));
+        println!("Training on {} threads (one node map each): up to {}", threads, inspect::mib(estimate.node_map_bytes() * threads as f64));
+        println!("Strategy CSV: up to {}", inspect::mib(estimate.csv_bytes()));
+        return;
+    }
+
+    let path = &args.positional[1];
+    let contents = std::fs::read(path).unwrap_or_else(|e| {
+        eprintln!("Unable to read {}: {}", path, e);
+        std::process::exit(2);
+    });
+
+    if contents.starts_with(bundle::BUNDLE_MAGIC.as_bytes()) {
+        let bundle = StrategyBundle::load_file(path).unwrap_or_else(|e| {
+            eprintln!("Unable to read bundle {}: {}", path, e);
+            std::process::exit(2);
+        });
+        println!("Strategy bundle with {} configuration(s).", bundle.tables.len());
+        if let Some(rules) = &bundle.rules {
+            print!("{}", strategy::format_metadata(rules));
+        }
+        let mut keys: Vec<&(u8, u8)> = bundle.tables.keys().collect();
+        keys.sort();
+        let mut sections = 0;
+        for key in keys {
+            let table = &bundle.tables[key];
+            let mut csv = Vec::new();
+            table.write_csv(&mut csv).expect("Unable to measure bundle section");
+            sections += csv.len();
+            let summary = inspect::TableSummary::collect(table);
+            println!(
+                "{}v{}: {} info sets, {} action rows, {} bytes on disk, {} to load",
+                key.0, key.1, summary.info_sets, summary.rows, csv.len(), inspect::mib(summary.memory_bytes as f64)
+            );
+        }
+        println!("File size: {} bytes ({} in the header and index)", contents.len(), contents.len().saturating_sub(sections));
+        return;
+    }
+
+    match read_metadata(path) {
+        Ok(config) => print!("{}", strategy::format_metadata(&config)),
+        Err(_) => println!("No metadata found next to {}.", path),
+    }
+    if strategy::read_average_only(path) {
+        println!("Trained average-only: regrets were discarded, so training cannot resume from this strategy.");
+    }
+    let table = StrategyTable::from_reader(contents.as_slice()).unwrap_or_else(|e| {
+        eprintln!("Unable to read strategy file {}: {}", path, e);
+        std::process::exit(2);
+    });
+    print!("{}", inspect::TableSummary::collect(&table));
+    print!("{}", inspect::CsvBreakdown::collect(&String::from_utf8_lossy(&contents)));
+}
+
+fn run_export(args: &Args) {
+    if args.positional.len() < 3 {
+        println!("Usage: cargo run export <strategy_file> <output.json|output.parquet> [--format <json|parquet>]");
+        return;
+    }
+
+    let input = &args.positional[1];
+    let output = &args.positional[2];
+    let format = args.parse_value("format").or_else(|| export::ExportFormat::from_path(output)).unwrap_or_else(|| {
+        eprintln!("Cannot tell the export format from {}; pass --format json or --format parquet.", output);
+        std::process::exit(2);
+    });
+    let table = StrategyTable::load(input).expect("Unable to read strategy file");
+    let rules = read_metadata(input).ok();
+
+    write_atomically(output, |file| match format {
+        export::ExportFormat::Json => export::write_json(file, &table, rules.as_ref()),
+        export::ExportFormat::Parquet => export::write_parquet(file, &table, rules.as_ref()),
+    })
+    .expect("Unable to write export");
+    println!("Exported {} info sets to {}.", table.entries.len(), output);
+}
+
+fn run_fit_opponent(args: &Args) {
+    if args.positional.len() < 6 {
+        println!("Usage: cargo run fit-opponent <records_file> <player> <p1_dice> <p2_dice> <output> [--smoothing <pseudo_count>] [rule options]");
+        return;
+    }
+
+    let records = record::read_records(&args.positional[1]).unwrap_or_else(|e| {
+        eprintln!("Unable to read {}: {}", args.positional[1], e);
+        std::process::exit(2);
+    });
+    let player = &args.positional[2];
+    let p1_dice: u8 = args.positional[3].parse().expect("Invalid P1 dice");
+    let p2_dice: u8 = args.positional[4].parse().expect("Invalid P2 dice");
+    let output = &args.positional[5];
+    let config = game_config(args, p1_dice, p2_dice);
+    require_private_hands(&config, "fit-opponent");
+    let smoothing: f64 = args.parse_value("smoothing").unwrap_or(1.0);
+
+    let model = opponent::OpponentModel::fit(&records, &config, player);
+    if model.invalid > 0 {
+        println!("Skipped {} records with actions that are illegal under these rules.", model.invalid);
+    }
+    if model.decisions == 0 {
+        eprintln!("{} makes no {}v{} decisions in {}.", player, p1_dice, p2_dice, args.positional[1]);
+        std::process::exit(2);
+    }
+    model.to_table(smoothing).save(output, &config).expect("Unable to write opponent model");
+    println!(
+        "Fitted {} decisions of {} from {} records at {} info sets into {}.",
+        model.decisions, player, model.records, model.info_sets(), output
+    );
+}
+
+fn run_experiment(args: &Args) {
+    if args.positional.len() < 4 {
+        println!("Usage: cargo run experiment <p1_dice> <p2_dice> <iterations> [--runs <n>] [--games <rounds_per_pair>] [--duplicate]");
+        println!("           [--results <dir>] [training and rule options]");
+        return;
+    }
+
+    let p1_dice: u8 = args.positional[1].parse().expect("Invalid P1 dice");
+    let p2_dice: u8 = args.positional[2].parse().expect("Invalid P2 dice");
+    let iterations: usize = args.positional[3].parse().expect("Invalid iterations");
+    let runs: usize = args.parse_value("runs").unwrap_or(5);
+    let games: usize = args.parse_value("games").unwrap_or(10_000);
+    let dir = args.value("results").map_or_else(|| format!("../experiments/{}v{}", p1_dice, p2_dice), str::to_string);
+    let config = game_config(args, p1_dice, p2_dice);
+    require_private_hands(&config, "experiment");
+
+    let experiment = experiment::Experiment::run(args, &config, iterations, runs, games, &dir).unwrap_or_else(|e| {
+        eprintln!("Unable to write to {}: {}", dir, e);
+        std::process::exit(2);
+    });
+    experiment.write(&dir).expect("Unable to write experiment results");
+    println!();
+    print!("{}", experiment);
+    println!("Wrote results to {}.", dir);
 }
 
-fn save_strategy(nodes: &HashMap<String, CFRNode>, n_dice_p1: u8, n_dice_p2: u8) {
-    let filename = format!("../strategy_{}v{}.csv", n_dice_p1, n_dice_p2);
-    println!("Saving strategy to {}...", filename);
+fn run_refine(args: &Args) {
+    if args.positional.len() < 4 {
+        println!("Usage: cargo run refine <p1_dice> <p2_dice> <iterations_per_round> [--rounds <n>] [--split-share <fraction>]");
+        println!("           [--exploitability] [training and rule options]");
+        return;
+    }
+
+    let p1_dice: u8 = args.positional[1].parse().expect("Invalid P1 dice");
+    let p2_dice: u8 = args.positional[2].parse().expect("Invalid P2 dice");
+    let iterations: usize = args.positional[3].parse().expect("Invalid iterations");
+    let rounds: usize = args.parse_value("rounds").unwrap_or(3);
+    let share: f64 = args.parse_value("split-share").unwrap_or(0.25);
+    if rounds == 0 || !(share > 0.0 && share <= 1.0) {
+        eprintln!("--rounds must be at least 1 and --split-share above 0 and at most 1.");
+        std::process::exit(2);
+    }
+    let config = game_config(args, p1_dice, p2_dice);
+    if config.history_abstraction == HistoryAbstraction::Full {
+        eprintln!("Full recall already remembers every bid; refine a --history last:k or pressure abstraction.");
+        std::process::exit(2);
+    }
+    if args.has("out-of-core") {
+        eprintln!("refine keeps every round's nodes in memory; it cannot be combined with --out-of-core.");
+        std::process::exit(2);
+    }
+    if args.has("exploitability") {
+        require_private_hands(&config, "--exploitability");
+    }
+
+    let (config, nodes) = refine::refine(args, &config, iterations, rounds, share);
+    let filename = strategy_filename(p1_dice, p2_dice);
+    save_strategy(&nodes, &config, &filename, SavePrecision::from_args(args));
+    if let Some(refinement) = &config.refinement {
+        let path = refine::refinement_filename(&filename);
+        write_atomically(&path, |file| writeln!(file, "{}", refinement.to_string().replace(',', "\n"))).expect("Unable to write refinement file");
+        println!("Wrote the {} split histories to {}; pass --refinement {} to commands that take rule options.", refinement.len(), path, path);
+    }
+}
+
+fn run_query(args: &Args) {
+    if args.positional.len() < 3 {
+        println!("Usage: cargo run query <strategy_file> <situations.csv> [<p1_dice> <p2_dice>] [--output <file>] [--rollouts <n>] [--temperature <t>] [rule options]");
+        println!("       (situations.csv columns: hand, bids as \"1-2/2-5\", optional opponent_dice)");
+        return;
+    }
+
+    let path = &args.positional[1];
+    let table = StrategyTable::load(path).expect("Unable to read strategy file");
+    let rules = read_metadata(path).unwrap_or_else(|_| {
+        if args.positional.len() < 5 {
+            eprintln!("{} has no rules metadata; give <p1_dice> <p2_dice> and the rule options it was solved with.", path);
+            std::process::exit(2);
+        }
+        let p1_dice: u8 = args.positional[3].parse().expect("Invalid p1 dice");
+        let p2_dice: u8 = args.positional[4].parse().expect("Invalid p2 dice");
+        game_config(args, p1_dice, p2_dice)
+    });
+    require_private_hands(&rules, "query");
+    let input = std::fs::File::open(&args.positional[2]).unwrap_or_else(|e| {
+        eprintln!("Unable to read {}: {}", args.positional[2], e);
+        std::process::exit(2);
+    });
+    let fallback = rollout_fallback(args, &rules);
+
+    let result = match args.value("output") {
+        Some(output) => {
+            let mut counts = (0, 0);
+            write_atomically(output, |file| {
+                counts = query::answer_csv(&table, &rules, fallback.as_ref(), temperature(args), input, file)?;
+                Ok(())
+            })
+            .map(|_| counts)
+        }
+        None => query::answer_csv(&table, &rules, fallback.as_ref(), temperature(args), input, &mut std::io::stdout().lock()),
+    };
+    match result {
+        Ok((answered, skipped)) => eprintln!("Answered {} situations ({} skipped).", answered, skipped),
+        Err(e) => {
+            eprintln!("Unable to answer {}: {}", args.positional[2], e);
+            std::process::exit(2);
+        }
+    }
+}
+
+fn run_aggregate(args: &Args) {
+    if args.positional.len() < 2 {
+        println!("Usage: cargo run aggregate <strategy_file> [<p1_dice> <p2_dice>] [--max-depth <bids>] [--min-reach <probability>]");
+        println!("           [--output <dir>] [rule options]");
+        return;
+    }
+
+    let path = &args.positional[1];
+    let table = StrategyTable::load(path).expect("Unable to read strategy file");
+    let rules = read_metadata(path).unwrap_or_else(|_| {
+        if args.positional.len() < 4 {
+            eprintln!("{} has no rules metadata; give <p1_dice> <p2_dice> and the rule options it was solved with.", path);
+            std::process::exit(2);
+        }
+        let p1_dice: u8 = args.positional[2].parse().expect("Invalid p1 dice");
+        let p2_dice: u8 = args.positional[3].parse().expect("Invalid p2 dice");
+        game_config(args, p1_dice, p2_dice)
+    });
+    require_private_hands(&rules, "aggregate");
 
-    let mut file = File::create(filename).expect("Unable to create file");
-    writeln!(file, "InfoSet,Action,Probability").expect("Unable to write header");
+    let aggregates = aggregate::Aggregates::collect(&table, &rules, args.parse_value("max-depth"));
+    print!("{}", aggregates.report(args.parse_value("min-reach").unwrap_or(0.01)));
+    if let Some(dir) = args.value("output") {
+        aggregates.write(dir).expect("Unable to write aggregates");
+        println!("Wrote {} public states to {}/states.csv, with depths.csv and bids.csv.", aggregates.states.len(), dir);
+    }
+}
 
-    for (info_set, node) in nodes {
-        let avg_strategy = node.get_average_strategy();
+fn run_explore(args: &Args) {
+    if args.positional.len() < 2 {
+        println!("Usage: cargo run explore <strategy_file> [<p1_dice> <p2_dice>] [--bookmarks <file>] [rule options]");
+        return;
+    }
 
-        // Reconstruct actions
-        let parts: Vec<&str> = info_set.split('|').collect();
-        let bid_str = parts[1];
-        
-        let mut dummy_game = GameState::new(n_dice_p1, n_dice_p2);
-        if bid_str != "None" {
-            let b_parts: Vec<&str> = bid_str.split('-').collect();
-            let q = b_parts[0].parse::<u8>().unwrap();
-            let f = b_parts[1].parse::<u8>().unwrap();
-            dummy_game.current_bid = Some((q, f));
-        } else {
-            dummy_game.current_bid = None;
+    let path = &args.positional[1];
+    let table = StrategyTable::load(path).expect("Unable to read strategy file");
+    let rules = read_metadata(path).unwrap_or_else(|_| {
+        if args.positional.len() < 4 {
+            eprintln!("{} has no rules metadata; give <p1_dice> <p2_dice> and the rule options it was solved with.", path);
+            std::process::exit(2);
         }
+        let p1_dice: u8 = args.positional[2].parse().expect("Invalid p1 dice");
+        let p2_dice: u8 = args.positional[3].parse().expect("Invalid p2 dice");
+        game_config(args, p1_dice, p2_dice)
+    });
+    require_private_hands(&rules, "explore");
 
-        let valid_actions = dummy_game.get_valid_actions();
+    explore::explore_session(&table, &rules, args.value("bookmarks")).expect("Unable to run explorer");
+}
 
-        for (i, prob) in avg_strategy.iter().enumerate() {
-            if *prob > 0.001 {
-                let action_str = action_to_str(&valid_actions[i]);
-                writeln!(file, "{},{},{}", info_set, action_str, prob).unwrap();
+fn run_engine(args: &Args) {
+    if args.positional.len() < 2 {
+        println!("Usage: cargo run engine <bundle_or_dir> [--max-dice <n>] [--rollouts <n>] [--temperature <t>] [--seed <n>]");
+        println!("           [rule options]");
+        println!("       (speaks a line protocol on stdin/stdout; send 'liarsdice' to start, see engine.rs)");
+        return;
+    }
+
+    let path = &args.positional[1];
+    let bundle = StrategyBundle::load(path, args.parse_value("max-dice").unwrap_or(2)).unwrap_or_else(|e| {
+        eprintln!("Unable to load strategy bundle {}: {}", path, e);
+        std::process::exit(2);
+    });
+    let rules = bundle.rules.clone().unwrap_or_else(|| game_config(args, 0, 0));
+    require_private_hands(&rules, "the engine");
+    assert!(rules.seat_dice.is_none(), "the engine plays one seat against one opponent; --teams is not supported");
+    let fallback = rollout_fallback(args, &rules);
+    let rng = StdRng::seed_from_u64(args.parse_value("seed").unwrap_or_else(rand::random));
+
+    let mut engine = engine::Engine::new(&bundle, &rules, fallback.as_ref(), temperature(args), rng);
+    engine.run(std::io::stdin().lock(), &mut std::io::stdout().lock()).expect("Engine I/O failed");
+}
+
+fn run_diff(args: &Args) {
+    if args.positional.len() < 3 {
+        println!("Usage: cargo run diff <strategy_a> <strategy_b> [<p1_dice> <p2_dice>] [--top <n>] [rule options]");
+        return;
+    }
+
+    let (path_a, path_b) = (&args.positional[1], &args.positional[2]);
+    let a = StrategyTable::load(path_a).expect("Unable to read strategy file");
+    let b = StrategyTable::load(path_b).expect("Unable to read strategy file");
+    let rules = read_metadata(path_a).unwrap_or_else(|_| {
+        if args.positional.len() < 5 {
+            eprintln!("{} has no rules metadata; give <p1_dice> <p2_dice> and the rule options it was solved with.", path_a);
+            std::process::exit(2);
+        }
+        let p1_dice: u8 = args.positional[3].parse().expect("Invalid p1 dice");
+        let p2_dice: u8 = args.positional[4].parse().expect("Invalid p2 dice");
+        game_config(args, p1_dice, p2_dice)
+    });
+    require_private_hands(&rules, "diff");
+
+    // Weighted by where the first strategy plays, so lines it never reaches do not count.
+    let distance = metrics::StrategyDistance::between(&a, &b, &a, &rules);
+    print!("{}", distance);
+    let top: usize = args.parse_value("top").unwrap_or(10);
+    if top > 0 && !distance.by_info_set.is_empty() {
+        println!("Largest differences (reach weight, total variation):");
+        for (info_set, weight, tv) in distance.by_info_set.iter().take(top) {
+            println!("  {:<24} {:.6} {:.4}", info_set, weight, tv);
+        }
+    }
+}
+
+fn run_verify(args: &Args) {
+    let iterations: usize = args.parse_value("iterations").unwrap_or(100_000);
+    let tolerance: f64 = args.parse_value("tolerance").unwrap_or_else(|| verify::default_tolerance(iterations));
+
+    let checks = verify::run(iterations, tolerance).unwrap_or_else(|e| {
+        eprintln!("Unable to solve a reference game: {}", e);
+        std::process::exit(2);
+    });
+
+    println!();
+    println!("{:<24} {:<22} {:>10} {:>10} {:>14}", "Game", "Variant", "Exact", "Trained", "Exploitability");
+    for check in &checks {
+        println!(
+            "{:<24} {:<22} {:>10.6} {:>10.6} {:>14.6}{}",
+            check.game, check.variant, check.game_value, check.estimated_value, check.exploitability,
+            if check.passed { "" } else { "  FAILED" }
+        );
+    }
+    println!("(Kuhn poker is a sanity check of a standalone CFR+ on a game with a known value, not of the trainer above.)");
+    let failed = checks.iter().filter(|c| !c.passed).count();
+    if failed > 0 {
+        println!("{} of {} checks failed (tolerance {:.4}).", failed, checks.len(), tolerance);
+        std::process::exit(1);
+    }
+    println!("All {} checks passed (tolerance {:.4}).", checks.len(), tolerance);
+}
+
+/// Exact equilibria for every round of a match with at most `max_total` dice
+/// in play, for `simulate::play_match` to use in place of the bundles.
+fn exact_endgame(rules: &GameConfig, start_dice: u8, max_total: u8) -> StrategyBundle {
+    let mut endgame = StrategyBundle::default();
+    for opener_dice in 1..=start_dice {
+        for other_dice in 1..=start_dice {
+            if opener_dice + other_dice > max_total {
+                continue;
             }
+            let mut round = rules.opening(false);
+            round.dice_p1 = opener_dice;
+            round.dice_p2 = other_dice;
+            let solution = exact::solve(&round).unwrap_or_else(|e| {
+                eprintln!("Unable to solve the {}v{} endgame exactly: {}", opener_dice, other_dice, e);
+                std::process::exit(2);
+            });
+            println!("Solved the {}v{} endgame exactly (value {:.4} for the opener).", opener_dice, other_dice, solution.value);
+            endgame.tables.insert((opener_dice, other_dice), solution.table);
+        }
+    }
+    endgame
+}
+
+fn run_simulate_match(args: &Args) {
+    if args.positional.len() < 3 {
+        println!("Usage: cargo run simulate-match <bundle_a> <bundle_b> [--start-dice <n>] [--matches <n>] [--record <file>] [--deal-script <file>]");
+        println!("           [--exact-endgame <total_dice>] [--duplicate] [--seed <n>] [rule options]");
+        return;
+    }
+
+    let start_dice: u8 = args.parse_value("start-dice").unwrap_or(2);
+    let matches: usize = args.parse_value("matches").unwrap_or(1000);
+    let rules = game_config(args, start_dice, start_dice);
+    let load = |path: &str| {
+        StrategyBundle::load(path, start_dice).unwrap_or_else(|e| {
+            eprintln!("Unable to load strategy bundle {}: {}", path, e);
+            std::process::exit(2);
+        })
+    };
+    let bundle_a = load(&args.positional[1]);
+    let bundle_b = load(&args.positional[2]);
+    let deals = args.value("deal-script").map(|path| {
+        deals::DealScript::load(path).unwrap_or_else(|e| {
+            eprintln!("Unable to read deal script {}: {}", path, e);
+            std::process::exit(2);
+        })
+    });
+
+    let endgame = args.parse_value("exact-endgame").map(|max_total| exact_endgame(&rules, start_dice, max_total));
+
+    if args.has("duplicate") {
+        if deals.is_some() {
+            eprintln!("--duplicate deals each pair of matches from a shared seed; it cannot be combined with --deal-script.");
+            std::process::exit(2);
+        }
+        let pairs = matches.div_ceil(2);
+        let seed: u64 = args.parse_value("seed").unwrap_or_else(rand::random);
+        println!("Simulating {} duplicate pairs of matches from {} dice each...", pairs, start_dice);
+        // Both matches of a pair share the deals and the first opener; only the seats of the bundles swap.
+        let results: Vec<[simulate::MatchResult; 2]> = (0..pairs)
+            .into_par_iter()
+            .map(|i| {
+                let deals = simulate::MatchDeals::Seeded(seed.wrapping_add(i as u64));
+                let mut rng = rand::thread_rng();
+                [
+                    simulate::play_match([&bundle_a, &bundle_b], &rules, start_dice, i % 2, deals, endgame.as_ref(), &mut rng),
+                    simulate::play_match([&bundle_b, &bundle_a], &rules, start_dice, i % 2, deals, endgame.as_ref(), &mut rng),
+                ]
+            })
+            .collect();
+
+        let scores: Vec<f64> = results.iter().map(|[first, second]| ((first.winner == 0) as u8 + (second.winner == 1) as u8) as f64 / 2.0).collect();
+        let (win_rate, stddev) = experiment::mean_and_stddev(&scores);
+        let margin = 1.96 * stddev / (pairs as f64).sqrt();
+        let split = scores.iter().filter(|&&s| s == 0.5).count();
+        println!("Bundle A duplicate score: {:.2}% +/- {:.2}%", win_rate * 100.0, margin * 100.0);
+        println!("Pairs split one match each: {} of {}", split, pairs);
+        let avg_rounds = results.iter().flatten().map(|r| r.rounds).sum::<usize>() as f64 / (2 * pairs) as f64;
+        println!("Average rounds per match: {:.2}", avg_rounds);
+        if let Some(path) = args.value("record") {
+            // The second match of each pair had the bundles seated the other way round.
+            let records: Vec<_> = results
+                .into_iter()
+                .flat_map(|[first, second]| {
+                    let swapped = second.records.into_iter().map(|mut r| {
+                        r.players = r.players.map(|p| if p == "A" { "B".to_string() } else { "A".to_string() });
+                        r
+                    });
+                    first.records.into_iter().chain(swapped).collect::<Vec<_>>()
+                })
+                .collect();
+            record::write_records(path, &records).expect("Unable to write game records");
+            println!("Wrote {} round records to {}.", records.len(), path);
         }
+        return;
+    }
+
+    println!("Simulating {} matches from {} dice each...", matches, start_dice);
+    // Alternate who opens the first round so neither bundle keeps the opening seat.
+    let match_deals = deals.as_ref().map_or(simulate::MatchDeals::Random, simulate::MatchDeals::Script);
+    let results: Vec<simulate::MatchResult> = (0..matches)
+        .into_par_iter()
+        .map(|i| simulate::play_match([&bundle_a, &bundle_b], &rules, start_dice, i % 2, match_deals, endgame.as_ref(), &mut rand::thread_rng()))
+        .collect();
+
+    let wins_a = results.iter().filter(|r| r.winner == 0).count();
+    let win_rate = wins_a as f64 / matches as f64;
+    let margin = 1.96 * (win_rate * (1.0 - win_rate) / matches as f64).sqrt();
+    let avg_rounds = results.iter().map(|r| r.rounds).sum::<usize>() as f64 / matches as f64;
+
+    println!("Bundle A wins: {} ({:.2}% +/- {:.2}%)", wins_a, win_rate * 100.0, margin * 100.0);
+    println!("Bundle B wins: {} ({:.2}%)", matches - wins_a, (1.0 - win_rate) * 100.0);
+    println!("Average rounds per match: {:.2}", avg_rounds);
+
+    if let Some(path) = args.value("record") {
+        let records: Vec<_> = results.into_iter().flat_map(|r| r.records).collect();
+        record::write_records(path, &records).expect("Unable to write game records");
+        println!("Wrote {} round records to {}.", records.len(), path);
     }
-    println!("Save complete.");
 }
 
-fn merge_nodes(mut map1: HashMap<String, CFRNode>, map2: HashMap<String, CFRNode>) -> HashMap<String, CFRNode> {
-    for (key, node2) in map2 {
-        let node1 = map1.entry(key).or_insert_with(|| CFRNode::new(node2.num_actions));
-        
-        for i in 0..node1.num_actions {
-            node1.regret_sum[i] += node2.regret_sum[i];
-            node1.strategy_sum[i] += node2.strategy_sum[i];
+fn run_play(args: &Args) {
+    if args.positional.len() < 3 {
+        println!("Usage: cargo run play <p1_dice> <p2_dice> [--strategy <file[:weight],...>] [--blend <decision|game>] [--hints]");
+        println!("           [--threshold <percent_of_a_die>] [--record <file>] [--rollouts <n>] [--temperature <t>] [--profile <file|none>]");
+        println!("           [rule options]");
+        return;
+    }
+
+    let p1_dice: u8 = args.positional[1].parse().expect("Invalid p1 dice");
+    let p2_dice: u8 = args.positional[2].parse().expect("Invalid p2 dice");
+    let config = game_config(args, p1_dice, p2_dice);
+    if args.has("hints") {
+        require_private_hands(&config, "--hints");
+    }
+    let spec = args.value("strategy").map_or_else(|| strategy_filename(p1_dice, p2_dice), str::to_string);
+    let blend = match blend::StrategyBlend::load(&spec, args.parse_value("blend").unwrap_or_default()) {
+        Ok(blend) => blend,
+        Err(e) => {
+            println!("No strategy found at {} ({}). Please train first.", spec, e);
+            return;
         }
+    };
+    let options = play::PlayOptions {
+        hints: args.has("hints"),
+        threshold: args.parse_value::<f64>("threshold").unwrap_or(5.0) / 100.0,
+        record: args.value("record").map(str::to_string),
+        fallback: rollout_fallback(args, &config),
+        temperature: temperature(args),
+        profile: match args.value("profile") {
+            Some("none") => None,
+            path => Some(path.unwrap_or(profile::DEFAULT_PROFILE).to_string()),
+        },
+    };
+
+    println!("Starting game {}v{} against Bot!", p1_dice, p2_dice);
+    play::play_session(&blend, &config, &options).expect("Unable to run play session");
+}
+
+/// Rollouts where a strategy is uninformed, unless `--rollouts 0` turns them off.
+fn rollout_fallback(args: &Args, rules: &GameConfig) -> Option<RolloutFallback> {
+    let rollouts = args.parse_value("rollouts").unwrap_or(rollout::DEFAULT_ROLLOUTS);
+    (rollouts > 0).then(|| RolloutFallback::new(rules, rollouts))
+}
+
+/// `--temperature <t>` for sampling the bot's moves; 1, the strategy as solved, by default.
+fn temperature(args: &Args) -> f64 {
+    let temperature: f64 = args.parse_value("temperature").unwrap_or(1.0);
+    if temperature.is_nan() || temperature < 0.0 {
+        eprintln!("--temperature must be at least 0.");
+        std::process::exit(2);
     }
-    map1
+    temperature
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 4 {
-        println!("Usage: cargo run <p1_dice> <p2_dice> <iterations>");
+fn run_stats(args: &Args) {
+    let path = args.positional.get(1).map_or(profile::DEFAULT_PROFILE, String::as_str);
+    match profile::read_profile(path) {
+        Ok(entries) if entries.is_empty() => println!("{} has no games yet.", path),
+        Ok(entries) => print!("{}", profile::ProfileStats::collect(&entries)),
+        Err(e) => {
+            eprintln!("Unable to read profile {}: {}", path, e);
+            std::process::exit(2);
+        }
+    }
+}
+
+fn run_checkpoint(args: &Args) {
+    if args.positional.len() < 4 || args.positional[1] != "verify" {
+        println!("Usage: cargo run checkpoint verify <p1_dice> <p2_dice> [--dir <checkpoint_dir>] [--warmup <iterations>] [--iterations <n>]");
+        println!("           [--fuzz <cases>] [--seed <n>] [--shards <n>] [--cache-shards <n>] [training and rule options]");
         return;
     }
 
-    let p1_dice: u8 = args[1].parse().expect("Invalid p1 dice");
-    let p2_dice: u8 = args[2].parse().expect("Invalid p2 dice");
-    let iterations: usize = args[3].parse().expect("Invalid iterations");
+    let p1_dice: u8 = args.positional[2].parse().expect("Invalid p1 dice");
+    let p2_dice: u8 = args.positional[3].parse().expect("Invalid p2 dice");
+    let config = game_config(args, p1_dice, p2_dice);
+    match checkpoint::verify(args, &config, args.value("dir"), &checkpoint::VerifyOptions::from_args(args)) {
+        Ok(true) => println!("Checkpoint checks passed."),
+        Ok(false) => std::process::exit(1),
+        Err(e) => {
+            eprintln!("Checkpoint check failed: {}", e);
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Times regret matching and average-strategy normalization on nodes of
+/// several sizes; build with and without `--features simd` to compare.
+fn run_bench_kernels(args: &Args) {
+    let calls: usize = args.parse_value("calls").unwrap_or(1_000_000);
+    let sizes: Vec<usize> = match args.value("actions") {
+        Some(list) => list.split(',').map(|n| n.trim().parse().expect("Invalid --actions entry")).collect(),
+        None => vec![6, 12, 24, 48, 96, 192],
+    };
+    let mut rng = StdRng::seed_from_u64(args.parse_value("seed").unwrap_or(0));
+    println!("Regret-matching kernels ({} build), {} calls per size:", if cfg!(feature = "simd") { "simd" } else { "scalar" }, calls);
+    println!("Actions  Strategy ns  Average ns");
+    for n in sizes {
+        let mut node = cfr::CFRNode::new((0..n).map(|i| game::Action::Bid(i as u8 + 1, 1)).collect());
+        // Roughly half the regrets positive, as in a node that is still learning.
+        node.regret_sum = (0..n).map(|_| rng.gen_range(-1.0..1.0)).collect();
+        node.strategy_sum = (0..n).map(|_| rng.gen_range(0.0..1.0)).collect();
+        let minimizer = cfr::RegretMinimizer::RegretMatchingPlus;
 
-    println!("Starting Rust training (Vanilla CFR+) for {}v{} with {} iterations...", p1_dice, p2_dice, iterations);
-    
-    let start_time = Instant::now();
+        let start = std::time::Instant::now();
+        for _ in 0..calls {
+            std::hint::black_box(std::hint::black_box(&node).current_strategy(minimizer));
+        }
+        let strategy = start.elapsed().as_nanos() as f64 / calls as f64;
+        let start = std::time::Instant::now();
+        for _ in 0..calls {
+            std::hint::black_box(std::hint::black_box(&node).get_average_strategy());
+        }
+        let average = start.elapsed().as_nanos() as f64 / calls as f64;
+        println!("{:>7} {:12.1} {:11.1}", n, strategy, average);
+    }
+}
 
-    // Determine number of threads
-    let num_threads = rayon::current_num_threads();
-    let iters_per_thread = iterations / num_threads;
-    
-    println!("Running on {} threads, {} iterations per thread.", num_threads, iters_per_thread);
+fn run_odds(args: &Args) {
+    if args.positional.len() < 5 {
+        println!("Usage: cargo run odds <p1_dice> <p2_dice> <quantity> <face> [--hand <dice, e.g. 125>] [rule options]");
+        return;
+    }
 
-    // Parallel Map-Reduce
-    let final_nodes = (0..num_threads).into_par_iter()
-        .map(|_| {
-            CFRTrainer::train(p1_dice, p2_dice, iters_per_thread)
+    let p1_dice: u8 = args.positional[1].parse().expect("Invalid p1 dice");
+    let p2_dice: u8 = args.positional[2].parse().expect("Invalid p2 dice");
+    let quantity: u8 = args.positional[3].parse().expect("Invalid quantity");
+    let face: u8 = args.positional[4].parse().expect("Invalid face");
+    assert!((1..=DICE_FACES).contains(&face), "face must be between 1 and {}", DICE_FACES);
+    let config = game_config(args, p1_dice, p2_dice);
+    let hand: Vec<u8> = args
+        .value("hand")
+        .unwrap_or("")
+        .chars()
+        .map(|c| c.to_digit(10).filter(|&d| (1..=DICE_FACES as u32).contains(&d)).expect("Invalid --hand die") as u8)
+        .collect();
+    let total = p1_dice + p2_dice;
+    assert!(hand.len() as u8 <= total, "--hand has more dice than are in play");
+
+    let bid = probability::bid_odds(&config, &hand, quantity, face);
+    println!("{} unseen dice; your hand shows {} x {}.", bid.unseen, bid.in_hand, face);
+    println!("{:>6} {:>10} {:>10}", "Bid", "P(true)", "P(exact)");
+    for q in 1..=total {
+        let odds = probability::bid_odds(&config, &hand, q, face);
+        let marker = if q == quantity { "  <-" } else { "" };
+        println!("{:>6} {:>9.2}% {:>9.2}%{}", format!("{}-{}", q, face), odds.truth * 100.0, odds.exact * 100.0, marker);
+    }
+}
+
+fn run_serve(args: &Args) {
+    if args.positional.len() < 3 {
+        println!("Usage: cargo run serve <p1_dice> <p2_dice> [--port <port>] [--strategy <file[:weight],...>] [--blend <decision|game>]");
+        println!("           [--watch] [--admin-token <token>] [--record <file>] [--rollouts <n>] [--temperature <t>] [rule options]");
+        return;
+    }
+
+    let p1_dice: u8 = args.positional[1].parse().expect("Invalid p1 dice");
+    let p2_dice: u8 = args.positional[2].parse().expect("Invalid p2 dice");
+    let config = game_config(args, p1_dice, p2_dice);
+    if args.has("strategy") {
+        require_private_hands(&config, "scoring moves with --strategy");
+    }
+    assert!(config.seat_dice.is_none(), "serve seats two players; --teams is not supported");
+    // Spectator evaluations need a strategy; without one the server only referees.
+    let options = server::ServerOptions {
+        port: args.parse_value("port").unwrap_or(8080),
+        strategy: args.value("strategy").map(str::to_string),
+        blend_mode: args.parse_value("blend").unwrap_or_default(),
+        record: args.value("record").map(str::to_string),
+        admin_token: args.value("admin-token").map(str::to_string),
+        watch: args.has("watch"),
+        rollouts: args.parse_value("rollouts").unwrap_or(rollout::DEFAULT_ROLLOUTS),
+        temperature: temperature(args),
+    };
+
+    server::serve(config, options).expect("Server failed");
+}
+
+fn run_analyze(args: &Args) {
+    if args.positional.len() < 3 {
+        println!("Usage: cargo run analyze <records_file> <strategy_file> [--threshold <percent_of_a_die>]");
+        println!("           [--opponent-model <file> --opponent <player>] [rule options]");
+        return;
+    }
+
+    let records = record::read_records(&args.positional[1]).unwrap_or_else(|e| {
+        eprintln!("Unable to read {}: {}", args.positional[1], e);
+        std::process::exit(2);
+    });
+    let path = &args.positional[2];
+    let table = StrategyTable::load(path).expect("Unable to read strategy file");
+    // Prefer the rules the strategy was solved under; records only say how many dice were in play.
+    let rules = read_metadata(path).unwrap_or_else(|_| game_config(args, 0, 0));
+    require_private_hands(&rules, "analyze");
+    let threshold = args.parse_value::<f64>("threshold").unwrap_or(5.0) / 100.0;
+    // EVs against a fitted opponent: the named player plays its model, the other the solver.
+    let model = args.value("opponent-model").map(|path| {
+        let Some(opponent) = args.value("opponent") else {
+            eprintln!("--opponent-model needs --opponent <player> to say whose model it is.");
+            std::process::exit(2);
+        };
+        (StrategyTable::load(path).expect("Unable to read opponent model"), opponent)
+    });
+
+    let mut summaries = BTreeMap::new();
+    let mut skipped = 0;
+    for (i, record) in records.iter().enumerate() {
+        let config = record.config(&rules);
+        let solved = rules.openings().iter().any(|(round, _)| (round.dice_p1, round.dice_p2) == (config.dice_p1, config.dice_p2));
+        if rules.dice_p1 != 0 && !solved {
+            skipped += 1;
+            continue;
+        }
+        if let Err(e) = GameState::from_history(&config, record.hands[0].clone(), record.hands[1].clone(), &record.actions) {
+            println!("record {}: skipped, {}", i + 1, e);
+            continue;
+        }
+        let modeled_seat = model.as_ref().and_then(|(_, opponent)| record.players.iter().position(|p| p == opponent));
+        let decisions = match (&model, modeled_seat) {
+            (Some((model, _)), Some(seat)) => {
+                let policy = opponent::ModeledOpponent { solver: &table, model, seat: seat as u8 };
+                analyze::analyze_record(&policy, &config, i, record)
+            }
+            _ => analyze::analyze_record(&table, &config, i, record),
+        };
+        for d in decisions.iter().filter(|d| d.loss() > threshold) {
+            println!(
+                "record {}: {} played {} at {} (EV {:.3}, solver {:.3}, loss {:.3})",
+                d.record + 1, d.player, d.chosen, d.info_set, d.chosen_ev, d.solver_ev, d.loss()
+            );
+        }
+        analyze::summarize(&decisions, threshold, &mut summaries);
+    }
+
+    if skipped > 0 {
+        println!("Skipped {} records whose dice counts differ from the strategy's.", skipped);
+    }
+    for (player, summary) in &summaries {
+        println!(
+            "{}: {} decisions, {} mistakes over {:.1}% of a die, {:.3} dice of EV lost ({:.4} per decision)",
+            player,
+            summary.decisions,
+            summary.mistakes,
+            threshold * 100.0,
+            summary.ev_lost,
+            summary.ev_lost / summary.decisions.max(1) as f64
+        );
+    }
+}
+
+fn run_train_all(args: &Args) {
+    if args.positional.len() < 3 {
+        println!("Usage: cargo run train-all <max_dice> <iterations> [--bundle <file>] [training and rule options]");
+        return;
+    }
+
+    let max_dice: u8 = args.positional[1].parse().expect("Invalid max dice");
+    let iterations: usize = args.positional[2].parse().expect("Invalid iterations");
+    let path = args.value("bundle").map_or_else(|| format!("../strategy_bundle_{}.bundle", max_dice), str::to_string);
+
+    let mut bundle = StrategyBundle {
+        rules: Some(game_config(args, max_dice, max_dice)),
+        ..Default::default()
+    };
+    for p1_dice in 1..=max_dice {
+        for p2_dice in 1..=max_dice {
+            let config = game_config(args, p1_dice, p2_dice);
+            let nodes = train::train_config(args, &config, iterations);
+            bundle.tables.insert((p1_dice, p2_dice), StrategyTable::from_nodes(&nodes, SavePrecision::from_args(args)));
+
+            // Rewrite after every configuration so an interrupted run keeps what it finished.
+            bundle.save(&path).expect("Unable to write strategy bundle");
+            println!("Bundle {} now holds {} configuration(s).", path, bundle.tables.len());
+        }
+    }
+}
+
+fn run_train_match(args: &Args) {
+    if args.positional.len() < 3 {
+        println!("Usage: cargo run train-match <start_dice> <iterations> [--exploration <epsilon>] [--seed <n>] [--output <file>]");
+        println!("           [--games <self_play_matches>] [rule options]");
+        return;
+    }
+
+    let start_dice: u8 = args.positional[1].parse().expect("Invalid start dice");
+    let iterations: usize = args.positional[2].parse().expect("Invalid iterations");
+    let rules = game_config(args, start_dice, start_dice);
+    assert!(rules.seat_dice.is_none(), "train-match seats two players; --teams is not supported");
+    let trainer = match_game::MatchTrainer {
+        rules: rules.clone(),
+        start_dice,
+        exploration: args.parse_value("exploration").unwrap_or(0.6),
+        minimizer: args.parse_value("minimizer").unwrap_or_default(),
+    };
+
+    let threads = rayon::current_num_threads();
+    println!("Training {}-dice matches with outcome-sampling MCCFR for {} iterations on {} threads...", start_dice, iterations, threads);
+    let seed: Option<u64> = args.parse_value("seed");
+    let start_time = std::time::Instant::now();
+    let nodes = (0..threads)
+        .into_par_iter()
+        .map(|w| {
+            let mut rng = match seed {
+                Some(seed) => StdRng::seed_from_u64(seed.wrapping_add(w as u64)),
+                None => StdRng::from_entropy(),
+            };
+            let first = w * (iterations / threads) + w.min(iterations % threads);
+            let count = iterations / threads + usize::from(w < iterations % threads);
+            let mut nodes = HashMap::new();
+            trainer.train_into(&mut nodes, &mut rng, first, count);
+            nodes
         })
-        .reduce(HashMap::new, merge_nodes);
+        .collect::<Vec<_>>()
+        .into_iter()
+        .fold(HashMap::new(), train::merge_nodes);
+    println!("Trained {} match info sets in {:.2?}.", nodes.len(), start_time.elapsed());
+
+    let games: usize = args.parse_value("games").unwrap_or(10_000);
+    if games > 0 {
+        let table = StrategyTable::from_nodes(&nodes, SavePrecision::Full);
+        let mut rng = rand::thread_rng();
+        let opener_wins = (0..games).filter(|_| match_game::self_play(&table, &rules, start_dice, &mut rng) == 0).count();
+        println!("In self-play the first opener wins {:.2}% of {} matches.", opener_wins as f64 / games as f64 * 100.0, games);
+    }
+
+    let filename = args.value("output").map_or_else(|| format!("../match_strategy_{}.csv", start_dice), str::to_string);
+    save_strategy(&nodes, &rules, &filename, SavePrecision::from_args(args));
+}
 
-    let duration = start_time.elapsed();
-    println!("Training complete in {:.2?}", duration);
-    println!("Iterations per second: {:.2}", iterations as f64 / duration.as_secs_f64());
+fn run_rebel(args: &Args) {
+    if args.positional.len() < 4 {
+        println!("Usage: cargo run rebel <p1_dice> <p2_dice> <episodes> [--depth <bids>] [--subgame-iterations <n>] [--exploration <p>] [rule options]");
+        return;
+    }
+
+    let p1_dice: u8 = args.positional[1].parse().expect("Invalid p1 dice");
+    let p2_dice: u8 = args.positional[2].parse().expect("Invalid p2 dice");
+    let episodes: usize = args.positional[3].parse().expect("Invalid episodes");
+    let config = single_opening(game_config(args, p1_dice, p2_dice), "rebel");
+    require_private_hands(&config, "rebel");
+    let settings = rebel::PbsSettings {
+        depth: args.parse_value("depth").unwrap_or(2),
+        iterations: args.parse_value("subgame-iterations").unwrap_or(100),
+        exploration: args.parse_value("exploration").unwrap_or(0.1),
+    };
+
+    println!(
+        "Running {} episodes of public-belief-state self-play for {}v{} (depth {}, {} iterations per search)...",
+        episodes, p1_dice, p2_dice, settings.depth, settings.iterations
+    );
+    let start_time = std::time::Instant::now();
+    let (table, values) = rebel::self_play(&config, episodes, &settings, &mut rand::thread_rng());
+    println!("Self-play complete in {:.2?}.", start_time.elapsed());
+    println!("Learned values for {} (player, hand, public state) entries; policy covers {} info sets.", values.len(), table.entries.len());
 
-    save_strategy(&final_nodes, p1_dice, p2_dice);
+    let filename = strategy_filename(p1_dice, p2_dice);
+    table.save(&filename, &config).expect("Unable to write strategy file");
+    println!("Saved strategy to {}.", filename);
+}
+
+fn run_openspiel(args: &Args) {
+    if args.positional.len() < 6 {
+        println!("Usage: cargo run openspiel export <strategy_file> <p1_dice> <p2_dice> <policy_file>");
+        println!("       cargo run openspiel import <policy_file> <p1_dice> <p2_dice> <strategy_file>");
+        return;
+    }
+
+    let input = &args.positional[2];
+    let p1_dice: u8 = args.positional[3].parse().expect("Invalid p1 dice");
+    let p2_dice: u8 = args.positional[4].parse().expect("Invalid p2 dice");
+    let output = &args.positional[5];
+    let config = single_opening(game_config(args, p1_dice, p2_dice), "openspiel");
+    require_private_hands(&config, "openspiel");
+
+    match args.positional[1].as_str() {
+        "export" => {
+            let table = StrategyTable::load(input).expect("Unable to read strategy file");
+            let mut info_states = 0;
+            write_atomically(output, |file| {
+                info_states = openspiel::export_policy(&table, &config, file)?;
+                Ok(())
+            })
+            .expect("Unable to write policy file");
+            println!("Exported {} OpenSpiel info states to {}.", info_states, output);
+        }
+        "import" => {
+            let table = openspiel::import_policy(input, &config).expect("Unable to read policy file");
+            table.save(output, &config).expect("Unable to write strategy file");
+            println!("Imported {} info sets into {}.", table.entries.len(), output);
+        }
+        other => println!("Unknown openspiel command: {}", other),
+    }
+}
+
+fn run_train(args: &Args) {
+    if args.positional.len() < 3 {
+        println!("Usage: cargo run <p1_dice> <p2_dice> <iterations> [--save-every <iterations>] [--save-minutes <minutes>]");
+        println!("           [--target-exploitability <value>] [--check-every <iterations>] [--patience <checks>]");
+        println!("           [--dashboard <port>]");
+        println!("           [--curriculum [--full-dice <n>] [--transfer-rounds <n>] [--episodes <n>] [--depth <bids>] [--subgame-iterations <n>]]");
+        println!("           [--stats-json <file>] [--export-regrets <file.csv|file.json|file.parquet>]");
+        println!("           [--min-reach <probability>] [--minimizer <rm+|hedge|hedge:scale>]");
+        println!("           [--track-distance <iterations>] [--converged-distance <tv_per_100k>]");
+        println!("           [--cfr-br] [--sampling <chance|outcome>] [--exploration <epsilon>]");
+        println!("           [--reset-averages <n[,n...]|every:n>] [--reset-regret-scale <factor>]");
+        println!("           [--average-only] [--freeze-visits <n>] [--freeze-tolerance <tv>] [--freeze-rare <share>]");
+        println!("           [--deal-script <file>] [--target-records <file>] [--target-share <fraction>] [--seed <n>]");
+        println!("           [--exhaustive-deals [--deal-start <n> | --resume-deals (out of core)]]");
+        println!("           [--save-threshold <probability>] [--full]");
+        println!("           [--face-weights <w1,...,w6>] [--bid-rules <standard|quantity-only|no-face-reset|aces-ladder>]");
+        println!("           [--max-quantity <total|n|half+k>] [--quantity-step <n>] [--history <length|last:k|full|pressure>]");
+        println!("           [--dice-loss <one|difference>] [--calza <reward>] [--opener <first|second|alternate|random>]");
+        println!("           [--risk-aversion <a>] [--handicap <player>:<no-calza|peek:n>[,...]] [--teams <a1,b1,a2,b2>]");
+        println!("           [--refinement <file>]");
+        println!("           [--no-symmetry] [--threads <n>] [--reserve-core] [--work-stealing] [--time-limit <minutes>] [--batch-size <n>]");
+        println!("           [--out-of-core <dir> [--shards <n>] [--cache-shards <n>]]");
+        println!("       cargo run validate <strategy_file> <p1_dice> <p2_dice> [--tolerance <sum_tolerance>]");
+        println!("       cargo run openspiel <export|import> <input> <p1_dice> <p2_dice> <output>");
+        println!("       cargo run exploitability <strategy_file> <p1_dice> <p2_dice> [--by-history [--top <n>] [--output <file.csv>]]");
+        println!("       cargo run simulate-match <bundle_a> <bundle_b> [--start-dice <n>] [--matches <n>] [--record <file>] [--deal-script <file>]");
+        println!("           [--exact-endgame <total_dice>] [--duplicate] [--seed <n>]");
+        println!("       cargo run analyze <records_file> <strategy_file> [--threshold <percent_of_a_die>]");
+        println!("           [--opponent-model <file> --opponent <player>]");
+        println!("       cargo run play <p1_dice> <p2_dice> [--strategy <file[:weight],...>] [--blend <decision|game>] [--hints] [--record <file>]");
+        println!("           [--rollouts <n>] [--temperature <t>] [--profile <file|none>]");
+        println!("       cargo run stats [<profile_file>]");
+        println!("       cargo run [--features simd] bench-kernels [--actions <n,...>] [--calls <n>]");
+        println!("       cargo run checkpoint verify <p1_dice> <p2_dice> [--dir <checkpoint_dir>] [--iterations <n>] [--fuzz <cases>]");
+        println!("       cargo run odds <p1_dice> <p2_dice> <quantity> <face> [--hand <dice>]");
+        println!("       cargo run solve-exact <p1_dice> <p2_dice> [--output <file>] [--compare <strategy_file>]");
+        println!("       cargo run verify [--iterations <n>] [--tolerance <value>]");
+        println!("       cargo run inspect <strategy_or_bundle_file>");
+        println!("       cargo run inspect estimate <p1_dice> <p2_dice>");
+        println!("       cargo run export <strategy_file> <output.json|output.parquet> [--format <json|parquet>]");
+        println!("       cargo run fit-opponent <records_file> <player> <p1_dice> <p2_dice> <output> [--smoothing <pseudo_count>]");
+        println!("       cargo run experiment <p1_dice> <p2_dice> <iterations> [--runs <n>] [--games <rounds_per_pair>] [--duplicate]");
+        println!("           [--results <dir>]");
+        println!("       cargo run refine <p1_dice> <p2_dice> <iterations_per_round> [--rounds <n>] [--split-share <fraction>] [--exploitability]");
+        println!("       cargo run query <strategy_file> <situations.csv> [<p1_dice> <p2_dice>] [--output <file>] [--rollouts <n>] [--temperature <t>]");
+        println!("       cargo run aggregate <strategy_file> [<p1_dice> <p2_dice>] [--max-depth <bids>] [--min-reach <probability>]");
+        println!("           [--output <dir>]");
+        println!("       cargo run diff <strategy_a> <strategy_b> [<p1_dice> <p2_dice>] [--top <n>]");
+        println!("       cargo run explore <strategy_file> [<p1_dice> <p2_dice>] [--bookmarks <file>]");
+        println!("       cargo run engine <bundle_or_dir> [--max-dice <n>] [--rollouts <n>] [--temperature <t>] [--seed <n>]");
+        println!("       cargo run serve <p1_dice> <p2_dice> [--port <port>] [--strategy <file[:weight],...>] [--blend <decision|game>]");
+        println!("           [--watch] [--admin-token <token>] [--record <file>] [--rollouts <n>] [--temperature <t>]");
+        println!("       cargo run train-all <max_dice> <iterations> [--bundle <file>]");
+        println!("       cargo run train-match <start_dice> <iterations> [--exploration <epsilon>] [--output <file>] [--games <n>]");
+        println!("       cargo run rebel <p1_dice> <p2_dice> <episodes> [--depth <bids>] [--subgame-iterations <n>] [--exploration <p>]");
+        return;
+    }
+
+    let p1_dice: u8 = args.positional[0].parse().expect("Invalid p1 dice");
+    let p2_dice: u8 = args.positional[1].parse().expect("Invalid p2 dice");
+    let iterations: usize = args.positional[2].parse().expect("Invalid iterations");
+    let config = game_config(args, p1_dice, p2_dice);
+
+    if args.has("curriculum") {
+        let config = single_opening(config, "train --curriculum");
+        require_private_hands(&config, "train --curriculum");
+        assert!(config.seat_dice.is_none(), "train --curriculum seats two players; --teams is not supported");
+        assert!(args.value("out-of-core").is_none(), "train --curriculum keeps every stage in memory; --out-of-core is not supported");
+        curriculum::train_curriculum(args, &config, iterations, &curriculum::Curriculum::from_args(args));
+        return;
+    }
+    if let Some(dir) = args.value("out-of-core") {
+        train::train_out_of_core(args, &config, iterations, dir);
+        return;
+    }
+    let final_nodes = train::train_config(args, &config, iterations);
+    save_strategy(&final_nodes, &config, &strategy_filename(p1_dice, p2_dice), SavePrecision::from_args(args));
 }
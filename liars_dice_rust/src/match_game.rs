@@ -0,0 +1,230 @@
+use crate::cfr::{CFRNode, RegretMinimizer};
+use crate::game::{Action, GameConfig, GameState, Opener};
+use crate::strategy::{sample_index, Policy};
+use rand::Rng;
+use std::collections::HashMap;
+
+/// How the previous round of a match ended, as the next round's opener sees it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LastRound {
+    /// The match has just started.
+    None,
+    /// The opener lost dice on the last call.
+    OpenerLost,
+    /// The opener won a Calza.
+    OpenerCalza,
+}
+
+impl LastRound {
+    fn tag(self) -> char {
+        match self {
+            LastRound::None => '-',
+            LastRound::OpenerLost => 'L',
+            LastRound::OpenerCalza => 'C',
+        }
+    }
+}
+
+/// The public state a match carries from one round to the next: each seat's
+/// dice, who opens, and how the last round ended. The re-deal between rounds
+/// is a chance node after it.
+#[derive(Clone, Debug)]
+pub struct MatchState {
+    pub dice: [u8; 2],
+    /// The seat opening the next round, which is its player 0.
+    pub opener: usize,
+    pub last: LastRound,
+    /// Calza can win back dice up to the starting count.
+    pub start_dice: u8,
+}
+
+impl MatchState {
+    pub fn new(start_dice: u8, first_opener: usize) -> Self {
+        MatchState { dice: [start_dice; 2], opener: first_opener, last: LastRound::None, start_dice }
+    }
+
+    /// The seat with dice left once the other has none.
+    pub fn winner(&self) -> Option<usize> {
+        match self.dice {
+            [0, _] => Some(1),
+            [_, 0] => Some(0),
+            _ => None,
+        }
+    }
+
+    /// The rules of the next round, with the opener as player 0.
+    pub fn round_config(&self, rules: &GameConfig) -> GameConfig {
+        // Opening as the seat that opens, so each seat keeps its handicaps.
+        let mut config = rules.opening(self.opener == 1);
+        config.dice_p1 = self.dice[self.opener];
+        config.dice_p2 = self.dice[1 - self.opener];
+        config
+    }
+
+    /// The seat playing as `player` this round.
+    pub fn seat_of(&self, player: u8) -> usize {
+        (self.opener + player as usize) % 2
+    }
+
+    /// The match after `round` has been called: the loser pays the dice the
+    /// call cost and opens the next round, or a successful Calza wins the
+    /// caller dice back and they open. Perudo's rules, as `simulate` plays them.
+    pub fn after(&self, round: &GameState) -> MatchState {
+        let challenger = self.seat_of(round.current_player);
+        let payoff = round.challenger_payoff();
+        let mut next = self.clone();
+
+        if round.final_call == Some(Action::Calza) && payoff > 0.0 {
+            let gained = payoff.round() as u8;
+            next.dice[challenger] = next.dice[challenger].saturating_add(gained).min(self.start_dice);
+            next.opener = challenger;
+            next.last = LastRound::OpenerCalza;
+            return next;
+        }
+
+        // The challenger's gain is exactly what the loser pays in dice.
+        let loser = if payoff > 0.0 { 1 - challenger } else { challenger };
+        let lost = payoff.abs().round().max(1.0) as u8;
+        next.dice[loser] = next.dice[loser].saturating_sub(lost);
+        next.opener = loser;
+        next.last = LastRound::OpenerLost;
+        next
+    }
+
+    /// A decision's info set: the round's dice counts (opener first) and how
+    /// the last round ended, then the round's own key.
+    pub fn information_set(&self, round: &GameState) -> String {
+        format!("{}v{}{}:{}", round.dice_p1, round.dice_p2, self.last.tag(), round.get_information_set())
+    }
+}
+
+/// A strategy keyed by match info sets, played round by round.
+pub struct MatchPolicy<'a, P> {
+    pub table: &'a P,
+    pub state: &'a MatchState,
+}
+
+impl<P: Policy> Policy for MatchPolicy<'_, P> {
+    fn action_probabilities(&self, info_set: &str, actions: &[Action]) -> Vec<f64> {
+        let prefix = format!("{}v{}{}:", self.state.dice[self.state.opener], self.state.dice[1 - self.state.opener], self.state.last.tag());
+        self.table.action_probabilities(&format!("{}{}", prefix, info_set), actions)
+    }
+}
+
+/// Plays one match between both seats using `table`, seat 0 opening, and
+/// returns the winning seat.
+pub fn self_play<P: Policy, R: Rng>(table: &P, rules: &GameConfig, start_dice: u8, rng: &mut R) -> usize {
+    let mut state = MatchState::new(start_dice, 0);
+    loop {
+        let mut round = GameState::deal(&state.round_config(rules), rng);
+        let policy = MatchPolicy { table, state: &state };
+        loop {
+            let actions = round.get_valid_actions();
+            let probs = policy.action_probabilities(&round.get_information_set(), &actions);
+            if round.apply_action(actions[sample_index(&probs, rng)].clone()) {
+                break;
+            }
+        }
+        state = state.after(&round);
+        if let Some(winner) = state.winner() {
+            return winner;
+        }
+    }
+}
+
+/// Outcome-sampling MCCFR over a whole match, with the re-deal between rounds
+/// as a sampled chance node. Utilities are +1 for winning the match and -1
+/// for losing it, so strategies trade dice for match equity rather than
+/// minimizing dice lost in each round on its own.
+pub struct MatchTrainer {
+    pub rules: GameConfig,
+    pub start_dice: u8,
+    pub exploration: f32,
+    pub minimizer: RegretMinimizer,
+}
+
+impl MatchTrainer {
+    /// Samples `iterations` matches, alternating the seat whose regrets are
+    /// updated. The first round's opener follows the rules' opener setting.
+    pub fn train_into<R: Rng>(&self, nodes: &mut HashMap<String, CFRNode>, rng: &mut R, first_iteration: usize, iterations: usize) {
+        for i in first_iteration..first_iteration + iterations {
+            let traverser = i % 2;
+            let opener = match self.rules.opener {
+                Opener::First => 0,
+                Opener::Second => 1,
+                Opener::Alternate => (i / 2) % 2,
+                Opener::Random => rng.gen_range(0..2),
+            };
+            let state = MatchState::new(self.start_dice, opener);
+            let round = GameState::deal(&state.round_config(&self.rules), rng);
+            let mut path = MatchPath { traverser, rng: &mut *rng };
+            self.sample(&state, round, &mut path, [1.0, 1.0], 1.0, nodes);
+        }
+    }
+
+    /// One step of the sampled path, with `reach` the traverser's and the
+    /// opponent's; returns (traverser's utility divided by the sampling
+    /// probability, the players' probability of the rest of the path), as
+    /// `CFRTrainer::outcome_sample` does for a single round.
+    fn sample<R: Rng>(
+        &self,
+        state: &MatchState,
+        round: GameState,
+        path: &mut MatchPath<R>,
+        reach: [f32; 2],
+        sample_prob: f32,
+        nodes: &mut HashMap<String, CFRNode>,
+    ) -> (f32, f32) {
+        let ([own_reach, opp_reach], traverser) = (reach, path.traverser);
+        let seat = state.seat_of(round.current_player);
+        let valid_actions = round.get_valid_actions();
+        let info_set = state.information_set(&round);
+        let node = nodes.entry(info_set.clone()).or_insert_with(|| CFRNode::new(valid_actions.clone()));
+        node.visits += 1;
+        let strategy = node.current_strategy(self.minimizer);
+
+        let explore = if seat == traverser { self.exploration } else { 0.0 };
+        let uniform = 1.0 / valid_actions.len() as f32;
+        let sampling: Vec<f64> = strategy.iter().map(|&s| (explore * uniform + (1.0 - explore) * s) as f64).collect();
+        let a = sample_index(&sampling, path.rng);
+        let next_sample_prob = sample_prob * sampling[a] as f32;
+        let next_reach = if seat == traverser { [own_reach * strategy[a], opp_reach] } else { [own_reach, opp_reach * strategy[a]] };
+
+        let mut next_round = round.clone();
+        let (utility, tail) = if next_round.apply_action(valid_actions[a].clone()) {
+            let next = state.after(&next_round);
+            match next.winner() {
+                Some(winner) => (if winner == traverser { 1.0 } else { -1.0 } / next_sample_prob, 1.0),
+                // Chance re-deals for the next round at its true odds, so it leaves the sampling weights alone.
+                None => {
+                    let dealt = GameState::deal(&next.round_config(&self.rules), path.rng);
+                    self.sample(&next, dealt, path, next_reach, next_sample_prob, nodes)
+                }
+            }
+        } else {
+            self.sample(state, next_round, path, next_reach, next_sample_prob, nodes)
+        };
+
+        if seat == traverser {
+            let node = nodes.get_mut(&info_set).unwrap();
+            let weight = utility * opp_reach;
+            for (b, &s) in strategy.iter().enumerate() {
+                let regret = if b == a { weight * tail * (1.0 - s) } else { -weight * tail * strategy[a] };
+                let cumulative = node.regret_sum[b] + regret;
+                node.regret_sum[b] = match self.minimizer {
+                    RegretMinimizer::RegretMatchingPlus => cumulative.max(0.0),
+                    RegretMinimizer::Hedge { .. } => cumulative,
+                };
+                node.strategy_sum[b] += own_reach / sample_prob * s;
+            }
+        }
+        (utility, tail * strategy[a])
+    }
+}
+
+/// What stays fixed along one sampled match.
+struct MatchPath<'a, R: Rng> {
+    /// The seat whose regrets this traversal updates.
+    traverser: usize,
+    rng: &'a mut R,
+}
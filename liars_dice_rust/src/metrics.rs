@@ -0,0 +1,72 @@
+use crate::game::{Action, GameConfig};
+use crate::reach;
+use crate::strategy::Policy;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Probability floor for KL divergence, so an action one strategy never
+/// plays costs a large but finite amount instead of infinity.
+const KL_FLOOR: f64 = 1e-9;
+
+/// KL divergence KL(p || q) in nats.
+pub fn kl_divergence(p: &[f64], q: &[f64]) -> f64 {
+    p.iter().zip(q).filter(|(p, _)| **p > 0.0).map(|(p, q)| p * (p / q.max(KL_FLOOR)).ln()).sum()
+}
+
+/// Total variation distance: half the L1 distance.
+pub fn total_variation(p: &[f64], q: &[f64]) -> f64 {
+    p.iter().zip(q).map(|(p, q)| (p - q).abs()).sum::<f64>() / 2.0
+}
+
+/// How far one strategy is from another, averaged over info sets weighted
+/// by how often a blueprint strategy reaches them.
+pub struct StrategyDistance {
+    /// Weighted mean of KL(a || b).
+    pub kl: f64,
+    /// Weighted mean of KL(b || a).
+    pub reverse_kl: f64,
+    pub tv: f64,
+    /// Info sets the blueprint reaches, and so counted.
+    pub info_sets: usize,
+    /// Info sets with their reach weight (normalized) and total variation,
+    /// largest weighted contribution first.
+    pub by_info_set: Vec<(String, f64, f64)>,
+}
+
+impl StrategyDistance {
+    /// Compares `a` and `b` at every info set `blueprint` reaches under `config`.
+    pub fn between<A: Policy, B: Policy, P: Policy>(a: &A, b: &B, blueprint: &P, config: &GameConfig) -> Self {
+        Self::with_reach(a, b, &reach::info_set_reach_and_actions(blueprint, config))
+    }
+
+    /// As `between`, with the blueprint's reach already computed.
+    pub fn with_reach<A: Policy, B: Policy>(a: &A, b: &B, reach: &HashMap<String, (f64, Vec<Action>)>) -> Self {
+        let total: f64 = reach.values().map(|(r, _)| r).sum();
+        let mut distance = StrategyDistance { kl: 0.0, reverse_kl: 0.0, tv: 0.0, info_sets: 0, by_info_set: Vec::new() };
+        for (info_set, (r, actions)) in reach {
+            if *r <= 0.0 {
+                continue;
+            }
+            let weight = r / total;
+            let p = a.action_probabilities(info_set, actions);
+            let q = b.action_probabilities(info_set, actions);
+            let tv = total_variation(&p, &q);
+            distance.kl += weight * kl_divergence(&p, &q);
+            distance.reverse_kl += weight * kl_divergence(&q, &p);
+            distance.tv += weight * tv;
+            distance.info_sets += 1;
+            distance.by_info_set.push((info_set.clone(), weight, tv));
+        }
+        distance.by_info_set.sort_by(|x, y| (y.1 * y.2).total_cmp(&(x.1 * x.2)).then_with(|| x.0.cmp(&y.0)));
+        distance
+    }
+}
+
+impl fmt::Display for StrategyDistance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Reached info sets: {}", self.info_sets)?;
+        writeln!(f, "Weighted total variation: {:.6}", self.tv)?;
+        writeln!(f, "Weighted KL(a || b): {:.6}", self.kl)?;
+        writeln!(f, "Weighted KL(b || a): {:.6}", self.reverse_kl)
+    }
+}
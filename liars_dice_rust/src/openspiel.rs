@@ -0,0 +1,181 @@
+use crate::game::{Action, GameConfig, GameState, DICE_FACES};
+use crate::strategy::{Policy, StrategyTable};
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::Path;
+
+// Conversion to and from OpenSpiel's `liars_dice` tabular policies.
+//
+// Policies are exchanged as CSV rows of (InfoState, ActionId, Probability),
+// one row per legal action, which loads directly into a python TabularPolicy
+// via `policy.policy_for_key(info_state)[action_id] = probability`.
+//
+// OpenSpiel info states list the player's dice in roll order followed by the
+// bid sequence ("31 1-2 2-5"), and bids are ranked by quantity then face, so
+// bid (q, f) is action (q - 1) * 6 + (f - 1) and "Liar" is total_dice * 6.
+// OpenSpiel treats the highest face as wild by default; pick game parameters
+// that match the rules the table was solved under before comparing values.
+
+pub fn action_id(action: &Action, total_dice: u8) -> usize {
+    match action {
+        Action::Bid(q, f) => (*q as usize - 1) * DICE_FACES as usize + (*f as usize - 1),
+        Action::Challenge => total_dice as usize * DICE_FACES as usize,
+        Action::Calza => unreachable!("OpenSpiel's liars_dice has no Calza action"),
+    }
+}
+
+pub fn action_from_id(id: usize, total_dice: u8) -> Option<Action> {
+    let faces = DICE_FACES as usize;
+    if id == total_dice as usize * faces {
+        Some(Action::Challenge)
+    } else if id < total_dice as usize * faces {
+        Some(Action::Bid((id / faces + 1) as u8, (id % faces + 1) as u8))
+    } else {
+        None
+    }
+}
+
+pub fn info_state_string(dice: &[u8], history: &[Action]) -> String {
+    let mut result: String = dice.iter().map(|d| d.to_string()).collect();
+    for action in history {
+        if let Action::Bid(q, f) = action {
+            result.push_str(&format!(" {}-{}", q, f));
+        }
+    }
+    result
+}
+
+/// Every roll-ordered hand of `n_dice` dice.
+fn rolls(n_dice: u8) -> Vec<Vec<u8>> {
+    let mut rolls = vec![Vec::new()];
+    for _ in 0..n_dice {
+        rolls = rolls
+            .into_iter()
+            .flat_map(|r| {
+                (1..=DICE_FACES).map(move |f| {
+                    let mut next = r.clone();
+                    next.push(f);
+                    next
+                })
+            })
+            .collect();
+    }
+    rolls
+}
+
+/// Writes `policy` as an OpenSpiel tabular policy, returning the number of info states.
+///
+/// Walks every bid sequence, so the output grows exponentially with the dice count.
+pub fn export_policy<P: Policy, W: Write>(policy: &P, config: &GameConfig, out: &mut W) -> io::Result<usize> {
+    if config.calza_reward.is_some() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "OpenSpiel's liars_dice has no Calza action"));
+    }
+    writeln!(out, "InfoState,ActionId,Probability")?;
+    let rolls_by_player = [rolls(config.dice_p1), rolls(config.dice_p2)];
+    let root = GameState::new(config);
+    export_node(policy, &root, &rolls_by_player, config.dice_p1 + config.dice_p2, out)
+}
+
+fn export_node<P: Policy, W: Write>(
+    policy: &P,
+    state: &GameState,
+    rolls_by_player: &[Vec<Vec<u8>>; 2],
+    total_dice: u8,
+    out: &mut W,
+) -> io::Result<usize> {
+    let actions = state.get_valid_actions();
+    let mut by_sorted_hand: HashMap<Vec<u8>, Vec<f64>> = HashMap::new();
+    let mut count = 0;
+
+    for roll in &rolls_by_player[state.current_player as usize] {
+        let mut sorted = roll.clone();
+        sorted.sort();
+        let probs = by_sorted_hand
+            .entry(sorted)
+            .or_insert_with_key(|hand| policy.action_probabilities(&state.information_set_for(hand), &actions));
+
+        let info_state = info_state_string(roll, &state.history);
+        for (action, prob) in actions.iter().zip(probs.iter()) {
+            writeln!(out, "{},{},{}", info_state, action_id(action, total_dice), prob)?;
+        }
+        count += 1;
+    }
+
+    for action in &actions {
+        let mut next = state.clone();
+        if !next.apply_action(action.clone()) {
+            count += export_node(policy, &next, rolls_by_player, total_dice, out)?;
+        }
+    }
+    Ok(count)
+}
+
+/// Reads an OpenSpiel tabular policy into a strategy table under this solver's info sets.
+///
+/// Several OpenSpiel info states (dice orderings, and bid sequences the info
+/// set abstraction merges) map to one table entry; their distributions are
+/// averaged uniformly since the policy file carries no reach probabilities.
+pub fn import_policy<P: AsRef<Path>>(path: P, config: &GameConfig) -> io::Result<StrategyTable> {
+    let total_dice = config.dice_p1 + config.dice_p2;
+    let mut reader = csv::Reader::from_path(path).map_err(io::Error::other)?;
+    let mut by_info_state: HashMap<String, Vec<(Action, f64)>> = HashMap::new();
+
+    for record in reader.records() {
+        let record = record.map_err(io::Error::other)?;
+        let line = record.position().map_or(0, |p| p.line());
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, format!("invalid policy row at line {}", line));
+
+        if record.len() != 3 {
+            return Err(invalid());
+        }
+        let id: usize = record[1].parse().map_err(|_| invalid())?;
+        let action = action_from_id(id, total_dice).ok_or_else(invalid)?;
+        let prob: f64 = record[2].parse().map_err(|_| invalid())?;
+        by_info_state.entry(record[0].to_string()).or_default().push((action, prob));
+    }
+
+    // Sum per solver info set, then divide by the number of OpenSpiel states merged into it.
+    let mut sums: HashMap<String, (Vec<(Action, f64)>, usize)> = HashMap::new();
+    for (info_state, probs) in by_info_state {
+        let key = solver_info_set(&info_state, config).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("info state '{}' does not fit a {}v{} game", info_state, config.dice_p1, config.dice_p2))
+        })?;
+        let (totals, merged) = sums.entry(key).or_default();
+        for (action, prob) in probs {
+            match totals.iter_mut().find(|(a, _)| *a == action) {
+                Some((_, total)) => *total += prob,
+                None => totals.push((action, prob)),
+            }
+        }
+        *merged += 1;
+    }
+
+    let mut table = StrategyTable::default();
+    for (key, (totals, merged)) in sums {
+        let averaged = totals.into_iter().map(|(a, p)| (a, p / merged as f64)).collect();
+        table.entries.insert(key, averaged);
+    }
+    Ok(table)
+}
+
+/// Maps an OpenSpiel info state string to this solver's info set key.
+fn solver_info_set(info_state: &str, config: &GameConfig) -> Option<String> {
+    let mut parts = info_state.split(' ');
+    let mut hand = parts
+        .next()?
+        .chars()
+        .map(|c| c.to_digit(10).map(|d| d as u8))
+        .collect::<Option<Vec<u8>>>()?;
+    hand.sort();
+
+    let mut state = GameState::new(config);
+    for bid in parts {
+        let (q, f) = bid.split_once('-')?;
+        let action = Action::Bid(q.parse().ok()?, f.parse().ok()?);
+        state.action_index(action.clone())?;
+        state.apply_action(action);
+    }
+
+    let expected = if state.current_player == 0 { config.dice_p1 } else { config.dice_p2 };
+    (hand.len() == expected as usize).then(|| state.information_set_for(&hand))
+}
@@ -0,0 +1,97 @@
+use crate::game::{Action, GameConfig, GameState};
+use crate::record::GameRecord;
+use crate::strategy::{Policy, StrategyTable};
+use std::collections::HashMap;
+
+/// How often one player took each action at each info set they were seen
+/// deciding at, keyed like the solver's info sets so the fitted model is a
+/// strategy table for the same rules.
+#[derive(Default)]
+pub struct OpponentModel {
+    counts: HashMap<String, (Vec<Action>, Vec<f64>)>,
+    /// Records the player appears in whose dice counts fit the rules.
+    pub records: usize,
+    /// Records skipped because an action was illegal under the rules.
+    pub invalid: usize,
+    pub decisions: usize,
+}
+
+impl OpponentModel {
+    /// Counts `player`'s decisions in the records whose dice counts are one
+    /// of the rules' openings.
+    pub fn fit(records: &[GameRecord], rules: &GameConfig, player: &str) -> Self {
+        let rounds: Vec<(u8, u8)> = rules.openings().iter().map(|(round, _)| (round.dice_p1, round.dice_p2)).collect();
+        let mut model = OpponentModel::default();
+        for record in records {
+            let Some(seat) = record.players.iter().position(|p| p == player) else {
+                continue;
+            };
+            let config = record.config(rules);
+            if !rounds.contains(&(config.dice_p1, config.dice_p2)) {
+                continue;
+            }
+            if GameState::from_history(&config, record.hands[0].clone(), record.hands[1].clone(), &record.actions).is_err() {
+                model.invalid += 1;
+                continue;
+            }
+            model.records += 1;
+
+            let mut state = GameState::from_hands(&config, record.hands[0].clone(), record.hands[1].clone());
+            for action in &record.actions {
+                if state.current_player as usize == seat {
+                    let chosen = state.action_index(action.clone()).expect("validated above");
+                    let entry = model.counts.entry(state.get_information_set()).or_insert_with(|| {
+                        let actions: Vec<Action> = state.valid_actions_iter().collect();
+                        let n = actions.len();
+                        (actions, vec![0.0; n])
+                    });
+                    entry.1[chosen] += 1.0;
+                    model.decisions += 1;
+                }
+                if state.apply_action(action.clone()) {
+                    break;
+                }
+            }
+        }
+        model
+    }
+
+    /// Info sets the player was seen at.
+    pub fn info_sets(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// The empirical frequencies with Laplace smoothing: every legal action
+    /// gets `smoothing` pseudo-counts. Unseen info sets are left out, which
+    /// strategy tables read as uniform play.
+    pub fn to_table(&self, smoothing: f64) -> StrategyTable {
+        let mut table = StrategyTable::default();
+        for (info_set, (actions, counts)) in &self.counts {
+            let total = counts.iter().sum::<f64>() + smoothing * counts.len() as f64;
+            let probs = actions.iter().cloned().zip(counts.iter().map(|c| (c + smoothing) / total)).collect();
+            table.entries.insert(info_set.clone(), probs);
+        }
+        table
+    }
+}
+
+/// A policy playing `solver` except at `seat`, which plays the opponent model.
+pub struct ModeledOpponent<'a, P> {
+    pub solver: &'a P,
+    pub model: &'a StrategyTable,
+    pub seat: u8,
+}
+
+impl<P: Policy> Policy for ModeledOpponent<'_, P> {
+    fn action_probabilities(&self, info_set: &str, actions: &[Action]) -> Vec<f64> {
+        self.solver.action_probabilities(info_set, actions)
+    }
+
+    fn action_probabilities_for(&self, seat: u8, info_set: &str, actions: &[Action]) -> Vec<f64> {
+        if seat == self.seat {
+            self.model.action_probabilities(info_set, actions)
+        } else {
+            self.solver.action_probabilities(info_set, actions)
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use std::io::{self, Write};
+
+/// One column of a Parquet file. Every column is required (no nulls).
+pub enum Column {
+    Utf8(Vec<String>),
+    Int32(Vec<i32>),
+    Double(Vec<f64>),
+}
+
+impl Column {
+    fn len(&self) -> usize {
+        match self {
+            Column::Utf8(v) => v.len(),
+            Column::Int32(v) => v.len(),
+            Column::Double(v) => v.len(),
+        }
+    }
+
+    /// The Parquet physical type: INT32 = 1, DOUBLE = 5, BYTE_ARRAY = 6.
+    fn physical_type(&self) -> i32 {
+        match self {
+            Column::Utf8(_) => 6,
+            Column::Int32(_) => 1,
+            Column::Double(_) => 5,
+        }
+    }
+
+    /// PLAIN encoding: little-endian values, byte arrays prefixed by their length.
+    fn plain(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        match self {
+            Column::Utf8(values) => {
+                for v in values {
+                    out.extend_from_slice(&(v.len() as u32).to_le_bytes());
+                    out.extend_from_slice(v.as_bytes());
+                }
+            }
+            Column::Int32(values) => values.iter().for_each(|v| out.extend_from_slice(&v.to_le_bytes())),
+            Column::Double(values) => values.iter().for_each(|v| out.extend_from_slice(&v.to_le_bytes())),
+        }
+        out
+    }
+}
+
+const MAGIC: &[u8] = b"PAR1";
+const PLAIN: i32 = 0;
+const RLE: i32 = 3;
+const UNCOMPRESSED: i32 = 0;
+const DATA_PAGE: i32 = 0;
+const REQUIRED: i32 = 0;
+const UTF8: i32 = 0;
+
+/// Writes a Parquet file with a single row group and one uncompressed,
+/// PLAIN-encoded data page per column, which every Parquet reader accepts.
+/// `metadata` becomes the file's key-value metadata.
+pub fn write<W: Write>(out: &mut W, columns: &[(&str, Column)], metadata: &[(&str, String)]) -> io::Result<()> {
+    let rows = columns.first().map_or(0, |(_, c)| c.len());
+    assert!(columns.iter().all(|(_, c)| c.len() == rows), "parquet columns must have equal lengths");
+
+    let mut offset = MAGIC.len();
+    out.write_all(MAGIC)?;
+    // (data page offset, bytes written) per column.
+    let mut chunks = Vec::with_capacity(columns.len());
+    for (_, column) in columns {
+        let data = column.plain();
+        let mut header = Thrift::default();
+        header.i32(1, DATA_PAGE);
+        header.i32(2, data.len() as i32);
+        header.i32(3, data.len() as i32);
+        header.begin_struct(5);
+        header.i32(1, rows as i32);
+        header.i32(2, PLAIN);
+        header.i32(3, RLE);
+        header.i32(4, RLE);
+        header.end_struct();
+        header.stop();
+
+        out.write_all(&header.buf)?;
+        out.write_all(&data)?;
+        let size = header.buf.len() + data.len();
+        chunks.push((offset, size));
+        offset += size;
+    }
+
+    let mut meta = Thrift::default();
+    meta.i32(1, 1);
+    meta.list(2, Thrift::STRUCT, columns.len() + 1);
+    meta.begin_element();
+    meta.binary(4, b"schema");
+    meta.i32(5, columns.len() as i32);
+    meta.end_struct();
+    for (name, column) in columns {
+        meta.begin_element();
+        meta.i32(1, column.physical_type());
+        meta.i32(3, REQUIRED);
+        meta.binary(4, name.as_bytes());
+        if let Column::Utf8(_) = column {
+            meta.i32(6, UTF8);
+        }
+        meta.end_struct();
+    }
+    meta.i64(3, rows as i64);
+
+    meta.list(4, Thrift::STRUCT, 1);
+    meta.begin_element();
+    meta.list(1, Thrift::STRUCT, columns.len());
+    for ((name, column), (page_offset, size)) in columns.iter().zip(&chunks) {
+        meta.begin_element();
+        meta.i64(2, *page_offset as i64);
+        meta.begin_struct(3);
+        meta.i32(1, column.physical_type());
+        meta.list(2, Thrift::I32, 2);
+        meta.list_i32(PLAIN);
+        meta.list_i32(RLE);
+        meta.list(3, Thrift::BINARY, 1);
+        meta.list_binary(name.as_bytes());
+        meta.i32(4, UNCOMPRESSED);
+        meta.i64(5, rows as i64);
+        meta.i64(6, *size as i64);
+        meta.i64(7, *size as i64);
+        meta.i64(9, *page_offset as i64);
+        meta.end_struct();
+        meta.end_struct();
+    }
+    meta.i64(2, chunks.iter().map(|(_, size)| *size as i64).sum());
+    meta.i64(3, rows as i64);
+    meta.end_struct();
+
+    if !metadata.is_empty() {
+        meta.list(5, Thrift::STRUCT, metadata.len());
+        for (key, value) in metadata {
+            meta.begin_element();
+            meta.binary(1, key.as_bytes());
+            meta.binary(2, value.as_bytes());
+            meta.end_struct();
+        }
+    }
+    meta.binary(6, concat!("liars_dice_rust ", env!("CARGO_PKG_VERSION")).as_bytes());
+    meta.stop();
+
+    out.write_all(&meta.buf)?;
+    out.write_all(&(meta.buf.len() as u32).to_le_bytes())?;
+    out.write_all(MAGIC)
+}
+
+/// Just enough of the Thrift compact protocol for Parquet's page headers and footer.
+#[derive(Default)]
+struct Thrift {
+    buf: Vec<u8>,
+    /// Last field id written in each open struct, innermost last.
+    last_field: Vec<i16>,
+}
+
+impl Thrift {
+    const I32: u8 = 5;
+    const I64: u8 = 6;
+    const BINARY: u8 = 8;
+    const LIST: u8 = 9;
+    const STRUCT: u8 = 12;
+
+    fn field(&mut self, id: i16, kind: u8) {
+        if self.last_field.is_empty() {
+            self.last_field.push(0);
+        }
+        let last = self.last_field.last_mut().expect("an open struct");
+        let delta = id - *last;
+        *last = id;
+        if (1..=15).contains(&delta) {
+            self.buf.push(((delta as u8) << 4) | kind);
+        } else {
+            self.buf.push(kind);
+            self.varint(zigzag(id as i64));
+        }
+    }
+
+    fn varint(&mut self, mut v: u64) {
+        while v >= 0x80 {
+            self.buf.push((v as u8) | 0x80);
+            v >>= 7;
+        }
+        self.buf.push(v as u8);
+    }
+
+    fn i32(&mut self, id: i16, v: i32) {
+        self.field(id, Self::I32);
+        self.varint(zigzag(v as i64));
+    }
+
+    fn i64(&mut self, id: i16, v: i64) {
+        self.field(id, Self::I64);
+        self.varint(zigzag(v));
+    }
+
+    fn binary(&mut self, id: i16, v: &[u8]) {
+        self.field(id, Self::BINARY);
+        self.list_binary(v);
+    }
+
+    fn begin_struct(&mut self, id: i16) {
+        self.field(id, Self::STRUCT);
+        self.last_field.push(0);
+    }
+
+    /// Starts a struct that is a list element, which has no field header.
+    fn begin_element(&mut self) {
+        if self.last_field.is_empty() {
+            self.last_field.push(0);
+        }
+        self.last_field.push(0);
+    }
+
+    fn end_struct(&mut self) {
+        self.buf.push(0);
+        self.last_field.pop();
+    }
+
+    /// Ends the top-level struct.
+    fn stop(&mut self) {
+        self.buf.push(0);
+    }
+
+    fn list(&mut self, id: i16, element: u8, len: usize) {
+        self.field(id, Self::LIST);
+        if len < 15 {
+            self.buf.push(((len as u8) << 4) | element);
+        } else {
+            self.buf.push(0xF0 | element);
+            self.varint(len as u64);
+        }
+    }
+
+    fn list_i32(&mut self, v: i32) {
+        self.varint(zigzag(v as i64));
+    }
+
+    fn list_binary(&mut self, v: &[u8]) {
+        self.varint(v.len() as u64);
+        self.buf.extend_from_slice(v);
+    }
+}
+
+fn zigzag(v: i64) -> u64 {
+    ((v << 1) ^ (v >> 63)) as u64
+}
@@ -0,0 +1,197 @@
+use crate::analyze::Beliefs;
+use crate::blend::StrategyBlend;
+use crate::game::{Action, GameConfig, GameState};
+use crate::profile::{self, GameEntry, Mistake};
+use crate::record::{self, GameRecord};
+use crate::rollout::RolloutFallback;
+use crate::strategy::{apply_temperature, sample_index, Policy};
+use std::io::{self, BufRead, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Options for an interactive session against the bot.
+pub struct PlayOptions {
+    /// After each of the human's moves, show what the solver would have done.
+    pub hints: bool,
+    /// EV loss, in dice, above which a hinted move counts as a mistake.
+    pub threshold: f64,
+    /// Where to write a game record of every round played.
+    pub record: Option<String>,
+    /// Rollouts for the bot where its strategy has no informed mix.
+    pub fallback: Option<RolloutFallback>,
+    /// How the bot samples its mix (see `apply_temperature`): 1 plays the
+    /// strategy as solved, lower is stronger and more predictable, higher noisier.
+    pub temperature: f64,
+    /// Where to append each game's result and hint score, for `stats`.
+    pub profile: Option<String>,
+}
+
+/// Running hint-mode score for the session.
+#[derive(Clone, Default)]
+struct Accuracy {
+    decisions: usize,
+    accurate: usize,
+    ev_lost: f64,
+    mistakes: Vec<Mistake>,
+}
+
+/// Plays rounds against the bot until the human declines another, with the
+/// human as player 1 (the opener). The bot plays `blend` in its own mode;
+/// hints and beliefs about the bot's hand use the full mixture.
+pub fn play_session(blend: &StrategyBlend, config: &GameConfig, options: &PlayOptions) -> io::Result<()> {
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut rng = rand::thread_rng();
+    let mut accuracy = Accuracy::default();
+    let mut records = Vec::new();
+    let session = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+
+    loop {
+        let before = accuracy.clone();
+        let mut state = GameState::new(config);
+        let mut beliefs = Beliefs::new(config);
+        let bot = blend.for_game(blend.pick_member(&mut rng));
+        println!("You have {:?}. Bot has {} dice.", state.hand_p1, state.dice_p2);
+        if !state.revealed[1].is_empty() {
+            println!("You can see the bot's {:?}.", state.revealed[1]);
+        }
+        if !state.revealed[0].is_empty() {
+            println!("The bot can see your {:?}.", state.revealed[0]);
+        }
+
+        loop {
+            println!();
+            println!("Current Bid: {}", state.current_bid.map_or("None".to_string(), |(q, f)| format!("{}-{}", q, f)));
+            let actions = state.get_valid_actions();
+
+            let chosen = if state.current_player == 0 {
+                println!("Valid actions:");
+                for (i, a) in actions.iter().enumerate() {
+                    println!("{}: {}", i, a);
+                }
+                let chosen = loop {
+                    print!("Enter action index: ");
+                    io::stdout().flush()?;
+                    let Some(line) = lines.next() else {
+                        return Ok(());
+                    };
+                    match line?.trim().parse::<usize>() {
+                        Ok(i) if i < actions.len() => break i,
+                        _ => println!("Invalid choice."),
+                    }
+                };
+                println!("You chose: {}", actions[chosen]);
+                if options.hints {
+                    show_hint(blend, &state, &beliefs, &actions, chosen, options.threshold, &mut accuracy);
+                }
+                chosen
+            } else {
+                let probs = match &options.fallback {
+                    Some(fallback) => fallback.action_probabilities(&bot, &state, &actions, &mut rng),
+                    None => bot.action_probabilities(&state.get_information_set(), &actions),
+                };
+                let chosen = sample_index(&apply_temperature(&probs, options.temperature), &mut rng);
+                println!("Bot chooses: {}", actions[chosen]);
+                chosen
+            };
+
+            beliefs.observe(blend, &state, &actions, chosen);
+            if state.apply_action(actions[chosen].clone()) {
+                break;
+            }
+        }
+
+        report_round(&state);
+        records.push(GameRecord::from_state(&state, ["You".to_string(), "Bot".to_string()]));
+        if let Some(path) = &options.record {
+            record::write_records(path, &records)?;
+        }
+        if let Some(path) = &options.profile {
+            let entry = GameEntry {
+                session,
+                dice: format!("{}v{}", config.dice_p1, config.dice_p2),
+                result: state.payoff_for(0),
+                decisions: accuracy.decisions - before.decisions,
+                accurate: accuracy.accurate - before.accurate,
+                ev_lost: accuracy.ev_lost - before.ev_lost,
+                mistakes: accuracy.mistakes[before.mistakes.len()..].iter().map(|m| m.to_string()).collect(),
+            };
+            profile::append_game(path, &entry)?;
+        }
+
+        print!("Play again? [y/N] ");
+        io::stdout().flush()?;
+        let again = match lines.next() {
+            Some(line) => line?.trim().eq_ignore_ascii_case("y"),
+            None => false,
+        };
+        if !again {
+            break;
+        }
+    }
+
+    if options.hints && accuracy.decisions > 0 {
+        println!(
+            "Session accuracy: {}/{} moves within {:.1}% of a die of the solver ({:.3} dice of EV given up).",
+            accuracy.accurate,
+            accuracy.decisions,
+            options.threshold * 100.0,
+            accuracy.ev_lost
+        );
+    }
+    Ok(())
+}
+
+/// Prints the solver's mix, the EV of the chosen move against it, and the running score.
+fn show_hint(
+    blend: &StrategyBlend,
+    state: &GameState,
+    beliefs: &Beliefs,
+    actions: &[Action],
+    chosen: usize,
+    threshold: f64,
+    accuracy: &mut Accuracy,
+) {
+    let probs = blend.action_probabilities(&state.get_information_set(), actions);
+    let values = beliefs.action_values(blend, state, actions);
+    let solver_ev: f64 = probs.iter().zip(&values).map(|(p, v)| p * v).sum();
+    let loss = solver_ev - values[chosen];
+
+    let mut suggested: Vec<(usize, f64)> = probs.iter().copied().enumerate().filter(|(_, p)| *p >= 0.01).collect();
+    suggested.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let mix: Vec<String> = suggested.iter().map(|(i, p)| format!("{} {:.0}%", actions[*i], p * 100.0)).collect();
+
+    accuracy.decisions += 1;
+    accuracy.ev_lost += loss.max(0.0);
+    let mistake = (loss > threshold).then(|| Mistake::classify(actions, &probs, chosen));
+    match mistake {
+        Some(m) => accuracy.mistakes.push(m),
+        None => accuracy.accurate += 1,
+    }
+    println!("  Solver plays: {}", mix.join(", "));
+    println!("  Your move EV {:+.3} vs solver {:+.3} ({:+.3})", values[chosen], solver_ev, -loss);
+    if let Some(m) = mistake {
+        println!("  Mistake: {}", m);
+    }
+    println!(
+        "  Accuracy so far: {}/{} ({:.0}%)",
+        accuracy.accurate,
+        accuracy.decisions,
+        100.0 * accuracy.accurate as f64 / accuracy.decisions as f64
+    );
+}
+
+fn report_round(state: &GameState) {
+    let payoff = state.challenger_payoff();
+    let call = if state.final_call == Some(Action::Calza) { "called Calza" } else { "challenged" };
+
+    println!();
+    println!("--- Game Over ---");
+    println!("Your hand: {:?}", state.hand_p1);
+    println!("Bot's hand: {:?}", state.hand_p2);
+    let outcome = if payoff > 0.0 { "WON" } else { "LOST" };
+    if state.current_player == 0 {
+        println!("You {} and {} ({:+} dice).", call, outcome, payoff);
+    } else {
+        println!("Bot {} and {} ({:+} dice for you).", call, outcome, -payoff);
+    }
+}
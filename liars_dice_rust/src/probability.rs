@@ -0,0 +1,50 @@
+use crate::game::GameConfig;
+
+/// Chance that one unseen die counts towards a bid on `face`. Only dice
+/// showing the face itself count, so this is the face's roll weight.
+pub fn counts_towards(config: &GameConfig, face: u8) -> f64 {
+    config.face_weights[face as usize - 1]
+}
+
+/// P(exactly `k` of `n` unseen dice count), each counting with probability `p`.
+pub fn exactly(n: u8, k: u8, p: f64) -> f64 {
+    if k > n {
+        return 0.0;
+    }
+    binomial(n, k) * p.powi(k as i32) * (1.0 - p).powi((n - k) as i32)
+}
+
+/// P(at least `k` of `n` unseen dice count), each counting with probability `p`.
+pub fn at_least(n: u8, k: u8, p: f64) -> f64 {
+    (k..=n).fold(0.0, |acc, j| acc + exactly(n, j, p)).min(1.0)
+}
+
+/// How plausible a bid is to someone holding `hand`, with every other die in play unseen.
+pub struct BidOdds {
+    /// Dice in `hand` that already count towards the bid.
+    pub in_hand: u8,
+    pub unseen: u8,
+    /// P(the bid is true), i.e. a challenge would fail.
+    pub truth: f64,
+    /// P(the count is exactly the bid), i.e. a calza would succeed.
+    pub exact: f64,
+}
+
+pub fn bid_odds(config: &GameConfig, hand: &[u8], quantity: u8, face: u8) -> BidOdds {
+    let total = config.dice_p1 + config.dice_p2;
+    let in_hand = hand.iter().filter(|&&d| d == face).count() as u8;
+    let unseen = total.saturating_sub(hand.len() as u8);
+    let p = counts_towards(config, face);
+    let needed = quantity.saturating_sub(in_hand);
+    BidOdds {
+        in_hand,
+        unseen,
+        truth: at_least(unseen, needed, p),
+        exact: if in_hand > quantity { 0.0 } else { exactly(unseen, needed, p) },
+    }
+}
+
+fn binomial(n: u8, k: u8) -> f64 {
+    let k = k.min(n - k);
+    (0..k).fold(1.0, |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
@@ -0,0 +1,227 @@
+use crate::game::Action;
+use crate::record::{json_string, parse_flat_object, JsonValue};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+
+/// Where play mode keeps its history unless `--profile` says otherwise.
+pub const DEFAULT_PROFILE: &str = "../play_profile.jsonl";
+
+/// What kind of error a hinted move was, judged against the solver's
+/// favourite action.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Mistake {
+    /// Challenged where the solver would have raised.
+    NeedlessChallenge,
+    /// Raised where the solver would have challenged.
+    MissedChallenge,
+    /// A failing or ill-judged Calza.
+    BadCalza,
+    /// Bid a higher quantity than the solver's bid.
+    Overbid,
+    /// Bid a lower quantity than the solver's bid.
+    Underbid,
+    /// The solver's quantity on another face.
+    WrongFace,
+    /// Anything else, e.g. a bid where the solver would have called Calza.
+    Other,
+}
+
+impl Mistake {
+    /// Classifies playing `actions[chosen]` where the solver plays `probs`.
+    pub fn classify(actions: &[Action], probs: &[f64], chosen: usize) -> Self {
+        let best = (0..actions.len()).max_by(|&a, &b| probs[a].total_cmp(&probs[b])).unwrap_or(chosen);
+        match (&actions[chosen], &actions[best]) {
+            (Action::Calza, _) => Mistake::BadCalza,
+            (Action::Challenge, Action::Bid(..)) => Mistake::NeedlessChallenge,
+            (Action::Bid(..), Action::Challenge) => Mistake::MissedChallenge,
+            (Action::Bid(q, _), Action::Bid(best_q, _)) if q > best_q => Mistake::Overbid,
+            (Action::Bid(q, _), Action::Bid(best_q, _)) if q < best_q => Mistake::Underbid,
+            (Action::Bid(_, f), Action::Bid(_, best_f)) if f != best_f => Mistake::WrongFace,
+            _ => Mistake::Other,
+        }
+    }
+}
+
+impl fmt::Display for Mistake {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Mistake::NeedlessChallenge => "needless-challenge",
+            Mistake::MissedChallenge => "missed-challenge",
+            Mistake::BadCalza => "bad-calza",
+            Mistake::Overbid => "overbid",
+            Mistake::Underbid => "underbid",
+            Mistake::WrongFace => "wrong-face",
+            Mistake::Other => "other",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// One game played against the bot, as the profile keeps it.
+pub struct GameEntry {
+    /// When the session started, in seconds since the Unix epoch.
+    pub session: u64,
+    /// The opening dice, e.g. `2v2`.
+    pub dice: String,
+    /// The human's payoff in dice.
+    pub result: f32,
+    /// Hint-mode counts for this game; zero without hints.
+    pub decisions: usize,
+    pub accurate: usize,
+    pub ev_lost: f64,
+    pub mistakes: Vec<String>,
+}
+
+impl GameEntry {
+    pub fn to_json(&self) -> String {
+        let mistakes: Vec<String> = self.mistakes.iter().map(|m| json_string(m)).collect();
+        format!(
+            "{{\"session\":{},\"dice\":{},\"result\":{},\"decisions\":{},\"accurate\":{},\"ev_lost\":{},\"mistakes\":[{}]}}",
+            self.session,
+            json_string(&self.dice),
+            self.result,
+            self.decisions,
+            self.accurate,
+            self.ev_lost,
+            mistakes.join(",")
+        )
+    }
+
+    pub fn parse(line: &str) -> Option<Self> {
+        let fields = parse_flat_object(line)?;
+        let number = |key: &str| match fields.get(key) {
+            Some(JsonValue::Number(n)) => Some(*n),
+            _ => None,
+        };
+        Some(GameEntry {
+            session: number("session")? as u64,
+            dice: match fields.get("dice") {
+                Some(JsonValue::String(dice)) => dice.clone(),
+                _ => return None,
+            },
+            result: number("result")? as f32,
+            decisions: number("decisions")? as usize,
+            accurate: number("accurate")? as usize,
+            ev_lost: number("ev_lost")?,
+            mistakes: match fields.get("mistakes") {
+                Some(JsonValue::List(items)) => items.clone(),
+                _ => return None,
+            },
+        })
+    }
+}
+
+/// Appends one game to the profile at `path`, creating it if needed.
+pub fn append_game(path: &str, entry: &GameEntry) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", entry.to_json())
+}
+
+pub fn read_profile(path: &str) -> io::Result<Vec<GameEntry>> {
+    fs::read_to_string(path)?
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| GameEntry::parse(line).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("invalid profile entry at line {}", i + 1))))
+        .collect()
+}
+
+/// Games won and played, and hint-mode totals, over some games.
+#[derive(Default)]
+struct Tally {
+    games: usize,
+    wins: usize,
+    dice: f64,
+    decisions: usize,
+    accurate: usize,
+}
+
+impl Tally {
+    fn add(&mut self, entry: &GameEntry) {
+        self.games += 1;
+        self.wins += (entry.result > 0.0) as usize;
+        self.dice += entry.result as f64;
+        self.decisions += entry.decisions;
+        self.accurate += entry.accurate;
+    }
+
+    fn accuracy(&self) -> String {
+        match self.decisions {
+            0 => "-".to_string(),
+            n => format!("{:.0}%", 100.0 * self.accurate as f64 / n as f64),
+        }
+    }
+}
+
+/// A profile summarized: overall and per-session results and the mistakes
+/// hint mode flagged, most common first.
+pub struct ProfileStats {
+    total: Tally,
+    sessions: BTreeMap<u64, Tally>,
+    mistakes: Vec<(String, usize)>,
+}
+
+impl ProfileStats {
+    pub fn collect(entries: &[GameEntry]) -> Self {
+        let mut total = Tally::default();
+        let mut sessions: BTreeMap<u64, Tally> = BTreeMap::new();
+        let mut mistakes: BTreeMap<&str, usize> = BTreeMap::new();
+        for entry in entries {
+            total.add(entry);
+            sessions.entry(entry.session).or_default().add(entry);
+            for m in &entry.mistakes {
+                *mistakes.entry(m).or_default() += 1;
+            }
+        }
+        let mut mistakes: Vec<(String, usize)> = mistakes.into_iter().map(|(m, n)| (m.to_string(), n)).collect();
+        mistakes.sort_by_key(|m| Reverse(m.1));
+        ProfileStats { total, sessions, mistakes }
+    }
+}
+
+impl fmt::Display for ProfileStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let t = &self.total;
+        writeln!(
+            f,
+            "{} games over {} sessions: won {} ({:.1}%), {:+.2} dice per game, hint accuracy {}",
+            t.games,
+            self.sessions.len(),
+            t.wins,
+            100.0 * t.wins as f64 / t.games.max(1) as f64,
+            t.dice / t.games.max(1) as f64,
+            t.accuracy()
+        )?;
+        writeln!(f)?;
+        writeln!(f, "{:<12} {:>6} {:>8} {:>9}", "Session", "Games", "Win rate", "Accuracy")?;
+        for (start, s) in &self.sessions {
+            writeln!(f, "{:<12} {:>6} {:>7.1}% {:>9}", date(*start), s.games, 100.0 * s.wins as f64 / s.games as f64, s.accuracy())?;
+        }
+        if !self.mistakes.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "Most common mistakes:")?;
+            for (m, n) in &self.mistakes {
+                writeln!(f, "  {:<20} {}", m, n)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The UTC calendar date of a Unix time, as YYYY-MM-DD.
+fn date(secs: u64) -> String {
+    // Howard Hinnant's days-to-civil algorithm.
+    let z = (secs / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
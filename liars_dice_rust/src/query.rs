@@ -0,0 +1,175 @@
+use crate::game::{Action, GameConfig, GameState, DICE_FACES};
+use crate::record::{json_string, parse_object_array, JsonValue};
+use crate::rollout::RolloutFallback;
+use crate::strategy::{action_to_str, apply_temperature, parse_action, Policy};
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+
+/// A decision point to look up: the hand of the player to move and the bids
+/// made so far this round.
+pub struct Situation {
+    pub hand: Vec<u8>,
+    pub bids: Vec<Action>,
+    /// The other player's dice, to pick the opening when the rules allow
+    /// more than one with the mover holding this many dice.
+    pub opponent_dice: Option<u8>,
+}
+
+/// A policy's answer for one situation.
+pub struct Answer {
+    pub info_set: String,
+    /// Every legal action with its probability.
+    pub actions: Vec<(Action, f64)>,
+}
+
+impl Situation {
+    /// Reads a hand of digits ("1356") and bids separated by '/' or spaces
+    /// ("1-2/2-5", empty before the opening bid).
+    pub fn parse(hand: &str, bids: &str, opponent_dice: Option<u8>) -> Result<Self, String> {
+        let hand = hand
+            .trim()
+            .chars()
+            .map(|c| c.to_digit(10).map(|d| d as u8))
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(|| format!("unreadable hand '{}'", hand))?;
+        let bids = bids
+            .split(['/', ' '])
+            .filter(|b| !b.is_empty())
+            .map(|b| parse_action(b).ok_or_else(|| format!("unreadable bid '{}'", b)))
+            .collect::<Result<_, _>>()?;
+        Ok(Situation { hand, bids, opponent_dice })
+    }
+
+    /// The state the situation describes under `rules`. The opponent's hand is
+    /// a placeholder: only the mover's hand enters their info set.
+    pub fn state(&self, rules: &GameConfig) -> Result<GameState, String> {
+        let mover = self.bids.len() % 2;
+        let round = rules
+            .openings()
+            .into_iter()
+            .map(|(round, _)| round)
+            .find(|round| {
+                let dice = [round.dice_p1, round.dice_p2];
+                dice[mover] as usize == self.hand.len() && self.opponent_dice.is_none_or(|d| dice[1 - mover] == d)
+            })
+            .ok_or_else(|| format!("no opening of the rules has the player to move holding {} dice", self.hand.len()))?;
+        if self.hand.iter().any(|&d| d == 0 || d > DICE_FACES) {
+            return Err("hand has a die outside 1-6".to_string());
+        }
+        let other = vec![1; if mover == 0 { round.dice_p2 } else { round.dice_p1 } as usize];
+        let (hand_p1, hand_p2) = if mover == 0 { (self.hand.clone(), other) } else { (other, self.hand.clone()) };
+        let state = GameState::from_history(&round, hand_p1, hand_p2, &self.bids)?;
+        if state.final_call.is_some() {
+            return Err("the round is already over".to_string());
+        }
+        Ok(state)
+    }
+
+    /// The policy's distribution over the legal actions here, from rollouts
+    /// with `fallback` where the policy is uninformed, reshaped by `temperature`
+    /// (see `apply_temperature`).
+    pub fn answer<P: Policy>(&self, policy: &P, rules: &GameConfig, fallback: Option<&RolloutFallback>, temperature: f64) -> Result<Answer, String> {
+        let state = self.state(rules)?;
+        let info_set = state.get_information_set();
+        let legal = state.get_valid_actions();
+        let probs = match fallback {
+            Some(fallback) => fallback.action_probabilities(policy, &state, &legal, &mut rand::thread_rng()),
+            None => policy.action_probabilities_for(state.current_player, &info_set, &legal),
+        };
+        Ok(Answer { info_set, actions: legal.into_iter().zip(apply_temperature(&probs, temperature)).collect() })
+    }
+}
+
+impl Answer {
+    /// `{"info_set": "...", "actions": {"<action>": p, ...}}`.
+    pub fn to_json(&self) -> String {
+        let actions: Vec<String> = self.actions.iter().map(|(a, p)| format!("{}:{}", json_string(&action_to_str(a)), p)).collect();
+        format!("{{\"info_set\":{},\"actions\":{{{}}}}}", json_string(&self.info_set), actions.join(","))
+    }
+}
+
+/// Answers a JSON array of `{"hand": "1356", "bids": ["1-2", "2-5"]}` objects
+/// (with an optional `"opponent_dice"`) as an array of answers in the same
+/// order; a situation that cannot be read or reached gets `{"error": "..."}`.
+pub fn answer_json<P: Policy>(policy: &P, rules: &GameConfig, fallback: Option<&RolloutFallback>, temperature: f64, body: &str) -> Result<String, String> {
+    let objects = parse_object_array(body).ok_or("expected a JSON array of situations")?;
+    let answers: Vec<String> = objects
+        .iter()
+        .map(|fields| match situation_from_json(fields).and_then(|s| s.answer(policy, rules, fallback, temperature)) {
+            Ok(answer) => answer.to_json(),
+            Err(e) => format!("{{\"error\":{}}}", json_string(&e)),
+        })
+        .collect();
+    Ok(format!("[{}]", answers.join(",\n")))
+}
+
+fn situation_from_json(fields: &HashMap<String, JsonValue>) -> Result<Situation, String> {
+    let hand = match fields.get("hand") {
+        Some(JsonValue::String(hand)) => hand,
+        _ => return Err("missing \"hand\"".to_string()),
+    };
+    let bids = match fields.get("bids") {
+        Some(JsonValue::List(bids)) => bids.join("/"),
+        Some(JsonValue::String(bids)) => bids.clone(),
+        None => String::new(),
+        _ => return Err("\"bids\" must be a list of actions".to_string()),
+    };
+    let opponent_dice = match fields.get("opponent_dice") {
+        Some(JsonValue::Number(n)) => Some(*n as u8),
+        None => None,
+        _ => return Err("\"opponent_dice\" must be a number".to_string()),
+    };
+    Situation::parse(hand, &bids, opponent_dice)
+}
+
+/// Reads a CSV of situations with columns `hand`, `bids` and optionally
+/// `opponent_dice`, and writes one `hand,bids,info_set,action,probability`
+/// row per legal action. Rows that cannot be answered are reported on stderr
+/// and skipped; returns (answered, skipped).
+pub fn answer_csv<P: Policy, R: Read, W: Write>(
+    policy: &P,
+    rules: &GameConfig,
+    fallback: Option<&RolloutFallback>,
+    temperature: f64,
+    input: R,
+    out: &mut W,
+) -> io::Result<(usize, usize)> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).trim(csv::Trim::All).from_reader(input);
+    let headers = reader.headers().map_err(io::Error::other)?.clone();
+    let column = |name: &str| headers.iter().position(|h| h == name);
+    let hand_column = column("hand").ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "situations need a 'hand' column"))?;
+    let (bids_column, dice_column) = (column("bids"), column("opponent_dice"));
+
+    writeln!(out, "hand,bids,info_set,action,probability")?;
+    let (mut answered, mut skipped) = (0, 0);
+    for record in reader.records() {
+        let record = record.map_err(io::Error::other)?;
+        let line = record.position().map_or(0, |p| p.line());
+        let hand = record.get(hand_column).unwrap_or("");
+        let bids = bids_column.and_then(|c| record.get(c)).unwrap_or("");
+        let opponent_dice = match dice_column.and_then(|c| record.get(c)).filter(|d| !d.is_empty()) {
+            Some(d) => match d.parse() {
+                Ok(d) => Some(d),
+                Err(_) => {
+                    eprintln!("line {}: unreadable opponent_dice '{}'", line, d);
+                    skipped += 1;
+                    continue;
+                }
+            },
+            None => None,
+        };
+        match Situation::parse(hand, bids, opponent_dice).and_then(|s| s.answer(policy, rules, fallback, temperature)) {
+            Ok(answer) => {
+                for (action, p) in &answer.actions {
+                    writeln!(out, "{},{},{},{},{}", hand, bids, answer.info_set, action_to_str(action), p)?;
+                }
+                answered += 1;
+            }
+            Err(e) => {
+                eprintln!("line {}: {}", line, e);
+                skipped += 1;
+            }
+        }
+    }
+    Ok((answered, skipped))
+}
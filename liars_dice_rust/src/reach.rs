@@ -0,0 +1,63 @@
+use crate::game::{hand_distribution, Action, GameConfig, GameState};
+use crate::strategy::Policy;
+use std::collections::HashMap;
+
+/// Probability that play under `policy` reaches each info set, chance included.
+///
+/// Walks the public bid tree carrying both players' reach for every possible
+/// hand, so like the best response it is only practical for small dice counts.
+/// Info sets the abstraction merges add up their reach over bid sequences,
+/// and over openers when either seat may open.
+pub fn info_set_reach<P: Policy>(policy: &P, config: &GameConfig) -> HashMap<String, f64> {
+    info_set_reach_and_actions(policy, config).into_iter().map(|(key, (reach, _))| (key, reach)).collect()
+}
+
+/// As `info_set_reach`, with each info set's legal actions.
+pub fn info_set_reach_and_actions<P: Policy>(policy: &P, config: &GameConfig) -> HashMap<String, (f64, Vec<Action>)> {
+    let mut out = HashMap::new();
+    for (round, p) in config.openings() {
+        let hands = [hand_distribution(round.dice_p1, &round), hand_distribution(round.dice_p2, &round)];
+        let reach = [
+            hands[0].iter().map(|(_, q)| p * q).collect(),
+            hands[1].iter().map(|(_, q)| *q).collect(),
+        ];
+        visit(policy, &GameState::new(&round), &hands, &reach, &mut out);
+    }
+    out
+}
+
+fn visit<P: Policy>(
+    policy: &P,
+    state: &GameState,
+    hands: &[Vec<(Vec<u8>, f64)>; 2],
+    reach: &[Vec<f64>; 2],
+    out: &mut HashMap<String, (f64, Vec<Action>)>,
+) {
+    let player = state.current_player as usize;
+    let opp_reach: f64 = reach[1 - player].iter().sum();
+    let actions = state.get_valid_actions();
+
+    let probs: Vec<Vec<f64>> = hands[player]
+        .iter()
+        .zip(&reach[player])
+        .map(|((hand, _), &own_reach)| {
+            let info_set = state.information_set_for(hand);
+            let probs = policy.action_probabilities(&info_set, &actions);
+            out.entry(info_set).or_insert_with(|| (0.0, actions.clone())).0 += own_reach * opp_reach;
+            probs
+        })
+        .collect();
+
+    for (i, action) in actions.iter().enumerate() {
+        let mut next = state.clone();
+        if next.apply_action(action.clone()) {
+            continue;
+        }
+        let mut child = reach.clone();
+        child[player] = reach[player].iter().zip(&probs).map(|(r, p)| r * p[i]).collect();
+        if child[player].iter().all(|&r| r == 0.0) {
+            continue;
+        }
+        visit(policy, &next, hands, &child, out);
+    }
+}
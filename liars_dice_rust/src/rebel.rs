@@ -0,0 +1,317 @@
+use crate::game::{hand_distribution, Action, GameConfig, GameState};
+use crate::strategy::{sample_index, StrategyTable};
+use rand::Rng;
+use std::collections::HashMap;
+
+// Experimental public-belief-state solver in the style of ReBeL.
+//
+// A public belief state (PBS) is the bid history everyone sees plus, for each
+// player, a distribution over the hands they might hold. From a PBS the solver
+// runs CFR over the next `depth` bids for every hand at once and scores the
+// frontier with a value function instead of searching to the end of the round,
+// so the cost depends on the search depth rather than the size of the game.
+//
+// Self-play moves from PBS to PBS by sampling actions from each search's root
+// policy and updating the beliefs with Bayes' rule. The values each search
+// computes at its root are the training data for the value function used at
+// later frontiers. That function is a table keyed by player, hand and the
+// public info set rather than a network, and falls back to a prior where it
+// has no data: by default "the player to move challenges now", or estimates
+// carried over from smaller games (see curriculum.rs). Nothing here is tuned;
+// treat results as a baseline for experiments.
+
+/// Every hand each player can hold, with its prior probability.
+pub type Hands = [Vec<(Vec<u8>, f64)>; 2];
+/// One value per hand for each player.
+pub type PerHand = [Vec<f64>; 2];
+
+pub trait ValueFunction {
+    /// Expected value of each hand for each player at `state` when hands are
+    /// distributed according to `beliefs`.
+    fn values(&self, state: &GameState, hands: &Hands, beliefs: &PerHand) -> PerHand;
+}
+
+/// Values averaged from the roots of earlier searches.
+#[derive(Default)]
+pub struct LearnedValues {
+    table: HashMap<String, (f64, f64)>,
+    prior: Option<Box<dyn ValueFunction>>,
+}
+
+impl LearnedValues {
+    /// An empty table that answers from `prior` instead of an immediate
+    /// challenge until searches fill it in.
+    pub fn with_prior(prior: Box<dyn ValueFunction>) -> Self {
+        LearnedValues { table: HashMap::new(), prior: Some(prior) }
+    }
+
+    fn key(state: &GameState, player: usize, hand: &[u8]) -> String {
+        format!("{}|{}", player, state.information_set_for(hand))
+    }
+
+    /// Records search results, weighting each hand by how likely it is.
+    fn observe(&mut self, state: &GameState, hands: &Hands, values: &PerHand, beliefs: &PerHand) {
+        for player in 0..2 {
+            for (((hand, _), &value), &weight) in hands[player].iter().zip(&values[player]).zip(&beliefs[player]) {
+                if weight > 0.0 {
+                    let entry = self.table.entry(Self::key(state, player, hand)).or_insert((0.0, 0.0));
+                    entry.0 += weight * value;
+                    entry.1 += weight;
+                }
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+}
+
+impl ValueFunction for LearnedValues {
+    fn values(&self, state: &GameState, hands: &Hands, beliefs: &PerHand) -> PerHand {
+        let mut values = match &self.prior {
+            Some(prior) => prior.values(state, hands, beliefs),
+            None => challenge_values(state, hands, beliefs),
+        };
+        for (player, player_values) in values.iter_mut().enumerate() {
+            for ((hand, _), value) in hands[player].iter().zip(player_values.iter_mut()) {
+                if let Some((sum, weight)) = self.table.get(&Self::key(state, player, hand)) {
+                    *value = sum / weight;
+                }
+            }
+        }
+        values
+    }
+}
+
+/// Values if the player to move challenges the current bid right away.
+pub fn challenge_values(state: &GameState, hands: &Hands, beliefs: &PerHand) -> PerHand {
+    let mut called = state.clone();
+    if called.current_bid.is_none() || !called.apply_action(Action::Challenge) {
+        return [vec![0.0; hands[0].len()], vec![0.0; hands[1].len()]];
+    }
+    terminal_values(&called, hands, beliefs)
+}
+
+/// Counterfactual values at a finished round: each hand's payoff summed over
+/// the opponent's hands weighted by `reach`.
+fn terminal_values(state: &GameState, hands: &Hands, reach: &PerHand) -> PerHand {
+    let mut values = [vec![0.0; hands[0].len()], vec![0.0; hands[1].len()]];
+    let mut scored = state.clone();
+
+    for (i, (hand0, _)) in hands[0].iter().enumerate() {
+        for (j, (hand1, _)) in hands[1].iter().enumerate() {
+            if reach[0][i] == 0.0 && reach[1][j] == 0.0 {
+                continue;
+            }
+            scored.hand_p1.clone_from(hand0);
+            scored.hand_p2.clone_from(hand1);
+            let payoff = scored.payoff_for(0) as f64;
+            values[0][i] += reach[1][j] * payoff;
+            values[1][j] -= reach[0][i] * payoff;
+        }
+    }
+    values
+}
+
+/// Regrets and strategy sums at one public node, indexed [hand][action].
+struct SubgameNode {
+    regrets: Vec<Vec<f64>>,
+    strategy_sum: Vec<Vec<f64>>,
+}
+
+impl SubgameNode {
+    fn new(num_hands: usize, num_actions: usize) -> Self {
+        SubgameNode {
+            regrets: vec![vec![0.0; num_actions]; num_hands],
+            strategy_sum: vec![vec![0.0; num_actions]; num_hands],
+        }
+    }
+
+    fn current_strategy(&self) -> Vec<Vec<f64>> {
+        self.regrets.iter().map(|r| normalized(r.iter().map(|&x| x.max(0.0)).collect())).collect()
+    }
+
+    fn average_strategy(&self) -> Vec<Vec<f64>> {
+        self.strategy_sum.iter().map(|s| normalized(s.clone())).collect()
+    }
+}
+
+fn normalized(mut weights: Vec<f64>) -> Vec<f64> {
+    let total: f64 = weights.iter().sum();
+    if total > 0.0 {
+        weights.iter_mut().for_each(|w| *w /= total);
+    } else {
+        let uniform = 1.0 / weights.len() as f64;
+        weights.iter_mut().for_each(|w| *w = uniform);
+    }
+    weights
+}
+
+/// CFR+ over the next `depth` bids from one PBS, vectorised over hands.
+struct Subgame<'a, V: ValueFunction> {
+    hands: &'a Hands,
+    value_fn: &'a V,
+    nodes: HashMap<Vec<Action>, SubgameNode>,
+}
+
+impl<V: ValueFunction> Subgame<'_, V> {
+    fn walk(&mut self, state: &GameState, reach: &PerHand, depth_left: usize) -> PerHand {
+        if depth_left == 0 {
+            let beliefs = [normalized(reach[0].clone()), normalized(reach[1].clone())];
+            let mut values = self.value_fn.values(state, self.hands, &beliefs);
+            for (player, player_values) in values.iter_mut().enumerate() {
+                let opp_reach: f64 = reach[1 - player].iter().sum();
+                player_values.iter_mut().for_each(|v| *v *= opp_reach);
+            }
+            return values;
+        }
+
+        let player = state.current_player as usize;
+        let actions = state.get_valid_actions();
+        let num_hands = self.hands[player].len();
+        let strategy = self
+            .nodes
+            .entry(state.history.clone())
+            .or_insert_with(|| SubgameNode::new(num_hands, actions.len()))
+            .current_strategy();
+
+        let mut node_values = [vec![0.0; self.hands[0].len()], vec![0.0; self.hands[1].len()]];
+        let mut action_values = Vec::with_capacity(actions.len());
+        for (a, action) in actions.iter().enumerate() {
+            let mut child_reach = reach.clone();
+            child_reach[player] = reach[player].iter().zip(&strategy).map(|(r, s)| r * s[a]).collect();
+
+            let mut next = state.clone();
+            let child = if next.apply_action(action.clone()) {
+                terminal_values(&next, self.hands, &child_reach)
+            } else {
+                self.walk(&next, &child_reach, depth_left - 1)
+            };
+
+            for (h, v) in node_values[player].iter_mut().enumerate() {
+                *v += strategy[h][a] * child[player][h];
+            }
+            for (v, c) in node_values[1 - player].iter_mut().zip(&child[1 - player]) {
+                *v += c;
+            }
+            action_values.push(child[player].clone());
+        }
+
+        let node = self.nodes.get_mut(&state.history).unwrap();
+        for h in 0..num_hands {
+            for (a, values) in action_values.iter().enumerate() {
+                node.regrets[h][a] = (node.regrets[h][a] + values[h] - node_values[player][h]).max(0.0);
+                node.strategy_sum[h][a] += reach[player][h] * strategy[h][a];
+            }
+        }
+        node_values
+    }
+}
+
+/// The result of searching from one PBS.
+struct Solution {
+    /// Root policy for each hand of the player to move, aligned with the legal actions.
+    policy: Vec<Vec<f64>>,
+    /// Expected value of each hand at the root, averaged over iterations.
+    values: PerHand,
+}
+
+fn solve<V: ValueFunction>(state: &GameState, hands: &Hands, beliefs: &PerHand, value_fn: &V, settings: &PbsSettings) -> Solution {
+    let mut subgame = Subgame {
+        hands,
+        value_fn,
+        nodes: HashMap::new(),
+    };
+    let mut values = [vec![0.0; hands[0].len()], vec![0.0; hands[1].len()]];
+    for _ in 0..settings.iterations {
+        let iteration = subgame.walk(state, beliefs, settings.depth.max(1));
+        for (total, value) in values.iter_mut().zip(iteration) {
+            total.iter_mut().zip(value).for_each(|(t, v)| *t += v / settings.iterations as f64);
+        }
+    }
+    Solution {
+        policy: subgame.nodes[&state.history].average_strategy(),
+        values,
+    }
+}
+
+/// Search and self-play settings.
+pub struct PbsSettings {
+    /// Bids searched ahead of each PBS before the value function takes over.
+    pub depth: usize,
+    /// CFR iterations per search.
+    pub iterations: usize,
+    /// Chance of a uniformly random action during self-play, so play keeps
+    /// visiting states the current policy avoids.
+    pub exploration: f64,
+}
+
+/// Runs `episodes` rounds of self-play search and returns the average root
+/// policy at every info set visited, plus the learned value table.
+pub fn self_play<R: Rng>(config: &GameConfig, episodes: usize, settings: &PbsSettings, rng: &mut R) -> (StrategyTable, LearnedValues) {
+    self_play_from(config, episodes, settings, LearnedValues::default(), rng)
+}
+
+/// `self_play` starting from `value_fn` rather than an empty table.
+pub fn self_play_from<R: Rng>(
+    config: &GameConfig,
+    episodes: usize,
+    settings: &PbsSettings,
+    mut value_fn: LearnedValues,
+    rng: &mut R,
+) -> (StrategyTable, LearnedValues) {
+    let hands: Hands = [hand_distribution(config.dice_p1, config), hand_distribution(config.dice_p2, config)];
+    let priors: PerHand = [
+        hands[0].iter().map(|(_, p)| *p).collect(),
+        hands[1].iter().map(|(_, p)| *p).collect(),
+    ];
+    let mut policy_sums: HashMap<String, Vec<(Action, f64)>> = HashMap::new();
+
+    for _ in 0..episodes {
+        let mut state = GameState::new(config);
+        let mut beliefs = priors.clone();
+
+        loop {
+            let solution = solve(&state, &hands, &beliefs, &value_fn, settings);
+            value_fn.observe(&state, &hands, &solution.values, &beliefs);
+
+            let player = state.current_player as usize;
+            let actions = state.get_valid_actions();
+            for (((hand, _), &belief), probs) in hands[player].iter().zip(&beliefs[player]).zip(&solution.policy) {
+                if belief > 0.0 {
+                    let sums = policy_sums
+                        .entry(state.information_set_for(hand))
+                        .or_insert_with(|| actions.iter().map(|a| (a.clone(), 0.0)).collect());
+                    sums.iter_mut().zip(probs).for_each(|((_, s), p)| *s += belief * p);
+                }
+            }
+
+            let held = hands[player].iter().position(|(h, _)| h.as_slice() == state.current_hand()).expect("dealt hand is enumerated");
+            let choice = if rng.gen::<f64>() < settings.exploration {
+                rng.gen_range(0..actions.len())
+            } else {
+                sample_index(&solution.policy[held], rng)
+            };
+
+            // Bayes' rule on the mover's hand; a purely exploratory action no hand
+            // would take leaves the beliefs where they were.
+            let updated: Vec<f64> = beliefs[player].iter().zip(&solution.policy).map(|(b, p)| b * p[choice]).collect();
+            if updated.iter().sum::<f64>() > 0.0 {
+                beliefs[player] = normalized(updated);
+            }
+
+            if state.apply_action(actions[choice].clone()) {
+                break;
+            }
+        }
+    }
+
+    let mut table = StrategyTable::default();
+    for (info_set, sums) in policy_sums {
+        let total: f64 = sums.iter().map(|(_, s)| s).sum();
+        let probs = sums.into_iter().map(|(a, s)| (a, s / total)).filter(|(_, p)| *p > 0.001).collect();
+        table.entries.insert(info_set, probs);
+    }
+    (table, value_fn)
+}
@@ -0,0 +1,228 @@
+use crate::game::{Action, GameConfig, GameState};
+use crate::strategy::{parse_action, write_atomically};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+
+/// One finished round, stored as a line of a JSON-lines game record file:
+///
+/// `{"players":["A","B"],"hands":["125","34"],"actions":["1-2","2-5","Challenge"],"result":-1}`
+///
+/// Player 0 opened the round, `actions` ends with the final call, and `result`
+/// is player 0's payoff in dice.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameRecord {
+    pub players: [String; 2],
+    pub hands: [Vec<u8>; 2],
+    pub actions: Vec<Action>,
+    pub result: f32,
+}
+
+impl GameRecord {
+    /// Records a finished round; `players` names player 0 and player 1.
+    pub fn from_state(state: &GameState, players: [String; 2]) -> Self {
+        let mut actions = state.history.clone();
+        actions.extend(state.final_call.clone());
+        GameRecord {
+            players,
+            hands: [state.hand_p1.clone(), state.hand_p2.clone()],
+            actions,
+            result: state.payoff_for(0),
+        }
+    }
+
+    /// The rules for this record's dice counts, taken from `rules` otherwise;
+    /// player 0 opened, whichever seat that was.
+    pub fn config(&self, rules: &GameConfig) -> GameConfig {
+        let mut config = rules.opening(false);
+        config.dice_p1 = self.hands[0].len() as u8;
+        config.dice_p2 = self.hands[1].len() as u8;
+        config
+    }
+
+    pub fn to_json(&self) -> String {
+        let strings = |items: Vec<String>| items.iter().map(|s| json_string(s)).collect::<Vec<_>>().join(",");
+        let hand = |h: &[u8]| h.iter().map(|d| d.to_string()).collect::<String>();
+        format!(
+            "{{\"players\":[{}],\"hands\":[{}],\"actions\":[{}],\"result\":{}}}",
+            strings(self.players.to_vec()),
+            strings(self.hands.iter().map(|h| hand(h)).collect()),
+            strings(self.actions.iter().map(|a| a.to_string()).collect()),
+            self.result
+        )
+    }
+
+    pub fn parse(line: &str) -> Option<Self> {
+        let fields = parse_flat_object(line)?;
+        let list = |key: &str| match fields.get(key) {
+            Some(JsonValue::List(items)) => Some(items),
+            _ => None,
+        };
+        let pair = |key: &str| -> Option<[String; 2]> { list(key)?.clone().try_into().ok() };
+        let hand = |s: &str| s.chars().map(|c| c.to_digit(10).map(|d| d as u8)).collect::<Option<Vec<u8>>>();
+
+        let [hand0, hand1] = pair("hands")?;
+        Some(GameRecord {
+            players: pair("players")?,
+            hands: [hand(&hand0)?, hand(&hand1)?],
+            actions: list("actions")?.iter().map(|a| parse_action(a)).collect::<Option<_>>()?,
+            result: match fields.get("result") {
+                Some(JsonValue::Number(n)) => *n as f32,
+                _ => return None,
+            },
+        })
+    }
+}
+
+pub fn write_records(path: &str, records: &[GameRecord]) -> io::Result<()> {
+    write_atomically(path, |file| {
+        for record in records {
+            writeln!(file, "{}", record.to_json())?;
+        }
+        Ok(())
+    })
+}
+
+pub fn read_records(path: &str) -> io::Result<Vec<GameRecord>> {
+    fs::read_to_string(path)?
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            GameRecord::parse(line)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("invalid game record at line {}", i + 1)))
+        })
+        .collect()
+}
+
+pub fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+pub enum JsonValue {
+    Number(f64),
+    String(String),
+    List(Vec<String>),
+}
+
+type Chars<'a> = std::iter::Peekable<std::str::Chars<'a>>;
+
+fn skip_ws(chars: &mut Chars) {
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+}
+
+fn read_string(chars: &mut Chars) -> Option<String> {
+    if chars.next()? != '"' {
+        return None;
+    }
+    let mut out = String::new();
+    loop {
+        match chars.next()? {
+            '"' => return Some(out),
+            '\\' => out.push(chars.next()?),
+            c => out.push(c),
+        }
+    }
+}
+
+/// Parses the subset of JSON game records use: one object whose values are
+/// numbers or arrays of strings.
+pub fn parse_flat_object(line: &str) -> Option<HashMap<String, JsonValue>> {
+    parse_object(&mut line.trim().chars().peekable())
+}
+
+/// Parses a JSON array of flat objects whose values are numbers, strings or
+/// arrays of strings.
+pub fn parse_object_array(text: &str) -> Option<Vec<HashMap<String, JsonValue>>> {
+    let mut chars = text.trim().chars().peekable();
+    if chars.next()? != '[' {
+        return None;
+    }
+    let mut objects = Vec::new();
+    loop {
+        skip_ws(&mut chars);
+        match chars.peek()? {
+            ']' => {
+                chars.next();
+                break;
+            }
+            ',' => {
+                chars.next();
+            }
+            _ => objects.push(parse_object(&mut chars)?),
+        }
+    }
+    skip_ws(&mut chars);
+    chars.next().is_none().then_some(objects)
+}
+
+fn parse_object(chars: &mut Chars) -> Option<HashMap<String, JsonValue>> {
+    let mut fields = HashMap::new();
+    if chars.next()? != '{' {
+        return None;
+    }
+    loop {
+        skip_ws(chars);
+        if chars.peek() == Some(&'}') {
+            chars.next();
+            break;
+        }
+        let key = read_string(chars)?;
+        skip_ws(chars);
+        if chars.next()? != ':' {
+            return None;
+        }
+        skip_ws(chars);
+
+        let value = match chars.peek()? {
+            '[' => {
+                chars.next();
+                let mut items = Vec::new();
+                loop {
+                    skip_ws(chars);
+                    match chars.peek()? {
+                        ']' => {
+                            chars.next();
+                            break;
+                        }
+                        ',' => {
+                            chars.next();
+                        }
+                        _ => items.push(read_string(chars)?),
+                    }
+                }
+                JsonValue::List(items)
+            }
+            '"' => JsonValue::String(read_string(chars)?),
+            _ => {
+                let mut number = String::new();
+                while chars.peek().is_some_and(|c| c.is_ascii_digit() || "+-.eE".contains(*c)) {
+                    number.push(chars.next()?);
+                }
+                JsonValue::Number(number.parse().ok()?)
+            }
+        };
+        fields.insert(key, value);
+
+        skip_ws(chars);
+        match chars.next()? {
+            ',' => continue,
+            '}' => break,
+            _ => return None,
+        }
+    }
+    Some(fields)
+}
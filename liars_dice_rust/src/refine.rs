@@ -0,0 +1,141 @@
+use crate::cfr::CFRNode;
+use crate::cli::Args;
+use crate::exploitability;
+use crate::game::{GameConfig, Refinement, DICE_FACES};
+use crate::strategy::InfoSetKey;
+use crate::symmetry;
+use crate::train;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// `<strategy_file>.refinement`: the split histories, one per line.
+pub fn refinement_filename(strategy_file: &str) -> String {
+    format!("{}.refinement", strategy_file)
+}
+
+/// What an info set remembers of the bidding, as a `Refinement` entry.
+fn public_key(key: &InfoSetKey) -> String {
+    Refinement::public_key(key.remembered_bids().map(|(q, f)| format!("{}-{}", q, f)), key.history_len)
+}
+
+/// Each remembered history's share of the strategy's positive regret: the
+/// largest positive cumulative regret of each info set, summed over the
+/// hands that share the history. Regret that will not go away concentrates
+/// where the abstraction merged histories that call for different play.
+/// Largest first; frozen nodes keep no regret and count for nothing.
+pub fn regret_by_history(nodes: &HashMap<String, CFRNode>) -> Vec<(String, f64)> {
+    let mut by_history: HashMap<String, f64> = HashMap::new();
+    for (key, node) in nodes {
+        let regret = node.regret_sum.iter().fold(0.0f32, |m, &r| m.max(r)) as f64;
+        let key = InfoSetKey::parse(key).expect("trained info set keys always parse");
+        *by_history.entry(public_key(&key)).or_insert(0.0) += regret;
+    }
+    let total: f64 = by_history.values().sum();
+    let mut shares: Vec<(String, f64)> = by_history.into_iter().map(|(history, regret)| (history, regret / total.max(f64::MIN_POSITIVE))).collect();
+    shares.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    shares
+}
+
+/// The fewest histories, taken in order of regret, that hold `share` of
+/// it, leaving out those that already remember every bid.
+pub fn histories_to_split(shares: &[(String, f64)], share: f64) -> Vec<String> {
+    let mut chosen = Vec::new();
+    let mut covered = 0.0;
+    for (history, history_share) in shares {
+        if covered >= share || *history_share <= 0.0 {
+            break;
+        }
+        let (bids, count) = history.split_once('|').expect("public keys have a bid count");
+        let remembered = if bids == "None" { 0 } else { bids.split('/').count() };
+        if remembered < count.parse().expect("public keys have a bid count") {
+            chosen.push(history.clone());
+            covered += history_share;
+        }
+    }
+    chosen
+}
+
+/// `histories` with every face renaming of each, so info sets that face
+/// symmetry trains together are refined together.
+fn with_relabellings(histories: Vec<String>) -> Vec<String> {
+    let relabels = symmetry::all_relabels();
+    let mut seen = HashSet::new();
+    let mut all = Vec::new();
+    for history in histories {
+        let key = InfoSetKey::parse(&format!("|{}", history)).expect("public keys parse without a hand");
+        for relabel in &relabels {
+            let renamed = public_key(&key.relabelled(relabel));
+            if seen.insert(renamed.clone()) {
+                all.push(renamed);
+            }
+        }
+    }
+    all
+}
+
+/// Rewrites `nodes` for the rules after `split` was added to their
+/// refinement: each info set of a split history gives way to one copy per
+/// bid that could have come before its oldest remembered one, so the finer
+/// info sets start where the coarse one left off.
+pub fn split_nodes(nodes: HashMap<String, CFRNode>, config: &GameConfig, split: &HashSet<String>) -> HashMap<String, CFRNode> {
+    let mut refined = HashMap::with_capacity(nodes.len());
+    for (key, node) in nodes {
+        let info_set = InfoSetKey::parse(&key).expect("trained info set keys always parse");
+        if !split.contains(&public_key(&info_set)) {
+            refined.insert(key, node);
+            continue;
+        }
+        let oldest = info_set.remembered_bids().next().expect("only histories with bids are split");
+        for q in (1..=config.max_quantity()).filter(|&q| config.quantity_allowed(q)) {
+            for f in 1..=DICE_FACES {
+                if !config.bid_rules.is_raise((q, f), oldest) {
+                    continue;
+                }
+                let mut child = info_set.clone();
+                child.earlier_bids.insert(0, (q, f));
+                refined.insert(child.to_string(), node.clone());
+            }
+        }
+    }
+    refined
+}
+
+/// Trains on the rules' coarse history abstraction, then `rounds - 1` times
+/// splits the remembered histories holding `share` of the positive regret
+/// and trains on, warm-started from the coarser nodes. Returns the refined
+/// rules and the final nodes.
+pub fn refine(args: &Args, config: &GameConfig, iterations: usize, rounds: usize, share: f64) -> (GameConfig, HashMap<String, CFRNode>) {
+    let mut config = config.clone();
+    let mut refinement = config.refinement.as_deref().cloned().unwrap_or_default();
+    let mut nodes = HashMap::new();
+    let report_exploitability = args.has("exploitability");
+
+    for round in 1..=rounds {
+        println!("Refinement round {} of {} ({} histories split so far).", round, rounds, refinement.len());
+        nodes = train::train_config_from(args, &config, iterations, nodes);
+        let shares = regret_by_history(&nodes);
+        print!("Round {}: {} info sets, {} remembered histories", round, nodes.len(), shares.len());
+        if report_exploitability {
+            print!(", exploitability {:.6}", exploitability::exploitability(&nodes, &config));
+        }
+        println!(".");
+        if round == rounds {
+            break;
+        }
+
+        let mut split = histories_to_split(&shares, share);
+        let covered: f64 = shares.iter().filter(|(h, _)| split.contains(h)).map(|(_, s)| s).sum();
+        if symmetry::applies(&config) {
+            split = with_relabellings(split);
+        }
+        let split: HashSet<String> = split.into_iter().filter(|h| refinement.insert(h.clone())).collect();
+        if split.is_empty() {
+            println!("No history left to split; stopping.");
+            break;
+        }
+        println!("Splitting {} histories holding {:.1}% of the positive regret.", split.len(), covered * 100.0);
+        config.refinement = Some(Arc::new(refinement.clone()));
+        nodes = split_nodes(nodes, &config, &split);
+    }
+    (config, nodes)
+}
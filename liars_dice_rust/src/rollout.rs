@@ -0,0 +1,81 @@
+use crate::game::{Action, GameConfig, GameState};
+use crate::strategy::{sample_index, Policy};
+use rand::Rng;
+
+/// Rollouts per action unless `--rollouts` says otherwise.
+pub const DEFAULT_ROLLOUTS: usize = 200;
+
+/// Stands in for a strategy where it has nothing to say. An info set the
+/// strategy never stored, or stored as near-uniform noise from a few visits,
+/// is scored instead by determinized rollouts: the opponent's hand is
+/// re-rolled, each action is tried, and both players follow the blueprint
+/// from there. The actions' values then set a softmax distribution.
+pub struct RolloutFallback {
+    /// Face weights to re-roll the opponent's hand with.
+    rules: GameConfig,
+    /// Rollouts per legal action.
+    pub rollouts: usize,
+    /// A stored mix within this of uniform on every action counts as unreached.
+    pub uniform_tolerance: f64,
+    /// Softmax temperature, in dice: lower plays the best-scoring action more.
+    pub temperature: f64,
+}
+
+impl RolloutFallback {
+    pub fn new(rules: &GameConfig, rollouts: usize) -> Self {
+        RolloutFallback { rules: rules.clone(), rollouts, uniform_tolerance: 0.01, temperature: 0.05 }
+    }
+
+    /// Whether `probs` carries no more information than uniform play.
+    pub fn uninformed(&self, probs: &[f64]) -> bool {
+        let uniform = 1.0 / probs.len() as f64;
+        probs.len() > 1 && probs.iter().all(|p| (p - uniform).abs() <= self.uniform_tolerance)
+    }
+
+    /// The policy's mix at `state`, or the rollout estimate where the policy is uninformed.
+    pub fn action_probabilities<P: Policy, R: Rng>(&self, policy: &P, state: &GameState, actions: &[Action], rng: &mut R) -> Vec<f64> {
+        let probs = policy.action_probabilities_for(state.current_player, &state.get_information_set(), actions);
+        if !self.uninformed(&probs) {
+            return probs;
+        }
+        let values = self.action_values(policy, state, actions, rng);
+        let best = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let weights: Vec<f64> = values.iter().map(|v| ((v - best) / self.temperature).exp()).collect();
+        let total: f64 = weights.iter().sum();
+        weights.iter().map(|w| w / total).collect()
+    }
+
+    /// The mover's mean payoff, in dice, for each action over `rollouts`
+    /// determinizations. Only the mover's own hand is kept; the opponent's is
+    /// drawn from the prior, not from what their bids suggest.
+    pub fn action_values<P: Policy, R: Rng>(&self, policy: &P, state: &GameState, actions: &[Action], rng: &mut R) -> Vec<f64> {
+        let mover = state.current_player;
+        actions
+            .iter()
+            .map(|action| {
+                let total: f64 = (0..self.rollouts)
+                    .map(|_| {
+                        let mut sample = self.determinize(state, rng);
+                        let mut over = sample.apply_action(action.clone());
+                        while !over {
+                            let legal = sample.get_valid_actions();
+                            let probs = policy.action_probabilities_for(sample.current_player, &sample.get_information_set(), &legal);
+                            over = sample.apply_action(legal[sample_index(&probs, rng)].clone());
+                        }
+                        sample.payoff_for(mover) as f64
+                    })
+                    .sum();
+                total / self.rollouts.max(1) as f64
+            })
+            .collect()
+    }
+
+    /// `state` with the opponent's hand re-rolled.
+    fn determinize<R: Rng>(&self, state: &GameState, rng: &mut R) -> GameState {
+        let mut sample = state.clone();
+        let hand = if state.current_player == 0 { &mut sample.hand_p2 } else { &mut sample.hand_p1 };
+        hand.iter_mut().for_each(|d| *d = self.rules.roll_face(rng));
+        hand.sort();
+        sample
+    }
+}
@@ -0,0 +1,413 @@
+use crate::analyze::Beliefs;
+use crate::blend::{BlendMode, StrategyBlend};
+use crate::game::{Action, GameConfig, GameState};
+use crate::http::{self, Request};
+use crate::probability;
+use crate::query;
+use crate::record::{self, json_string, GameRecord};
+use crate::rollout::RolloutFallback;
+use crate::strategy::{parse_action, Policy};
+use rand::Rng;
+use std::fs;
+use std::io;
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// Options for a two-human table.
+pub struct ServerOptions {
+    pub port: u16,
+    /// Strategy file, or blend of files (see `StrategyBlend::load`), used to
+    /// score each move for spectators.
+    pub strategy: Option<String>,
+    pub blend_mode: BlendMode,
+    /// Where to write a game record of every round played.
+    pub record: Option<String>,
+    /// Token `/reload` requires; without one the endpoint is open.
+    pub admin_token: Option<String>,
+    /// Reload the strategy whenever its file changes.
+    pub watch: bool,
+    /// Rollouts per action where the strategy has no informed mix; 0 answers with its uniform fallback.
+    pub rollouts: usize,
+    /// Temperature for `/query` answers (see `apply_temperature`); spectator
+    /// scoring always uses the strategy as solved.
+    pub temperature: f64,
+}
+
+struct Seat {
+    name: String,
+    token: String,
+}
+
+/// One table: two seats, the round in progress, and the spectators'
+/// event streams. Seat 0 has `rules.dice_p1` dice and seat 1 `rules.dice_p2`
+/// in every round; whoever opens a round is its player 0.
+struct Table {
+    rules: GameConfig,
+    evaluator: Option<StrategyBlend>,
+    fallback: Option<RolloutFallback>,
+    temperature: f64,
+    strategy_path: Option<String>,
+    blend_mode: BlendMode,
+    /// The blend member scoring this round, when blending per game.
+    member: Option<usize>,
+    record: Option<String>,
+    seats: Vec<Seat>,
+    round: usize,
+    opener: usize,
+    state: Option<GameState>,
+    beliefs: Option<Beliefs>,
+    last_result: Option<String>,
+    records: Vec<GameRecord>,
+    subscribers: Vec<TcpStream>,
+}
+
+/// Serves one table over HTTP until the process is stopped. Players `POST
+/// /join?name=...` for a token, then poll `GET /state?token=...` and `POST
+/// /action?token=...&action=...` (`3-5`, `Challenge` or `Calza`). The server
+/// deals, rejects illegal or out-of-turn moves and settles each call;
+/// `GET /events` streams every move and result to spectators, scored against
+/// the solver when a strategy is loaded.
+///
+/// `POST /query` takes a JSON array of situations (see `query::answer_json`)
+/// and answers each with the loaded strategy's action probabilities, rolled
+/// out where the strategy has no informed mix and reshaped by the temperature.
+///
+/// `POST /reload[?path=...]` swaps in a new strategy or blend (by default
+/// re-reading the current files) without interrupting the game; with an admin token set
+/// it needs `token=...` too. Without one, `path=` is refused, so an anonymous
+/// client can only re-read the files the server was started with.
+pub fn serve(rules: GameConfig, options: ServerOptions) -> io::Result<()> {
+    let evaluator = options.strategy.as_deref().map(|spec| StrategyBlend::load(spec, options.blend_mode)).transpose()?;
+    let fallback = (options.rollouts > 0).then(|| RolloutFallback::new(&rules, options.rollouts));
+    let listener = TcpListener::bind(("0.0.0.0", options.port))?;
+    println!("Serving a {}v{} table on port {}", rules.dice_p1, rules.dice_p2, options.port);
+
+    let table = Arc::new(Mutex::new(Table {
+        rules,
+        evaluator,
+        fallback,
+        temperature: options.temperature,
+        strategy_path: options.strategy.clone(),
+        blend_mode: options.blend_mode,
+        member: None,
+        record: options.record,
+        seats: Vec::new(),
+        round: 0,
+        opener: 0,
+        state: None,
+        beliefs: None,
+        last_result: None,
+        records: Vec::new(),
+        subscribers: Vec::new(),
+    }));
+
+    if let (true, Some(spec)) = (options.watch, options.strategy) {
+        let table = Arc::clone(&table);
+        thread::spawn(move || watch_strategy(&spec, &table));
+    }
+
+    let admin_token = Arc::new(options.admin_token);
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let table = Arc::clone(&table);
+        let admin_token = Arc::clone(&admin_token);
+        thread::spawn(move || {
+            if let Err(e) = handle(stream, &table, admin_token.as_deref()) {
+                eprintln!("Connection error: {}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Polls the strategy files and reloads the blend after any of them changes.
+/// Strategy files are replaced atomically, so a changed timestamp means a
+/// complete file.
+fn watch_strategy(spec: &str, table: &Mutex<Table>) {
+    let (mode, paths) = {
+        let table = table.lock().unwrap();
+        let paths: Vec<String> = table.evaluator.iter().flat_map(|blend| blend.paths().map(str::to_string)).collect();
+        (table.blend_mode, paths)
+    };
+    let modified = || -> Vec<Option<SystemTime>> { paths.iter().map(|p| fs::metadata(p).and_then(|m| m.modified()).ok()).collect() };
+    let mut seen = modified();
+    loop {
+        thread::sleep(Duration::from_secs(2));
+        let current = modified();
+        if current.iter().any(Option::is_none) || current == seen {
+            continue;
+        }
+        seen = current;
+        match StrategyBlend::load(spec, mode) {
+            Ok(strategy) => table.lock().unwrap().swap_strategy(spec, strategy),
+            Err(e) => eprintln!("Unable to reload {}: {}", spec, e),
+        }
+    }
+}
+
+fn handle(stream: TcpStream, table: &Mutex<Table>, admin_token: Option<&str>) -> io::Result<()> {
+    let request = http::read_request(&stream)?;
+    if request.path == "/reload" {
+        return reload(&stream, &request, table, admin_token);
+    }
+
+    let mut table = table.lock().unwrap();
+    match (request.method.as_str(), request.path.as_str()) {
+        ("POST", "/join") => table.join(&stream, &request),
+        ("GET", "/state") => match table.seat_for(&request) {
+            Some(seat) => http::respond_json(&stream, 200, &table.view(seat)),
+            None => http::respond_error(&stream, 403, "unknown token"),
+        },
+        ("POST", "/action") => table.act(&stream, &request),
+        ("POST", "/query") => match &table.evaluator {
+            Some(blend) => match query::answer_json(blend, &table.rules, table.fallback.as_ref(), table.temperature, &request.body) {
+                Ok(answers) => http::respond_json(&stream, 200, &answers),
+                Err(e) => http::respond_error(&stream, 400, &e),
+            },
+            None => http::respond_error(&stream, 409, "no strategy loaded"),
+        },
+        ("GET", "/events") => {
+            http::start_event_stream(&stream)?;
+            http::send_event(&stream, "table", &table.summary())?;
+            table.subscribers.push(stream);
+            Ok(())
+        }
+        _ => http::respond_error(&stream, 404, "no such endpoint"),
+    }
+}
+
+/// Loads the requested strategy before taking the table lock, so play
+/// continues while a large file is read, then swaps it in.
+fn reload(stream: &TcpStream, request: &Request, table: &Mutex<Table>, admin_token: Option<&str>) -> io::Result<()> {
+    if request.method != "POST" {
+        return http::respond_error(stream, 404, "no such endpoint");
+    }
+    if admin_token.is_some_and(|token| request.param("token") != Some(token)) {
+        return http::respond_error(stream, 403, "admin token required");
+    }
+    if admin_token.is_none() && request.param("path").is_some() {
+        return http::respond_error(stream, 403, "reloading another path requires --admin-token");
+    }
+    let (current, mode) = {
+        let table = table.lock().unwrap();
+        (table.strategy_path.clone(), table.blend_mode)
+    };
+    let Some(path) = request.param("path").map(str::to_string).or(current) else {
+        return http::respond_error(stream, 400, "no strategy file to reload");
+    };
+
+    match StrategyBlend::load(&path, mode) {
+        Ok(strategy) => {
+            let info_sets = strategy.info_sets();
+            table.lock().unwrap().swap_strategy(&path, strategy);
+            http::respond_json(stream, 200, &format!("{{\"path\":{},\"info_sets\":{}}}", json_string(&path), info_sets))
+        }
+        Err(e) => http::respond_error(stream, 400, &format!("unable to load {}: {}", path, e)),
+    }
+}
+
+impl Table {
+    /// Replaces the evaluating strategy. A round in progress keeps the beliefs
+    /// it formed under the old strategy; if there was none, evaluations start
+    /// with the next deal.
+    fn swap_strategy(&mut self, path: &str, strategy: StrategyBlend) {
+        let info_sets = strategy.info_sets();
+        // A per-game member index may not exist in the new blend; use the mixture until the next deal.
+        self.member = None;
+        self.evaluator = Some(strategy);
+        self.strategy_path = Some(path.to_string());
+        println!("Loaded strategy {} ({} info sets).", path, info_sets);
+        self.broadcast("reload", &format!("{{\"path\":{},\"info_sets\":{}}}", json_string(path), info_sets));
+    }
+
+    fn join(&mut self, stream: &TcpStream, request: &Request) -> io::Result<()> {
+        let name = request.param("name").unwrap_or("").trim();
+        if name.is_empty() {
+            return http::respond_error(stream, 400, "missing name");
+        }
+        if self.seats.len() == 2 {
+            return http::respond_error(stream, 409, "table is full");
+        }
+        if self.seats.iter().any(|s| s.name == name) {
+            return http::respond_error(stream, 409, "name already taken");
+        }
+
+        let token = format!("{:016x}", rand::thread_rng().gen::<u64>());
+        let seat = self.seats.len();
+        self.seats.push(Seat { name: name.to_string(), token: token.clone() });
+        self.broadcast("join", &format!("{{\"seat\":{},\"name\":{}}}", seat, json_string(name)));
+        if self.seats.len() == 2 {
+            self.deal();
+        }
+        http::respond_json(stream, 200, &format!("{{\"token\":{},\"seat\":{}}}", json_string(&token), seat))
+    }
+
+    fn act(&mut self, stream: &TcpStream, request: &Request) -> io::Result<()> {
+        let Some(seat) = self.seat_for(request) else {
+            return http::respond_error(stream, 403, "unknown token");
+        };
+        let Some(state) = &self.state else {
+            return http::respond_error(stream, 409, "waiting for an opponent");
+        };
+        if self.seat_of(state.current_player) != seat {
+            return http::respond_error(stream, 409, "not your turn");
+        }
+        let Some(action) = request.param("action").and_then(parse_action) else {
+            return http::respond_error(stream, 400, "missing or unreadable action");
+        };
+        let Some(chosen) = state.action_index(action.clone()) else {
+            return http::respond_error(stream, 400, &format!("{} is not a legal action here", action));
+        };
+        let actions = state.get_valid_actions();
+
+        let evaluation = self.evaluate(&actions, chosen);
+        let mut event = format!(
+            "{{\"round\":{},\"player\":{},\"action\":{}",
+            self.round,
+            json_string(&self.seats[seat].name),
+            json_string(&action.to_string())
+        );
+        if let Some(evaluation) = evaluation {
+            event.push_str(&format!(",\"evaluation\":{}", evaluation));
+        }
+        event.push('}');
+        self.broadcast("action", &event);
+
+        let state = self.state.as_mut().unwrap();
+        if state.apply_action(action) {
+            self.settle()?;
+        }
+        http::respond_json(stream, 200, "{\"ok\":true}")
+    }
+
+    /// Scores `actions[chosen]` against the solver for spectators, then folds
+    /// the move into the running beliefs about the mover's hand.
+    fn evaluate(&mut self, actions: &[Action], chosen: usize) -> Option<String> {
+        let (Some(blend), Some(state), Some(beliefs)) = (&self.evaluator, &self.state, &mut self.beliefs) else {
+            return None;
+        };
+        let policy = &blend.for_game(self.member);
+        let probs = match &self.fallback {
+            Some(fallback) => fallback.action_probabilities(policy, state, actions, &mut rand::thread_rng()),
+            None => policy.action_probabilities(&state.get_information_set(), actions),
+        };
+        let values = beliefs.action_values(policy, state, actions);
+        let solver_ev: f64 = probs.iter().zip(&values).map(|(p, v)| p * v).sum();
+        let mix: Vec<String> = actions
+            .iter()
+            .zip(&probs)
+            .filter(|(_, p)| **p >= 0.01)
+            .map(|(a, p)| format!("{}:{:.4}", json_string(&a.to_string()), p))
+            .collect();
+        beliefs.observe(policy, state, actions, chosen);
+        Some(format!(
+            "{{\"ev\":{:.4},\"solver_ev\":{:.4},\"loss\":{:.4},\"solver\":{{{}}}}}",
+            values[chosen],
+            solver_ev,
+            solver_ev - values[chosen],
+            mix.join(",")
+        ))
+    }
+
+    /// Announces the finished round and deals the next, which the loser opens.
+    fn settle(&mut self) -> io::Result<()> {
+        let state = self.state.take().unwrap();
+        let names = [self.seats[self.seat_of(0)].name.clone(), self.seats[self.seat_of(1)].name.clone()];
+        let caller = self.seat_of(state.current_player);
+        let payoff = state.challenger_payoff();
+        let winner = if payoff > 0.0 { caller } else { 1 - caller };
+        let hand = |h: &[u8]| json_string(&h.iter().map(|d| d.to_string()).collect::<String>());
+
+        let result = format!(
+            "{{\"round\":{},\"hands\":{{{}:{},{}:{}}},\"caller\":{},\"call\":{},\"winner\":{},\"dice\":{}}}",
+            self.round,
+            json_string(&names[0]),
+            hand(&state.hand_p1),
+            json_string(&names[1]),
+            hand(&state.hand_p2),
+            json_string(&self.seats[caller].name),
+            json_string(&state.final_call.as_ref().map_or(String::new(), |a| a.to_string())),
+            json_string(&self.seats[winner].name),
+            payoff.abs()
+        );
+        self.broadcast("result", &result);
+        self.last_result = Some(result);
+
+        self.records.push(GameRecord::from_state(&state, names));
+        if let Some(path) = &self.record {
+            record::write_records(path, &self.records)?;
+        }
+        self.opener = 1 - winner;
+        self.deal();
+        Ok(())
+    }
+
+    fn deal(&mut self) {
+        let config = self.rules.opening(self.opener == 1);
+        self.round += 1;
+        self.state = Some(GameState::new(&config));
+        self.beliefs = self.evaluator.as_ref().map(|_| Beliefs::new(&config));
+        self.member = self.evaluator.as_ref().and_then(|blend| blend.pick_member(&mut rand::thread_rng()));
+        let opener = self.seats[self.opener].name.clone();
+        self.broadcast("round", &format!("{{\"round\":{},\"opener\":{}}}", self.round, json_string(&opener)));
+    }
+
+    fn seat_for(&self, request: &Request) -> Option<usize> {
+        let token = request.param("token")?;
+        self.seats.iter().position(|s| s.token == token)
+    }
+
+    /// The seat playing as `player` in the current round.
+    fn seat_of(&self, player: u8) -> usize {
+        (self.opener + player as usize) % 2
+    }
+
+    /// The table as `seat` sees it: their own hand, the public bids, and whose turn it is.
+    fn view(&self, seat: usize) -> String {
+        let last_result = self.last_result.as_deref().unwrap_or("null");
+        let Some(state) = &self.state else {
+            return format!("{{\"waiting\":true,\"last_result\":{}}}", last_result);
+        };
+        let player = if self.seat_of(0) == seat { 0 } else { 1 };
+        let (hand, opponent_dice) = if player == 0 { (&state.hand_p1, state.dice_p2) } else { (&state.hand_p2, state.dice_p1) };
+        let your_turn = state.current_player == player;
+        let actions = |list: &[Action]| list.iter().map(|a| json_string(&a.to_string())).collect::<Vec<_>>().join(",");
+        let legal = if your_turn { state.get_valid_actions() } else { Vec::new() };
+        // How likely the standing bid is to be true, given only this seat's hand.
+        let bid_truth = state.current_bid.map_or("null".to_string(), |(q, f)| {
+            format!("{:.4}", probability::bid_odds(&self.rules, hand, q, f).truth)
+        });
+
+        format!(
+            "{{\"waiting\":false,\"round\":{},\"hand\":{},\"opponent\":{},\"opponent_dice\":{},\"bids\":[{}],\"your_turn\":{},\"legal\":[{}],\"bid_truth\":{},\"last_result\":{}}}",
+            self.round,
+            json_string(&hand.iter().map(|d| d.to_string()).collect::<String>()),
+            json_string(&self.seats[1 - seat].name),
+            opponent_dice,
+            actions(&state.history),
+            your_turn,
+            actions(&legal),
+            bid_truth,
+            last_result
+        )
+    }
+
+    fn summary(&self) -> String {
+        let names: Vec<String> = self.seats.iter().map(|s| json_string(&s.name)).collect();
+        format!(
+            "{{\"dice\":[{},{}],\"seats\":[{}],\"round\":{},\"evaluations\":{}}}",
+            self.rules.dice_p1,
+            self.rules.dice_p2,
+            names.join(","),
+            self.round,
+            self.evaluator.is_some()
+        )
+    }
+
+    /// Sends an event to every spectator, dropping any that have disconnected.
+    fn broadcast(&mut self, event: &str, data: &str) {
+        self.subscribers.retain(|s| http::send_event(s, event, data).is_ok());
+    }
+}
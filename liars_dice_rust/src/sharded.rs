@@ -0,0 +1,431 @@
+use crate::cfr::{CFRNode, NodeStore};
+use crate::game::{Action, GameConfig};
+use crate::strategy::{format_metadata, parse_metadata, write_atomically, write_metadata, write_strategy_rows, SavePrecision};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Shards unless `--shards` says otherwise.
+pub const DEFAULT_SHARDS: usize = 4096;
+/// Shards held in memory unless `--cache-shards` says otherwise.
+pub const DEFAULT_CACHE_SHARDS: usize = 256;
+
+/// One shard in memory.
+struct CachedShard {
+    nodes: HashMap<String, CFRNode>,
+    last_used: u64,
+    dirty: bool,
+}
+
+/// A node map too big for memory, split into shard files under a directory
+/// with only the most recently used shards held in memory. Info sets are
+/// sharded by their hand and first remembered bid (see `shard_prefix`), so a
+/// hand's info sets fill a shard per opening bid rather than one, while a
+/// deal's traversal still stays within the shards of the hands dealt.
+///
+/// Shards are read and written whole rather than memory-mapped. Nodes vary
+/// in size (keys, action lists) and new ones appear mid-traversal, so a
+/// mapped file would still need an index and a growth scheme; a loaded
+/// shard is a plain `HashMap` the trainer updates in place, and the store
+/// needs no `unsafe` or mmap dependency. The cache capacity bounds memory
+/// the way a mapping's resident set would.
+pub struct ShardedNodes {
+    dir: PathBuf,
+    shards: usize,
+    capacity: usize,
+    cache: HashMap<usize, CachedShard>,
+    clock: u64,
+    /// Shards read from disk and written back, for reporting.
+    pub loads: u64,
+    pub writes: u64,
+}
+
+impl ShardedNodes {
+    /// Opens (or starts) a store of `shards` shard files in `dir` for nodes
+    /// trained under `config`, holding at most `capacity` in memory. Shards
+    /// already in `dir` are picked up, so a run can train further on them.
+    /// The shard count and rules are kept in a manifest; a store written with
+    /// others, or with no manifest, is refused, since its keys would land in
+    /// the wrong shards or mean other info sets.
+    pub fn open<P: AsRef<Path>>(dir: P, shards: usize, capacity: usize, config: &GameConfig) -> io::Result<Self> {
+        let shards = shards.max(1);
+        fs::create_dir_all(&dir)?;
+        check_manifest(dir.as_ref(), shards, config)?;
+        Ok(ShardedNodes {
+            dir: dir.as_ref().to_path_buf(),
+            shards,
+            capacity: capacity.max(1),
+            cache: HashMap::new(),
+            clock: 0,
+            loads: 0,
+            writes: 0,
+        })
+    }
+
+    pub fn shards(&self) -> usize {
+        self.shards
+    }
+
+    fn shard_of(&self, key: &str) -> usize {
+        let prefix = shard_prefix(key);
+        // FNV-1a, so a key's shard is the same in every run.
+        let hash = prefix.bytes().fold(0xcbf2_9ce4_8422_2325u64, |h, b| (h ^ b as u64).wrapping_mul(0x100_0000_01b3));
+        (hash % self.shards as u64) as usize
+    }
+
+    fn path(&self, shard: usize) -> PathBuf {
+        self.dir.join(format!("shard_{:05}.bin", shard))
+    }
+
+    /// The shard's nodes, loading it (and evicting the least recently used
+    /// shard if the cache is full) when it is not in memory.
+    fn shard(&mut self, shard: usize) -> &mut CachedShard {
+        self.clock += 1;
+        if !self.cache.contains_key(&shard) {
+            if self.cache.len() >= self.capacity {
+                let oldest = self.cache.iter().min_by_key(|(_, s)| s.last_used).map(|(&i, _)| i).expect("a full cache has a shard");
+                self.evict(oldest).expect("Unable to write node shard");
+            }
+            let nodes = read_shard(&self.path(shard)).expect("Unable to read node shard");
+            self.loads += 1;
+            self.cache.insert(shard, CachedShard { nodes, last_used: 0, dirty: false });
+        }
+        let cached = self.cache.get_mut(&shard).expect("shard was just loaded");
+        cached.last_used = self.clock;
+        cached
+    }
+
+    fn evict(&mut self, shard: usize) -> io::Result<()> {
+        if let Some(cached) = self.cache.remove(&shard) {
+            if cached.dirty {
+                write_shard(&self.path(shard), &cached.nodes, false)?;
+                self.writes += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds `nodes` to the store, replacing any already there under the same keys.
+    pub fn insert_all(&mut self, nodes: HashMap<String, CFRNode>) {
+        for (key, node) in nodes {
+            let shard = self.shard(self.shard_of(&key));
+            shard.dirty = true;
+            shard.nodes.insert(key, node);
+        }
+    }
+
+    /// Writes every changed shard in memory back to disk and syncs it. Each
+    /// file is replaced whole, but not all at once, and evictions since the
+    /// last flush are not synced: a run killed mid-way can leave shards from
+    /// different points of training.
+    pub fn flush(&mut self) -> io::Result<()> {
+        let dir = self.dir.clone();
+        for (&shard, cached) in self.cache.iter_mut().filter(|(_, c)| c.dirty) {
+            write_shard(&dir.join(format!("shard_{:05}.bin", shard)), &cached.nodes, true)?;
+            cached.dirty = false;
+            self.writes += 1;
+        }
+        Ok(())
+    }
+
+    /// Calls `f` with each shard's nodes in turn, from disk or the cache, so
+    /// the whole map is never in memory at once. Flush first to see every change.
+    pub fn for_each_shard<F: FnMut(HashMap<String, CFRNode>) -> io::Result<()>>(&self, mut f: F) -> io::Result<()> {
+        for shard in 0..self.shards {
+            let nodes = match self.cache.get(&shard) {
+                Some(cached) => cached.nodes.clone(),
+                None => read_shard(&self.path(shard))?,
+            };
+            if !nodes.is_empty() {
+                f(nodes)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The manifest's file name within a store's directory.
+const MANIFEST: &str = "manifest.txt";
+
+/// Checks the manifest in `dir` against `shards` and `config`, writing one
+/// if the store is new.
+fn check_manifest(dir: &Path, shards: usize, config: &GameConfig) -> io::Result<()> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let path = dir.join(MANIFEST);
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            if fs::read_dir(dir)?.any(|entry| entry.is_ok_and(|e| e.path().extension().is_some_and(|ext| ext == "bin"))) {
+                return Err(invalid(format!("shard files without a {}; they may use another shard layout", MANIFEST)));
+            }
+            let path = path.to_string_lossy();
+            return write_atomically(&path, |file| write!(file, "shards={}\n{}", shards, format_metadata(config)));
+        }
+        Err(e) => return Err(e),
+    };
+    let written: Option<usize> = contents.lines().find_map(|line| line.strip_prefix("shards=")).and_then(|n| n.parse().ok());
+    if written != Some(shards) {
+        return Err(invalid(format!("the store has {} shards, not {}", written.map_or("an unknown number of".to_string(), |n| n.to_string()), shards)));
+    }
+    if parse_metadata(&contents)? != *config {
+        return Err(invalid("the store was trained under other rules".to_string()));
+    }
+    Ok(())
+}
+
+/// The part of an info set key its shard is chosen by: the hand and the
+/// first remembered bid, e.g. `12|2-3` for `12|2-3/3-3|2`, or `12|None` at
+/// the opening.
+fn shard_prefix(key: &str) -> &str {
+    let Some(hand) = key.find('|') else {
+        return key;
+    };
+    let end = key[hand + 1..].find(['/', '|']).map_or(key.len(), |i| hand + 1 + i);
+    &key[..end]
+}
+
+/// Saves the store's average strategy to `filename`, one shard at a time.
+/// `export` rewrites each shard before it is written (e.g.
+/// `symmetry::expand`); rows are sorted within each shard, not across the file.
+pub fn save_sharded_strategy<F>(store: &mut ShardedNodes, config: &GameConfig, filename: &str, precision: SavePrecision, export: F)
+where
+    F: Fn(HashMap<String, CFRNode>) -> HashMap<String, CFRNode>,
+{
+    println!("Saving strategy to {}...", filename);
+    store.flush().expect("Unable to write node shard");
+    write_metadata(config, filename).expect("Unable to write strategy metadata");
+    write_atomically(filename, |file| {
+        writeln!(file, "InfoSet,Action,Probability")?;
+        store.for_each_shard(|nodes| write_strategy_rows(file, &export(nodes), precision))
+    })
+    .expect("Unable to write strategy file");
+    println!("Save complete.");
+}
+
+impl NodeStore for ShardedNodes {
+    fn node(&mut self, key: &str, actions: &[Action]) -> &mut CFRNode {
+        let shard = self.shard(self.shard_of(key));
+        shard.dirty = true;
+        shard.nodes.entry(key.to_string()).or_insert_with(|| CFRNode::new(actions.to_vec()))
+    }
+
+    fn existing(&mut self, key: &str) -> &mut CFRNode {
+        let shard = self.shard(self.shard_of(key));
+        shard.dirty = true;
+        shard.nodes.get_mut(key).expect("node was created earlier in the traversal")
+    }
+
+    fn in_memory(&mut self) -> Option<&mut HashMap<String, CFRNode>> {
+        None
+    }
+}
+
+// Shard files are a sequence of nodes, little-endian: key length (u16) and
+// bytes, action count (u16) and two bytes per action, visits (u64), then a
+// frozen flag (u8) followed by the frozen strategy (u16 each) or the regret
+// and strategy sums (f32 each).
+
+fn encode_action(action: &Action) -> [u8; 2] {
+    match action {
+        Action::Bid(q, f) => [*q, *f],
+        Action::Challenge => [0, 0],
+        Action::Calza => [0, 1],
+    }
+}
+
+fn decode_action(bytes: [u8; 2]) -> Action {
+    match bytes {
+        [0, 0] => Action::Challenge,
+        [0, _] => Action::Calza,
+        [q, f] => Action::Bid(q, f),
+    }
+}
+
+/// A key or action count as written, or an error rather than a silently
+/// truncated count when it does not fit.
+fn length(n: usize, key: &str) -> io::Result<[u8; 2]> {
+    u16::try_from(n)
+        .map(u16::to_le_bytes)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, format!("{}: {} does not fit a shard file's u16 count", key, n)))
+}
+
+/// Writes a shard via a temp file and a rename; `sync` also waits for the
+/// data to reach the disk, which evictions skip since they happen constantly.
+fn write_shard(path: &Path, nodes: &HashMap<String, CFRNode>, sync: bool) -> io::Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    let mut out = BufWriter::new(fs::File::create(&tmp_path)?);
+    for (key, node) in nodes {
+        out.write_all(&length(key.len(), key)?)?;
+        out.write_all(key.as_bytes())?;
+        out.write_all(&length(node.actions.len(), key)?)?;
+        for action in &node.actions {
+            out.write_all(&encode_action(action))?;
+        }
+        out.write_all(&node.visits.to_le_bytes())?;
+        match &node.frozen {
+            Some(frozen) => {
+                out.write_all(&[1])?;
+                for p in frozen.iter() {
+                    out.write_all(&p.to_le_bytes())?;
+                }
+            }
+            None => {
+                out.write_all(&[0])?;
+                for x in node.regret_sum.iter().chain(&node.strategy_sum) {
+                    out.write_all(&x.to_le_bytes())?;
+                }
+            }
+        }
+    }
+    let file = out.into_inner().map_err(|e| e.into_error())?;
+    if sync {
+        file.sync_all()?;
+    }
+    fs::rename(tmp_path, path)
+}
+
+/// Reads a shard file; a missing one is an empty shard.
+pub fn read_shard(path: &Path) -> io::Result<HashMap<String, CFRNode>> {
+    let file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(e) => return Err(e),
+    };
+    let mut input = BufReader::new(file);
+    let mut nodes = HashMap::new();
+    let mut len = [0u8; 2];
+    loop {
+        match input.read_exact(&mut len) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(nodes),
+            Err(e) => return Err(e),
+        }
+        let mut key = vec![0u8; u16::from_le_bytes(len) as usize];
+        input.read_exact(&mut key)?;
+        let key = String::from_utf8(key).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("{}: invalid key", path.display())))?;
+
+        input.read_exact(&mut len)?;
+        let count = u16::from_le_bytes(len) as usize;
+        let mut actions = Vec::with_capacity(count);
+        for _ in 0..count {
+            let mut action = [0u8; 2];
+            input.read_exact(&mut action)?;
+            actions.push(decode_action(action));
+        }
+        let mut node = CFRNode::new(actions);
+        let mut visits = [0u8; 8];
+        input.read_exact(&mut visits)?;
+        node.visits = u64::from_le_bytes(visits);
+
+        let mut byte = [0u8; 1];
+        input.read_exact(&mut byte)?;
+        if byte[0] == 1 {
+            let mut frozen = Vec::with_capacity(node.num_actions);
+            for _ in 0..node.num_actions {
+                let mut p = [0u8; 2];
+                input.read_exact(&mut p)?;
+                frozen.push(u16::from_le_bytes(p));
+            }
+            node.regret_sum = Vec::new();
+            node.strategy_sum = Vec::new();
+            node.frozen = Some(frozen.into_boxed_slice());
+        } else {
+            let mut x = [0u8; 4];
+            for i in 0..2 * node.num_actions {
+                input.read_exact(&mut x)?;
+                let sums = if i < node.num_actions { &mut node.regret_sum } else { &mut node.strategy_sum };
+                sums[i % node.num_actions] = f32::from_le_bytes(x);
+            }
+        }
+        nodes.insert(key, node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn scratch(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("liars_dice_{}_{}.bin", name, std::process::id()))
+    }
+
+    #[test]
+    fn shards_round_trip_every_field() {
+        let mut trained = CFRNode::new(vec![Action::Bid(1, 2), Action::Challenge, Action::Calza]);
+        trained.regret_sum = vec![0.5, -1.25, 3.0];
+        trained.strategy_sum = vec![1.0, 2.0, 0.0];
+        trained.visits = 7;
+        let mut frozen = CFRNode::new(vec![Action::Bid(2, 6), Action::Challenge]);
+        frozen.visits = 3;
+        frozen.freeze();
+        // Past 42 dice a node has more actions than a byte can count.
+        let wide = CFRNode::new((1..=50).flat_map(|q| (1..=6).map(move |f| Action::Bid(q, f))).collect());
+        let nodes = HashMap::from([("12|None|0".to_string(), trained), ("3|1-2|1".to_string(), frozen), ("1|None|0".to_string(), wide)]);
+
+        let path = scratch("round_trip");
+        write_shard(&path, &nodes, false).unwrap();
+        let read = read_shard(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(read.len(), nodes.len());
+        for (key, node) in &nodes {
+            let back = &read[key];
+            assert_eq!(back.actions, node.actions);
+            assert_eq!(back.visits, node.visits);
+            assert_eq!(back.frozen, node.frozen);
+            assert_eq!(back.regret_sum, node.regret_sum);
+            assert_eq!(back.strategy_sum, node.strategy_sum);
+        }
+    }
+
+    #[test]
+    fn corrupted_shards_fail_without_panicking() {
+        let mut node = CFRNode::new(vec![Action::Bid(1, 3), Action::Bid(2, 3), Action::Challenge]);
+        node.regret_sum = vec![1.0, 0.0, 2.5];
+        node.visits = 4;
+        let path = scratch("fuzz_source");
+        write_shard(&path, &HashMap::from([("33|1-3|1".to_string(), node)]), false).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let target = scratch("fuzz");
+        let mut rng = StdRng::seed_from_u64(0);
+        for case in 0..200 {
+            fs::write(&target, crate::checkpoint::corrupt(bytes.clone(), &mut rng)).unwrap();
+            let read = std::panic::catch_unwind(|| read_shard(&target));
+            assert!(read.is_ok(), "case {} made the reader panic", case);
+        }
+        fs::remove_file(&target).unwrap();
+    }
+
+    #[test]
+    fn shards_split_a_hand_by_its_first_bid() {
+        assert_eq!(shard_prefix("12|2-3/3-3|2"), "12|2-3");
+        assert_eq!(shard_prefix("12|2-3|1|r0j0-"), "12|2-3");
+        assert_eq!(shard_prefix("12|None|0"), "12|None");
+        assert_eq!(shard_prefix("12"), "12");
+    }
+
+    #[test]
+    fn stores_with_other_shards_or_rules_are_refused() {
+        let dir = scratch("manifest");
+        let config = GameConfig::new(1, 1);
+        drop(ShardedNodes::open(&dir, 8, 1, &config).unwrap());
+        assert!(ShardedNodes::open(&dir, 8, 1, &config).is_ok());
+        assert!(ShardedNodes::open(&dir, 16, 1, &config).is_err());
+        assert!(ShardedNodes::open(&dir, 8, 1, &GameConfig::new(1, 2)).is_err());
+
+        fs::remove_file(dir.join(MANIFEST)).unwrap();
+        fs::write(dir.join("shard_00000.bin"), []).unwrap();
+        assert!(ShardedNodes::open(&dir, 8, 1, &config).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn missing_shards_are_empty() {
+        assert!(read_shard(&scratch("missing")).unwrap().is_empty());
+    }
+}
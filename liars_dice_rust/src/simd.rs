@@ -0,0 +1,73 @@
+// The inner loops of regret matching: positive parts, sums and normalization
+// over a node's actions. Built with `--features simd`, they run over packed
+// chunks of `LANES` f32s with one accumulator per lane, which the compiler
+// turns into vector instructions; that pays off on nodes with dozens of
+// actions, such as opening bids with many dice. Packed sums add in a
+// different order, so results can differ from the scalar build in the last
+// bits. `cargo run --release [--features simd] bench-kernels` times both.
+
+#[cfg(feature = "simd")]
+const LANES: usize = 8;
+
+/// Sum of `values`.
+#[cfg(not(feature = "simd"))]
+pub fn sum(values: &[f32]) -> f32 {
+    values.iter().sum()
+}
+
+/// Sum of `values`.
+#[cfg(feature = "simd")]
+pub fn sum(values: &[f32]) -> f32 {
+    let mut lanes = [0.0f32; LANES];
+    let chunks = values.chunks_exact(LANES);
+    let tail: f32 = chunks.remainder().iter().sum();
+    for chunk in chunks {
+        for (lane, &v) in lanes.iter_mut().zip(chunk) {
+            *lane += v;
+        }
+    }
+    lanes.iter().sum::<f32>() + tail
+}
+
+/// Each value floored at zero. A plain map already compiles to vector
+/// max instructions, so both builds share it.
+pub fn positive_parts(values: &[f32]) -> Vec<f32> {
+    values.iter().map(|&v| v.max(0.0)).collect()
+}
+
+/// Scales non-negative `values` to sum to one in place, or makes them
+/// uniform when they sum to zero.
+pub fn normalize(values: &mut [f32]) {
+    let total = sum(values);
+    if total > 0.0 {
+        divide(values, total);
+    } else {
+        let uniform = 1.0 / values.len() as f32;
+        values.iter_mut().for_each(|v| *v = uniform);
+    }
+}
+
+#[cfg(not(feature = "simd"))]
+fn divide(values: &mut [f32], total: f32) {
+    values.iter_mut().for_each(|v| *v /= total);
+}
+
+/// Multiplies by the inverse, which vectorizes where division is slow.
+#[cfg(feature = "simd")]
+fn divide(values: &mut [f32], total: f32) {
+    let inverse = 1.0 / total;
+    let mut chunks = values.chunks_exact_mut(LANES);
+    for chunk in &mut chunks {
+        for v in chunk.iter_mut() {
+            *v *= inverse;
+        }
+    }
+    chunks.into_remainder().iter_mut().for_each(|v| *v *= inverse);
+}
+
+/// `sums[i] += weight * values[i]`.
+pub fn add_scaled(sums: &mut [f32], values: &[f32], weight: f32) {
+    for (s, &v) in sums.iter_mut().zip(values) {
+        *s += weight * v;
+    }
+}
@@ -0,0 +1,127 @@
+use crate::bundle::StrategyBundle;
+use crate::deals::DealScript;
+use crate::exact;
+use crate::game::{GameConfig, GameState};
+use crate::match_game::MatchState;
+use crate::record::GameRecord;
+use crate::strategy::{sample_index, Policy};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Where a match's deals come from.
+#[derive(Clone, Copy)]
+pub enum MatchDeals<'a> {
+    /// Fresh random hands every round.
+    Random,
+    /// The next scripted deal for the round's dice counts, where there is one.
+    Script(&'a DealScript),
+    /// Hands drawn from a stream fixed by the seed and the round number, so
+    /// matches with the same seed deal the same hands for as long as their
+    /// dice counts agree. Duplicate evaluation plays each such pair of
+    /// matches with the strategies' seats swapped.
+    Seeded(u64),
+}
+
+impl MatchDeals<'_> {
+    fn deal(&self, config: &GameConfig, round: usize) -> GameState {
+        match self {
+            MatchDeals::Random => GameState::new(config),
+            MatchDeals::Script(script) => script.next_state(config).unwrap_or_else(|| GameState::new(config)),
+            MatchDeals::Seeded(seed) => {
+                let mut rng = StdRng::seed_from_u64(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ round as u64);
+                GameState::deal(config, &mut rng)
+            }
+        }
+    }
+}
+
+/// Outcome of one full match between two seats.
+pub struct MatchResult {
+    pub winner: usize,
+    pub rounds: usize,
+    /// Every round played, with the seats named `A` and `B`.
+    pub records: Vec<GameRecord>,
+}
+
+/// Plays rounds until one seat has no dice left. Each round uses the strategy
+/// solved for the current dice counts, with the round's opener as player 0;
+/// the loser of a round opens the next one, as in Perudo. `deals` says how
+/// each round is dealt. With `endgame`, rounds it has a table for are played by both seats from
+/// that table instead, under perfect recall (see `exact::solve`).
+pub fn play_match<R: Rng>(
+    bundles: [&StrategyBundle; 2],
+    rules: &GameConfig,
+    start_dice: u8,
+    first_opener: usize,
+    deals: MatchDeals,
+    endgame: Option<&StrategyBundle>,
+    rng: &mut R,
+) -> MatchResult {
+    let mut state = MatchState::new(start_dice, first_opener);
+    let mut rounds = 0;
+    let mut records = Vec::new();
+
+    while state.winner().is_none() {
+        rounds += 1;
+        let (opener, other) = (state.opener, 1 - state.opener);
+        let config = state.round_config(rules);
+
+        let round = play_round(&config, bundles, opener, deals.deal(&config, rounds), endgame, rng);
+        let names = ["A".to_string(), "B".to_string()];
+        records.push(GameRecord::from_state(&round, [names[opener].clone(), names[other].clone()]));
+        state = state.after(&round);
+    }
+
+    MatchResult {
+        winner: state.winner().expect("the match is over"),
+        rounds,
+        records,
+    }
+}
+
+/// Plays a dealt round out between two policies, `players[0]` opening, and
+/// returns the opener's payoff in dice.
+pub fn play_out<R: Rng>(mut state: GameState, players: [&dyn Policy; 2], rng: &mut R) -> f64 {
+    loop {
+        let actions = state.get_valid_actions();
+        let probs = players[state.current_player as usize].action_probabilities(&state.get_information_set(), &actions);
+        let action = actions[sample_index(&probs, rng)].clone();
+        if state.apply_action(action) {
+            return state.payoff_for(0) as f64;
+        }
+    }
+}
+
+fn seat_of(player: u8, opener: usize) -> usize {
+    (opener + player as usize) % 2
+}
+
+fn play_round<R: Rng>(
+    config: &GameConfig,
+    bundles: [&StrategyBundle; 2],
+    opener: usize,
+    dealt: GameState,
+    endgame: Option<&StrategyBundle>,
+    rng: &mut R,
+) -> GameState {
+    let exact = endgame.and_then(|e| e.get(config.dice_p1, config.dice_p2));
+    let config = &match exact {
+        Some(_) => exact::exact_config(config),
+        None => config.clone(),
+    };
+    let mut state = GameState::from_hands(config, dealt.hand_p1, dealt.hand_p2);
+    state.revealed = dealt.revealed;
+    loop {
+        let seat = seat_of(state.current_player, opener);
+        let table = exact.or_else(|| bundles[seat].get(config.dice_p1, config.dice_p2)).unwrap_or_else(|| {
+            panic!("strategy bundle has no {}v{} strategy", config.dice_p1, config.dice_p2)
+        });
+
+        let actions = state.get_valid_actions();
+        let probs = table.action_probabilities(&state.get_information_set(), &actions);
+        let action = actions[sample_index(&probs, rng)].clone();
+        if state.apply_action(action) {
+            return state;
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use crate::cfr::CFRNode;
+use crate::game::Action;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::mem::size_of;
+
+/// Estimated heap and table footprint of a node map.
+pub fn memory_bytes(nodes: &HashMap<String, CFRNode>) -> usize {
+    let entries: usize = nodes
+        .iter()
+        .map(|(key, node)| {
+            size_of::<(String, CFRNode)>()
+                + key.capacity()
+                + (node.regret_sum.capacity() + node.strategy_sum.capacity()) * size_of::<f32>()
+                + node.actions.capacity() * size_of::<Action>()
+                + node.frozen.as_ref().map_or(0, |f| f.len() * size_of::<u16>())
+        })
+        .sum();
+    // Hash table slots beyond the stored entries, plus one control byte each.
+    entries + (nodes.capacity() - nodes.len()) * size_of::<(String, CFRNode)>() + nodes.capacity()
+}
+
+/// Largest action probability at which a node counts as near-deterministic.
+const NEAR_DETERMINISTIC: f32 = 0.99;
+
+/// Summary of a trained node map, for spotting under-trained regions.
+pub struct TrainingStats {
+    pub info_sets: usize,
+    /// Estimated heap and table footprint of the node map.
+    pub memory_bytes: usize,
+    /// Info sets per visit-count bucket: 0, 1-9, 10-99, ... keyed by the bucket's lower bound.
+    pub visit_buckets: BTreeMap<u64, usize>,
+    pub near_deterministic: f64,
+    pub depths: Vec<DepthStats>,
+}
+
+/// Average-strategy entropy over the info sets `depth` bids into the round.
+pub struct DepthStats {
+    pub depth: usize,
+    pub info_sets: usize,
+    /// Mean entropy in bits, each info set weighted equally.
+    pub mean_entropy: f64,
+}
+
+impl TrainingStats {
+    pub fn collect(nodes: &HashMap<String, CFRNode>) -> Self {
+        let mut visit_buckets = BTreeMap::new();
+        let mut near_deterministic = 0;
+        let mut by_depth: BTreeMap<usize, (usize, f64)> = BTreeMap::new();
+
+        for (key, node) in nodes {
+            *visit_buckets.entry(visit_bucket(node.visits)).or_insert(0) += 1;
+
+            let strategy = node.get_average_strategy();
+            if strategy.iter().any(|&p| p >= NEAR_DETERMINISTIC) {
+                near_deterministic += 1;
+            }
+
+            // The key ends with the number of bids so far.
+            let depth = key.rsplit('|').next().and_then(|d| d.parse().ok()).unwrap_or(0);
+            let entry = by_depth.entry(depth).or_insert((0, 0.0));
+            entry.0 += 1;
+            entry.1 += entropy(&strategy);
+
+        }
+
+        TrainingStats {
+            info_sets: nodes.len(),
+            memory_bytes: memory_bytes(nodes),
+            visit_buckets,
+            near_deterministic: near_deterministic as f64 / nodes.len().max(1) as f64,
+            depths: by_depth
+                .into_iter()
+                .map(|(depth, (count, total))| DepthStats {
+                    depth,
+                    info_sets: count,
+                    mean_entropy: total / count as f64,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let buckets: Vec<String> = self
+            .visit_buckets
+            .iter()
+            .map(|(low, count)| format!("{{\"min_visits\":{},\"info_sets\":{}}}", low, count))
+            .collect();
+        let depths: Vec<String> = self
+            .depths
+            .iter()
+            .map(|d| format!("{{\"depth\":{},\"info_sets\":{},\"mean_entropy\":{}}}", d.depth, d.info_sets, d.mean_entropy))
+            .collect();
+        format!(
+            "{{\"info_sets\":{},\"memory_bytes\":{},\"visit_buckets\":[{}],\"near_deterministic\":{},\"depths\":[{}]}}\n",
+            self.info_sets,
+            self.memory_bytes,
+            buckets.join(","),
+            self.near_deterministic,
+            depths.join(",")
+        )
+    }
+}
+
+impl fmt::Display for TrainingStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Info sets: {}", self.info_sets)?;
+        writeln!(f, "Estimated memory: {:.2} MiB", self.memory_bytes as f64 / (1024.0 * 1024.0))?;
+        writeln!(f, "Visit counts:")?;
+        for (low, count) in &self.visit_buckets {
+            let range = match low {
+                0 => "0".to_string(),
+                _ => format!("{}-{}", low, low * 10 - 1),
+            };
+            writeln!(f, "  {:>15}: {}", range, count)?;
+        }
+        writeln!(f, "Near-deterministic (max prob >= {}): {:.2}%", NEAR_DETERMINISTIC, self.near_deterministic * 100.0)?;
+        writeln!(f, "Average strategy entropy by depth:")?;
+        for d in &self.depths {
+            writeln!(f, "  depth {:>2}: {:.3} bits over {} info sets", d.depth, d.mean_entropy, d.info_sets)?;
+        }
+        Ok(())
+    }
+}
+
+/// Lower bound of the power-of-ten bucket `visits` falls in.
+fn visit_bucket(visits: u64) -> u64 {
+    match visits {
+        0 => 0,
+        v => 10u64.pow(v.ilog10()),
+    }
+}
+
+fn entropy(strategy: &[f32]) -> f64 {
+    strategy
+        .iter()
+        .filter(|&&p| p > 0.0)
+        .map(|&p| -(p as f64) * (p as f64).log2())
+        .sum()
+}
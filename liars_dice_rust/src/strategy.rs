@@ -0,0 +1,576 @@
+use crate::cfr::CFRNode;
+use crate::cli::Args;
+use crate::game::{Action, GameConfig, QuantityCap, DICE_FACES};
+use rand::Rng;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Anything that can say how a player acts at an info set.
+pub trait Policy {
+    /// Probabilities aligned with `actions`, the legal actions at `info_set`.
+    fn action_probabilities(&self, info_set: &str, actions: &[Action]) -> Vec<f64>;
+
+    /// As `action_probabilities`, for a policy that plays each seat differently.
+    fn action_probabilities_for(&self, _seat: u8, info_set: &str, actions: &[Action]) -> Vec<f64> {
+        self.action_probabilities(info_set, actions)
+    }
+}
+
+impl Policy for HashMap<String, CFRNode> {
+    fn action_probabilities(&self, info_set: &str, actions: &[Action]) -> Vec<f64> {
+        match self.get(info_set) {
+            Some(node) => node.probabilities_for(&node.get_average_strategy(), actions),
+            None => vec![1.0 / actions.len() as f64; actions.len()],
+        }
+    }
+}
+
+/// An average strategy loaded from a strategy CSV, keyed by info set.
+#[derive(Clone, Debug, Default)]
+pub struct StrategyTable {
+    pub entries: HashMap<String, Vec<(Action, f64)>>,
+}
+
+impl StrategyTable {
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_reader(File::open(path)?)
+    }
+
+    pub fn from_reader<R: Read>(source: R) -> io::Result<Self> {
+        let mut reader = csv::Reader::from_reader(source);
+        let mut table = StrategyTable::default();
+
+        for record in reader.records() {
+            let record = record.map_err(io::Error::other)?;
+            let line = record.position().map_or(0, |p| p.line());
+            let invalid = || io::Error::new(io::ErrorKind::InvalidData, format!("invalid strategy row at line {}", line));
+
+            if record.len() != 3 {
+                return Err(invalid());
+            }
+            let action = parse_action(&record[1]).ok_or_else(invalid)?;
+            let prob: f64 = record[2].parse().map_err(|_| invalid())?;
+            table.entries.entry(record[0].to_string()).or_default().push((action, prob));
+        }
+        Ok(table)
+    }
+
+    /// The average strategy of trained nodes, keeping what the CSV export would.
+    pub fn from_nodes(nodes: &HashMap<String, CFRNode>, precision: SavePrecision) -> Self {
+        let mut table = StrategyTable::default();
+        for (info_set, node) in nodes {
+            table.entries.insert(info_set.clone(), precision.kept(node));
+        }
+        table
+    }
+
+    pub fn save(&self, filename: &str, config: &GameConfig) -> io::Result<()> {
+        write_metadata(config, filename)?;
+        write_atomically(filename, |file| self.write_csv(file))
+    }
+
+    pub fn write_csv<W: Write>(&self, file: &mut W) -> io::Result<()> {
+        writeln!(file, "InfoSet,Action,Probability")?;
+        let mut info_sets: Vec<&String> = self.entries.keys().collect();
+        info_sets.sort();
+        for info_set in info_sets {
+            for (action, prob) in &self.entries[info_set] {
+                writeln!(file, "{},{},{}", info_set, action_to_str(action), prob)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Policy for StrategyTable {
+    fn action_probabilities(&self, info_set: &str, actions: &[Action]) -> Vec<f64> {
+        let uniform = vec![1.0 / actions.len() as f64; actions.len()];
+        let Some(stored) = self.entries.get(info_set) else {
+            return uniform;
+        };
+
+        // Saved tables may omit low-probability actions, so align and renormalize.
+        let mut probs: Vec<f64> = actions
+            .iter()
+            .map(|a| stored.iter().find(|(s, _)| s == a).map_or(0.0, |(_, p)| *p))
+            .collect();
+        let total: f64 = probs.iter().sum();
+        if total <= 0.0 {
+            return uniform;
+        }
+        probs.iter_mut().for_each(|p| *p /= total);
+        probs
+    }
+}
+
+/// Reshapes a mix for play: each probability is raised to `1 / temperature`
+/// and the result renormalized. 1 leaves the mix as it is, higher values
+/// flatten it towards uniform over the actions it plays, and 0 plays only
+/// the most likely action (splitting ties).
+pub fn apply_temperature(probs: &[f64], temperature: f64) -> Vec<f64> {
+    let best = probs.iter().copied().fold(0.0, f64::max);
+    if temperature == 1.0 || best <= 0.0 {
+        return probs.to_vec();
+    }
+    // Relative to the best action, so low temperatures do not underflow.
+    let weights: Vec<f64> = if temperature <= 0.0 {
+        probs.iter().map(|&p| if p == best { 1.0 } else { 0.0 }).collect()
+    } else {
+        probs.iter().map(|&p| if p > 0.0 { ((p / best).ln() / temperature).exp() } else { 0.0 }).collect()
+    };
+    let total: f64 = weights.iter().sum();
+    weights.iter().map(|w| w / total).collect()
+}
+
+/// Draws an index with the given probabilities; they need not be normalized.
+pub fn sample_index<R: Rng>(probs: &[f64], rng: &mut R) -> usize {
+    let total: f64 = probs.iter().sum();
+    let mut x = rng.gen::<f64>() * total;
+    for (i, p) in probs.iter().enumerate() {
+        if x < *p {
+            return i;
+        }
+        x -= p;
+    }
+    probs.len() - 1
+}
+
+pub fn action_to_str(action: &Action) -> String {
+    action.to_string()
+}
+
+/// Writes `path` via a sibling temp file and a rename, so readers never see a partial file.
+pub fn write_atomically<F>(path: &str, write: F) -> io::Result<()>
+where
+    F: FnOnce(&mut BufWriter<File>) -> io::Result<()>,
+{
+    let tmp_path = format!("{}.tmp", path);
+    let mut writer = BufWriter::new(File::create(&tmp_path)?);
+    write(&mut writer)?;
+    let file = writer.into_inner().map_err(|e| e.into_error())?;
+    file.sync_all()?;
+    fs::rename(&tmp_path, Path::new(path))
+}
+
+pub fn strategy_basename(n_dice_p1: u8, n_dice_p2: u8) -> String {
+    format!("strategy_{}v{}.csv", n_dice_p1, n_dice_p2)
+}
+
+pub fn strategy_filename(n_dice_p1: u8, n_dice_p2: u8) -> String {
+    format!("../{}", strategy_basename(n_dice_p1, n_dice_p2))
+}
+
+/// Sidecar file recording the rules a strategy was solved under.
+pub fn metadata_filename(strategy_file: &str) -> String {
+    format!("{}.meta", strategy_file)
+}
+
+pub fn write_metadata(config: &GameConfig, strategy_file: &str) -> io::Result<()> {
+    write_atomically(&metadata_filename(strategy_file), |file| write!(file, "{}", format_metadata(config)))
+}
+
+/// The rules as `key=value` lines, one per setting.
+pub fn format_metadata(config: &GameConfig) -> String {
+    let weights: Vec<String> = config.face_weights.iter().map(|w| w.to_string()).collect();
+    let calza = config.calza_reward.map_or("none".to_string(), |r| r.to_string());
+    let seats = config.seat_dice.map_or("none".to_string(), |d| d.map(|n| n.to_string()).join(","));
+
+    let mut out = String::new();
+    out.push_str(&format!("dice_p1={}\n", config.dice_p1));
+    out.push_str(&format!("dice_p2={}\n", config.dice_p2));
+    out.push_str(&format!("face_weights={}\n", weights.join(",")));
+    out.push_str(&format!("bid_rules={}\n", config.bid_rules));
+    out.push_str(&format!("quantity_cap={}\n", config.quantity_cap));
+    out.push_str(&format!("quantity_step={}\n", config.quantity_step));
+    out.push_str(&format!("history_abstraction={}\n", config.history_abstraction));
+    out.push_str(&format!("dice_loss={}\n", config.dice_loss));
+    out.push_str(&format!("calza_reward={}\n", calza));
+    out.push_str(&format!("seat_dice={}\n", seats));
+    out.push_str(&format!("opener={}\n", config.opener));
+    out.push_str(&format!("utility={}\n", config.utility));
+    out.push_str(&format!("handicaps={}\n", config.handicaps));
+    if let Some(refinement) = &config.refinement {
+        out.push_str(&format!("refinement={}\n", refinement));
+    }
+    out
+}
+
+/// Reads the rules recorded next to `strategy_file`.
+pub fn read_metadata(strategy_file: &str) -> io::Result<GameConfig> {
+    parse_metadata(&fs::read_to_string(metadata_filename(strategy_file))?)
+}
+
+/// Parses `format_metadata` output; unknown keys and other lines are ignored.
+pub fn parse_metadata(contents: &str) -> io::Result<GameConfig> {
+    let invalid = |key: &str| io::Error::new(io::ErrorKind::InvalidData, format!("invalid metadata value for {}", key));
+    let mut config = GameConfig::new(0, 0);
+
+    for line in contents.lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        match key {
+            "dice_p1" => config.dice_p1 = value.parse().map_err(|_| invalid(key))?,
+            "dice_p2" => config.dice_p2 = value.parse().map_err(|_| invalid(key))?,
+            "face_weights" => {
+                let weights: Vec<f64> = value
+                    .split(',')
+                    .map(|w| w.parse().map_err(|_| invalid(key)))
+                    .collect::<io::Result<_>>()?;
+                config.face_weights = weights.try_into().map_err(|_| invalid(key))?;
+            }
+            "bid_rules" => config.bid_rules = value.parse().map_err(|_| invalid(key))?,
+            // Either zero would leave no legal bid, so neither is a rule a strategy was trained under.
+            "quantity_cap" => config.quantity_cap = value.parse().ok().filter(|&cap| cap != QuantityCap::Fixed(0)).ok_or_else(|| invalid(key))?,
+            "quantity_step" => config.quantity_step = value.parse().ok().filter(|&step| step > 0).ok_or_else(|| invalid(key))?,
+            "history_abstraction" => config.history_abstraction = value.parse().map_err(|_| invalid(key))?,
+            "dice_loss" => config.dice_loss = value.parse().map_err(|_| invalid(key))?,
+            "opener" => config.opener = value.parse().map_err(|_| invalid(key))?,
+            "utility" => config.utility = value.parse().map_err(|_| invalid(key))?,
+            "handicaps" => config.handicaps = value.parse().map_err(|_| invalid(key))?,
+            "refinement" => config.refinement = Some(Arc::new(value.parse().map_err(|_| invalid(key))?)),
+            "calza_reward" => {
+                config.calza_reward = match value {
+                    "none" => None,
+                    r => Some(r.parse().map_err(|_| invalid(key))?),
+                }
+            }
+            "seat_dice" => {
+                config.seat_dice = match value {
+                    "none" => None,
+                    seats => {
+                        let dice: Vec<u8> = seats
+                            .split(',')
+                            .map(|d| d.parse().map_err(|_| invalid(key)))
+                            .collect::<io::Result<_>>()?;
+                        Some(dice.try_into().map_err(|_| invalid(key))?)
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(config)
+}
+
+/// Metadata line of strategies saved from average-only training, whose
+/// regrets were discarded: exact CFR cannot resume from them.
+pub const AVERAGE_ONLY_MARKER: &str = "average_only=true";
+
+/// Whether `format_metadata` output carries `AVERAGE_ONLY_MARKER`.
+pub fn is_average_only(contents: &str) -> bool {
+    contents.lines().any(|line| line == AVERAGE_ONLY_MARKER)
+}
+
+/// Whether the strategy saved as `strategy_file` was trained average-only; a
+/// strategy without metadata is not.
+pub fn read_average_only(strategy_file: &str) -> bool {
+    fs::read_to_string(metadata_filename(strategy_file)).is_ok_and(|contents| is_average_only(&contents))
+}
+
+/// How much of each average strategy a saved strategy keeps.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SavePrecision {
+    /// Drop actions at or below this probability and renormalize the rest,
+    /// written at f32 precision.
+    Threshold(f64),
+    /// Every action, normalized and written at f64 precision.
+    Full,
+}
+
+impl Default for SavePrecision {
+    fn default() -> Self {
+        SavePrecision::Threshold(0.001)
+    }
+}
+
+impl SavePrecision {
+    /// `--full`, or `--save-threshold <probability>`.
+    pub fn from_args(args: &Args) -> Self {
+        if args.has("full") {
+            return SavePrecision::Full;
+        }
+        match args.parse_value::<f64>("save-threshold") {
+            Some(t) if !(0.0..1.0).contains(&t) => {
+                eprintln!("--save-threshold must be at least 0 and below 1.");
+                std::process::exit(2);
+            }
+            Some(t) => SavePrecision::Threshold(t),
+            None => SavePrecision::default(),
+        }
+    }
+
+    /// The node's average strategy as saved.
+    pub fn kept(&self, node: &CFRNode) -> Vec<(Action, f64)> {
+        let SavePrecision::Threshold(threshold) = *self else {
+            return node.actions.iter().cloned().zip(node.average_strategy_f64()).collect();
+        };
+        let average: Vec<(Action, f64)> = node.actions.iter().cloned().zip(node.get_average_strategy().into_iter().map(|p| p as f64)).collect();
+        let kept: Vec<(Action, f64)> = average.iter().filter(|(_, p)| *p > threshold).cloned().collect();
+        // A threshold above every probability would drop the whole info set; keep it as is.
+        let kept = if kept.is_empty() { average } else { kept };
+        let total: f64 = kept.iter().map(|(_, p)| p).sum();
+        kept.into_iter().map(|(a, p)| (a, p / total)).collect()
+    }
+}
+
+pub fn save_strategy(nodes: &HashMap<String, CFRNode>, config: &GameConfig, filename: &str, precision: SavePrecision) {
+    println!("Saving strategy to {}...", filename);
+
+    if nodes.values().any(CFRNode::is_frozen) {
+        let marked = format!("{}{}\n", format_metadata(config), AVERAGE_ONLY_MARKER);
+        write_atomically(&metadata_filename(filename), |file| file.write_all(marked.as_bytes())).expect("Unable to write strategy metadata");
+    } else {
+        write_metadata(config, filename).expect("Unable to write strategy metadata");
+    }
+    write_atomically(filename, |file| write_strategy(file, nodes, precision))
+        .expect("Unable to write strategy file");
+    println!("Save complete.");
+}
+
+/// Info sets merged and written at a time by `write_strategy_shards`.
+pub const SHARD_KEYS: usize = 1 << 16;
+
+/// As `save_strategy` for the sum of several node maps, such as training
+/// workers', written with `write_strategy_shards` so the merged map is never
+/// built.
+pub fn save_strategy_streaming<F>(maps: &[HashMap<String, CFRNode>], config: &GameConfig, filename: &str, precision: SavePrecision, export: F)
+where
+    F: Fn(HashMap<String, CFRNode>) -> HashMap<String, CFRNode>,
+{
+    println!("Saving strategy to {}...", filename);
+
+    if maps.iter().flat_map(HashMap::values).any(CFRNode::is_frozen) {
+        let marked = format!("{}{}\n", format_metadata(config), AVERAGE_ONLY_MARKER);
+        write_atomically(&metadata_filename(filename), |file| file.write_all(marked.as_bytes())).expect("Unable to write strategy metadata");
+    } else {
+        write_metadata(config, filename).expect("Unable to write strategy metadata");
+    }
+    write_atomically(filename, |file| write_strategy_shards(file, maps, precision, export))
+        .expect("Unable to write strategy file");
+    println!("Save complete.");
+}
+
+/// Writes the sum of `maps` as `write_strategy` would, `SHARD_KEYS` info
+/// sets at a time: only a sorted index of the keys and one shard's merged
+/// nodes are held beyond the maps themselves. Nodes merge in map order, so
+/// the sums match folding the maps together. `export` rewrites each shard
+/// before it is written (e.g. `symmetry::expand`); when it renames keys the
+/// rows are sorted within each shard rather than across the file.
+pub fn write_strategy_shards<W, F>(file: &mut W, maps: &[HashMap<String, CFRNode>], precision: SavePrecision, export: F) -> io::Result<()>
+where
+    W: Write,
+    F: Fn(HashMap<String, CFRNode>) -> HashMap<String, CFRNode>,
+{
+    writeln!(file, "InfoSet,Action,Probability")?;
+
+    let mut keys: Vec<&String> = maps.iter().flat_map(HashMap::keys).collect();
+    keys.sort_unstable();
+    keys.dedup();
+    for shard_keys in keys.chunks(SHARD_KEYS) {
+        let mut shard: HashMap<String, CFRNode> = HashMap::with_capacity(shard_keys.len());
+        for &key in shard_keys {
+            let mut merged: Option<CFRNode> = None;
+            for node in maps.iter().filter_map(|map| map.get(key)) {
+                merged.get_or_insert_with(|| CFRNode::new(node.actions.clone())).merge(node);
+            }
+            shard.extend(merged.map(|node| (key.clone(), node)));
+        }
+        write_strategy_rows(file, &export(shard), precision)?;
+    }
+    Ok(())
+}
+
+pub fn write_strategy<W: Write>(file: &mut W, nodes: &HashMap<String, CFRNode>, precision: SavePrecision) -> io::Result<()> {
+    writeln!(file, "InfoSet,Action,Probability")?;
+    write_strategy_rows(file, nodes, precision)
+}
+
+pub fn write_strategy_rows<W: Write>(file: &mut W, nodes: &HashMap<String, CFRNode>, precision: SavePrecision) -> io::Result<()> {
+    // Sorted so identical node maps give identical files.
+    let mut info_sets: Vec<&String> = nodes.keys().collect();
+    info_sets.sort();
+    for info_set in info_sets {
+        for (action, prob) in precision.kept(&nodes[info_set]) {
+            match precision {
+                SavePrecision::Full => writeln!(file, "{},{},{}", info_set, action_to_str(&action), prob)?,
+                SavePrecision::Threshold(_) => writeln!(file, "{},{},{}", info_set, action_to_str(&action), prob as f32)?,
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn parse_action(s: &str) -> Option<Action> {
+    match s {
+        "Challenge" => return Some(Action::Challenge),
+        "Calza" => return Some(Action::Calza),
+        _ => {}
+    }
+    let (q, f) = s.split_once('-')?;
+    Some(Action::Bid(q.parse().ok()?, f.parse().ok()?))
+}
+
+/// The parts of an info set key as produced by `GameState::get_information_set`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InfoSetKey {
+    pub hand: Vec<u8>,
+    pub current_bid: Option<(u8, u8)>,
+    /// Bids remembered before the current one, oldest first; empty unless the
+    /// history abstraction keeps more than the last bid.
+    pub earlier_bids: Vec<(u8, u8)>,
+    pub history_len: usize,
+    /// The pressure features, under the pressure history abstraction.
+    pub pressure: Option<String>,
+    /// Under peek handicaps, the opponent's dice shown to and seen by the player.
+    pub peek: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+impl InfoSetKey {
+    pub fn parse(key: &str) -> Option<Self> {
+        let mut parts = key.split('|');
+        let hand_str = parts.next()?;
+        let bid_str = parts.next()?;
+        let count_str = parts.next()?;
+        let (mut pressure, mut peek) = (None, None);
+        for field in parts {
+            match field.strip_prefix('s') {
+                // Peek features come last, so nothing may follow them.
+                Some(_) if peek.is_some() => return None,
+                Some(features) => {
+                    let (shown, seen) = features.split_once('v')?;
+                    let faces = |dice: &str| dice.chars().map(|c| c.to_digit(10).map(|d| d as u8)).collect::<Option<Vec<u8>>>();
+                    peek = Some((faces(shown)?, faces(seen)?));
+                }
+                None if pressure.is_some() || peek.is_some() => return None,
+                None => pressure = Some(field.to_string()),
+            }
+        }
+
+        let hand = hand_str
+            .chars()
+            .map(|c| c.to_digit(10).map(|d| d as u8))
+            .collect::<Option<Vec<u8>>>()?;
+        if hand.iter().any(|&d| d == 0 || d > DICE_FACES) {
+            return None;
+        }
+
+        let mut bids = Vec::new();
+        if bid_str != "None" {
+            for bid in bid_str.split('/') {
+                match parse_action(bid)? {
+                    Action::Bid(q, f) => bids.push((q, f)),
+                    Action::Challenge | Action::Calza => return None,
+                }
+            }
+        }
+        let current_bid = bids.pop();
+
+        Some(InfoSetKey {
+            hand,
+            current_bid,
+            earlier_bids: bids,
+            history_len: count_str.parse().ok()?,
+            pressure,
+            peek,
+        })
+    }
+
+    /// All remembered bids, oldest first, ending with the current one.
+    pub fn remembered_bids(&self) -> impl Iterator<Item = (u8, u8)> + '_ {
+        self.earlier_bids.iter().copied().chain(self.current_bid)
+    }
+
+    /// The same info set with every face renamed to `relabel[face]`.
+    pub fn relabelled(&self, relabel: &[u8]) -> Self {
+        let mut hand: Vec<u8> = self.hand.iter().map(|&d| relabel[d as usize]).collect();
+        hand.sort();
+        InfoSetKey {
+            hand,
+            current_bid: self.current_bid.map(|(q, f)| (q, relabel[f as usize])),
+            earlier_bids: self.earlier_bids.iter().map(|&(q, f)| (q, relabel[f as usize])).collect(),
+            history_len: self.history_len,
+            pressure: self.pressure.clone(),
+            peek: self.peek.as_ref().map(|(shown, seen)| {
+                let relabel_all = |dice: &[u8]| {
+                    let mut dice: Vec<u8> = dice.iter().map(|&d| relabel[d as usize]).collect();
+                    dice.sort();
+                    dice
+                };
+                (relabel_all(shown), relabel_all(seen))
+            }),
+        }
+    }
+}
+
+impl fmt::Display for InfoSetKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let hand_str: String = self.hand.iter().map(|d| d.to_string()).collect();
+        let bids: Vec<String> = self.remembered_bids().map(|(q, face)| format!("{}-{}", q, face)).collect();
+        let bid_str = if bids.is_empty() { "None".to_string() } else { bids.join("/") };
+        write!(f, "{}|{}|{}", hand_str, bid_str, self.history_len)?;
+        if let Some(features) = &self.pressure {
+            write!(f, "|{}", features)?;
+        }
+        match &self.peek {
+            Some((shown, seen)) => {
+                let faces = |dice: &[u8]| dice.iter().map(|d| d.to_string()).collect::<String>();
+                write!(f, "|s{}v{}", faces(shown), faces(seen))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{BidRules, DiceLoss, HistoryAbstraction, Opener, Utility};
+
+    #[test]
+    fn actions_parse_back_from_their_labels() {
+        for action in [Action::Challenge, Action::Calza, Action::Bid(3, 5), Action::Bid(12, 1)] {
+            assert_eq!(parse_action(&action_to_str(&action)), Some(action));
+        }
+        for bad in ["", "challenge", "3-", "-4", "x-2", "3-5-1", "300-2"] {
+            assert_eq!(parse_action(bad), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn metadata_round_trips_every_rule() {
+        let config = GameConfig {
+            bid_rules: BidRules::QuantityOnly,
+            quantity_cap: QuantityCap::Fixed(3),
+            quantity_step: 2,
+            history_abstraction: HistoryAbstraction::LastBids(2),
+            dice_loss: DiceLoss::Difference,
+            calza_reward: Some(2.0),
+            opener: Opener::Alternate,
+            utility: Utility::RiskAverse(0.5),
+            handicaps: "1:no-calza,2:peek:1".parse().unwrap(),
+            ..GameConfig::new(2, 3).with_face_weights([2.0, 1.0, 1.0, 1.0, 1.0, 2.0])
+        }
+        .with_teams([1, 2, 1, 1]);
+        assert_eq!(parse_metadata(&format_metadata(&config)).unwrap(), config);
+        assert_eq!(parse_metadata(&format_metadata(&GameConfig::new(1, 1))).unwrap(), GameConfig::new(1, 1));
+    }
+
+    #[test]
+    fn metadata_ignores_unknown_keys_and_rejects_bad_values() {
+        let contents = format!("dice_p1=2\ndice_p2=1\n{}\nnot a setting\n", AVERAGE_ONLY_MARKER);
+        let config = parse_metadata(&contents).unwrap();
+        assert_eq!((config.dice_p1, config.dice_p2), (2, 1));
+        assert!(is_average_only(&contents));
+        assert!(!is_average_only(&format_metadata(&config)));
+        assert!(parse_metadata("dice_p1=two\n").is_err());
+        assert!(parse_metadata("face_weights=1,1,1\n").is_err());
+        assert!(parse_metadata("seat_dice=1,2,3\n").is_err());
+        assert!(parse_metadata("quantity_step=0\n").is_err());
+        assert!(parse_metadata("quantity_cap=0\n").is_err());
+    }
+}
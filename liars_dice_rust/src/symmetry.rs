@@ -0,0 +1,219 @@
+use crate::cfr::CFRNode;
+use crate::game::{Action, BidRules, GameConfig, DICE_FACES};
+use crate::strategy::InfoSetKey;
+use std::collections::HashMap;
+
+// Face symmetry.
+//
+// When the bid rules ignore face order and every face is equally likely,
+// renaming the faces consistently across both hands and the bids maps the
+// game onto itself, so info sets that differ only by such a renaming can share
+// one CFR node. The standard ladders rank faces against each other and the
+// aces ladder singles out ones, so under those rules no renaming is safe.
+//
+// Training keys nodes by the canonical info set and translates actions through
+// the renaming; `expand` turns the result back into one node per real info set
+// before anything else sees it.
+
+/// A face renaming indexed by face; slot 0 is unused.
+pub type Relabel = [u8; DICE_FACES as usize + 1];
+
+/// Whether renaming faces leaves the game under `config` unchanged.
+pub fn applies(config: &GameConfig) -> bool {
+    let uniform = config.face_weights.iter().all(|&w| (w - config.face_weights[0]).abs() < 1e-12);
+    // Canonical labels come from the hand and bids alone, so peeked dice would break ties differently.
+    config.bid_rules == BidRules::QuantityOnly && uniform && !config.handicaps.has_peek()
+}
+
+/// The renaming that takes an info set to its canonical form.
+///
+/// Faces named in the remembered bids get the lowest labels in order of first
+/// mention, then the other faces in the hand by descending count, then the rest.
+/// Faces tied on count are interchangeable, so the canonical key does not
+/// depend on how the tie is broken.
+pub fn canonical_relabel(hand: &[u8], bid_faces: impl Iterator<Item = u8>) -> Relabel {
+    let mut relabel = [0; DICE_FACES as usize + 1];
+    let mut next = 1;
+    let mut assign = |face: u8| {
+        if relabel[face as usize] == 0 {
+            relabel[face as usize] = next;
+            next += 1;
+        }
+    };
+
+    bid_faces.for_each(&mut assign);
+    let mut in_hand: Vec<u8> = (1..=DICE_FACES).filter(|f| hand.contains(f)).collect();
+    in_hand.sort_by_key(|f| std::cmp::Reverse(hand.iter().filter(|&d| d == f).count()));
+    in_hand.into_iter().for_each(&mut assign);
+    (1..=DICE_FACES).for_each(&mut assign);
+    relabel
+}
+
+pub fn relabel_action(action: &Action, relabel: &Relabel) -> Action {
+    match action {
+        Action::Bid(q, f) => Action::Bid(*q, relabel[*f as usize]),
+        other => other.clone(),
+    }
+}
+
+/// For each of `actions`, the slot its renamed counterpart occupies.
+///
+/// Renaming faces permutes the legal actions among themselves, so every
+/// renamed action is found in the same list.
+pub fn action_slots(actions: &[Action], relabel: &Relabel) -> Vec<usize> {
+    actions
+        .iter()
+        .map(|a| {
+            let renamed = relabel_action(a, relabel);
+            actions.iter().position(|b| *b == renamed).expect("face renaming permutes legal actions")
+        })
+        .collect()
+}
+
+/// Every renaming of the faces.
+pub fn all_relabels() -> Vec<Relabel> {
+    let mut result = Vec::new();
+    let mut relabel = [0; DICE_FACES as usize + 1];
+    permute(1, &mut relabel, &mut result);
+    result
+}
+
+fn permute(face: u8, relabel: &mut Relabel, out: &mut Vec<Relabel>) {
+    if face > DICE_FACES {
+        out.push(*relabel);
+        return;
+    }
+    for label in 1..=DICE_FACES {
+        if !relabel[1..face as usize].contains(&label) {
+            relabel[face as usize] = label;
+            permute(face + 1, relabel, out);
+        }
+    }
+    relabel[face as usize] = 0;
+}
+
+/// Rewrites nodes keyed by canonical info sets as one node per real info set,
+/// so exports and policy lookups need no knowledge of the symmetry.
+pub fn expand(nodes: &HashMap<String, CFRNode>) -> HashMap<String, CFRNode> {
+    let relabels = all_relabels();
+    let mut expanded = HashMap::new();
+
+    for (key, node) in nodes {
+        let canonical = InfoSetKey::parse(key).expect("trained info set keys always parse");
+        let mut orbit: Vec<InfoSetKey> = Vec::new();
+        for relabel in &relabels {
+            let real = canonical.relabelled(relabel);
+            if !orbit.contains(&real) {
+                orbit.push(real);
+            }
+        }
+
+        // The canonical node's visits were shared by the whole orbit, so split
+        // them rather than credit each real info set with all of them.
+        let share = node.visits / orbit.len() as u64;
+        let extra = (node.visits % orbit.len() as u64) as usize;
+        for (n, real) in orbit.into_iter().enumerate() {
+            let real_key = real.to_string();
+
+            // Translate with the renaming training used for this info set, not
+            // the one that generated it, in case tied faces were split differently.
+            let to_canonical = canonical_relabel(&real.hand, real.remembered_bids().map(|(_, f)| f));
+            let slots = action_slots(&node.actions, &to_canonical);
+            let mut real_node = CFRNode::new(node.actions.clone());
+            real_node.visits = share + u64::from(n < extra);
+            if let Some(frozen) = &node.frozen {
+                real_node.regret_sum = Vec::new();
+                real_node.strategy_sum = Vec::new();
+                real_node.frozen = Some(slots.iter().map(|&slot| frozen[slot]).collect());
+                expanded.insert(real_key, real_node);
+                continue;
+            }
+            for (i, &slot) in slots.iter().enumerate() {
+                real_node.regret_sum[i] = node.regret_sum[slot];
+                real_node.strategy_sum[i] = node.strategy_sum[slot];
+            }
+            expanded.insert(real_key, real_node);
+        }
+    }
+    expanded
+}
+
+/// The inverse of `expand`: one node per canonical info set, taken from the
+/// first real info set found for it with the orbit's visits summed, for
+/// training that resumes from real keys.
+pub fn canonicalize(nodes: &HashMap<String, CFRNode>) -> HashMap<String, CFRNode> {
+    let mut canonical_nodes: HashMap<String, CFRNode> = HashMap::new();
+    let mut keys: Vec<&String> = nodes.keys().collect();
+    keys.sort();
+
+    for key in keys {
+        let node = &nodes[key];
+        let real = InfoSetKey::parse(key).expect("trained info set keys always parse");
+        let to_canonical = canonical_relabel(&real.hand, real.remembered_bids().map(|(_, f)| f));
+        let canonical_key = real.relabelled(&to_canonical).to_string();
+        if let Some(existing) = canonical_nodes.get_mut(&canonical_key) {
+            existing.visits += node.visits;
+            continue;
+        }
+
+        let slots = action_slots(&node.actions, &to_canonical);
+        let mut canonical_node = node.clone();
+        for (i, &slot) in slots.iter().enumerate() {
+            match &node.frozen {
+                Some(frozen) => canonical_node.frozen.as_mut().expect("cloned from a frozen node")[slot] = frozen[i],
+                None => {
+                    canonical_node.regret_sum[slot] = node.regret_sum[i];
+                    canonical_node.strategy_sum[slot] = node.strategy_sum[i];
+                }
+            }
+        }
+        canonical_nodes.insert(canonical_key, canonical_node);
+    }
+    canonical_nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bids() -> Vec<Action> {
+        (1..=2).flat_map(|q| (1..=DICE_FACES).map(move |f| Action::Bid(q, f))).collect()
+    }
+
+    #[test]
+    fn canonical_labels_follow_bids_then_hand_counts() {
+        let relabel = canonical_relabel(&[2, 5, 5], [3].into_iter());
+        assert_eq!(relabel, [0, 4, 3, 1, 5, 2, 6]);
+    }
+
+    #[test]
+    fn relabelled_hands_share_a_canonical_key() {
+        let key = |hand: &[u8], bid: (u8, u8)| {
+            let real = InfoSetKey { hand: hand.to_vec(), current_bid: Some(bid), earlier_bids: Vec::new(), history_len: 1, pressure: None, peek: None };
+            let relabel = canonical_relabel(&real.hand, real.remembered_bids().map(|(_, f)| f));
+            real.relabelled(&relabel).to_string()
+        };
+        assert_eq!(key(&[4, 6], (2, 6)), key(&[1, 3], (2, 3)));
+        assert_ne!(key(&[4, 6], (2, 6)), key(&[6, 6], (2, 6)));
+    }
+
+    #[test]
+    fn expand_splits_visits_across_the_orbit() {
+        let mut node = CFRNode::new(bids());
+        node.visits = 13;
+        node.strategy_sum[0] = 1.0;
+        let nodes = HashMap::from([("1|None|0".to_string(), node)]);
+
+        let expanded = expand(&nodes);
+        assert_eq!(expanded.len(), DICE_FACES as usize);
+        assert_eq!(expanded.values().map(|n| n.visits).sum::<u64>(), 13);
+        // Each real hand bids one of its own face, as the canonical hand did.
+        let six = &expanded["6|None|0"];
+        assert_eq!(six.strategy_sum[six.actions.iter().position(|a| *a == Action::Bid(1, 6)).unwrap()], 1.0);
+
+        let back = canonicalize(&expanded);
+        assert_eq!(back.len(), 1);
+        assert_eq!(back["1|None|0"].visits, 13);
+        assert_eq!(back["1|None|0"].strategy_sum, nodes["1|None|0"].strategy_sum);
+    }
+}
@@ -0,0 +1,808 @@
+use crate::cfr::{CFRNode, CFRTrainer, RegretMinimizer, Sampling};
+use crate::cli::Args;
+use crate::dashboard::Dashboard;
+use crate::deals::{ChanceStream, DealCursor, DealEnumeration, DealScript, DealTargets};
+use crate::exploitability;
+use crate::export::{self, ExportFormat};
+use crate::game::GameConfig;
+use crate::metrics::StrategyDistance;
+use crate::reach;
+use crate::record;
+use crate::sharded::{self, save_sharded_strategy, ShardedNodes};
+use crate::stats::{self, TrainingStats};
+use crate::strategy::{save_strategy_streaming, strategy_filename, write_atomically, SavePrecision, StrategyTable};
+use crate::symmetry;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+fn merge_into(map1: &mut HashMap<String, CFRNode>, key: &str, node2: &CFRNode) {
+    map1.entry(key.to_string()).or_insert_with(|| CFRNode::new(node2.actions.clone())).merge(node2);
+}
+
+/// Adds `map2`'s sums into `map1`, as when combining training workers.
+pub fn merge_nodes(mut map1: HashMap<String, CFRNode>, map2: HashMap<String, CFRNode>) -> HashMap<String, CFRNode> {
+    for (key, node2) in &map2 {
+        merge_into(&mut map1, key, node2);
+    }
+    map1
+}
+
+/// Merges the per-thread maps without consuming them, for saving mid-run.
+fn snapshot_nodes(worker_nodes: &[HashMap<String, CFRNode>]) -> HashMap<String, CFRNode> {
+    let mut merged = HashMap::new();
+    for nodes in worker_nodes {
+        for (key, node) in nodes {
+            merge_into(&mut merged, key, node);
+        }
+    }
+    merged
+}
+
+/// When to write intermediate strategy files during training.
+struct AutosavePolicy {
+    every_iterations: Option<usize>,
+    every: Option<Duration>,
+}
+
+impl AutosavePolicy {
+    fn from_args(args: &Args) -> Self {
+        AutosavePolicy {
+            every_iterations: args.parse_value("save-every"),
+            every: args.parse_value::<f64>("save-minutes").map(|m| Duration::from_secs_f64(m * 60.0)),
+        }
+    }
+
+    fn enabled(&self) -> bool {
+        self.every_iterations.is_some() || self.every.is_some()
+    }
+
+    /// Per-thread chunk size; the save check runs between chunks.
+    fn chunk_size(&self, iters_per_thread: usize, num_threads: usize) -> usize {
+        let chunk = match (self.every_iterations, self.every) {
+            (Some(n), _) => n / num_threads,
+            (None, Some(_)) => iters_per_thread / 100,
+            (None, None) => iters_per_thread,
+        };
+        chunk.max(1)
+    }
+
+    fn is_due(&self, iterations_since_save: usize, last_save: Instant) -> bool {
+        self.every_iterations.is_some_and(|n| iterations_since_save >= n)
+            || self.every.is_some_and(|d| last_save.elapsed() >= d)
+    }
+}
+
+/// Average-only training: between chunks, nodes that have converged or are
+/// rarely visited are frozen, trading their regret and strategy sums for a
+/// compact average strategy. Saves the memory of big runs when only the
+/// final policy is wanted, at the cost of ever resuming exact CFR.
+struct FreezePolicy {
+    min_visits: u64,
+    tolerance: f32,
+    /// Nodes visited on fewer than this share of a worker's iterations.
+    rare_share: f64,
+}
+
+impl FreezePolicy {
+    fn from_args(args: &Args) -> Option<Self> {
+        args.has("average-only").then(|| FreezePolicy {
+            min_visits: args.parse_value("freeze-visits").unwrap_or(1000),
+            tolerance: args.parse_value("freeze-tolerance").unwrap_or(0.01),
+            rare_share: args.parse_value("freeze-rare").unwrap_or(1e-4),
+        })
+    }
+
+    /// Freezing needs several chunks per run to take effect.
+    fn chunk_size(&self, iters_per_thread: usize) -> usize {
+        (iters_per_thread / 20).max(1)
+    }
+
+    /// Freezes the eligible nodes of one worker after `iterations` of its
+    /// iterations and returns how many it froze.
+    fn apply(&self, nodes: &mut HashMap<String, CFRNode>, iterations: usize, minimizer: RegretMinimizer) -> usize {
+        let rare = self.rare_share * iterations as f64;
+        let mut frozen = 0;
+        for node in nodes.values_mut() {
+            if !node.is_frozen() && ((node.visits as f64) < rare || node.has_converged(self.min_visits, self.tolerance, minimizer)) {
+                node.freeze();
+                frozen += 1;
+            }
+        }
+        frozen
+    }
+}
+
+/// Stops training once exploitability reaches a target or stops improving.
+struct StoppingRule {
+    target: f64,
+    check_every: usize,
+    patience: usize,
+    best: f64,
+    checks_without_improvement: usize,
+}
+
+impl StoppingRule {
+    /// Minimum relative improvement over the best value that resets the patience counter.
+    const MIN_IMPROVEMENT: f64 = 0.01;
+
+    fn from_args(args: &Args, iterations: usize) -> Option<Self> {
+        let target = args.parse_value("target-exploitability")?;
+        Some(StoppingRule {
+            target,
+            check_every: args.parse_value("check-every").unwrap_or((iterations / 20).max(1)),
+            patience: args.parse_value("patience").unwrap_or(3),
+            best: f64::INFINITY,
+            checks_without_improvement: 0,
+        })
+    }
+
+    fn chunk_size(&self, num_threads: usize) -> usize {
+        (self.check_every / num_threads).max(1)
+    }
+
+    /// Records a measurement and returns why training should stop, if it should.
+    fn observe(&mut self, exploitability: f64) -> Option<&'static str> {
+        if exploitability <= self.target {
+            return Some("target reached");
+        }
+        if exploitability < self.best * (1.0 - Self::MIN_IMPROVEMENT) {
+            self.best = exploitability;
+            self.checks_without_improvement = 0;
+            None
+        } else {
+            self.checks_without_improvement += 1;
+            (self.checks_without_improvement >= self.patience).then_some("improvement stalled")
+        }
+    }
+}
+
+/// Measures how far the average strategy moves between snapshots, weighted
+/// by the newer snapshot's reach, and optionally stops training once it has
+/// effectively stopped changing.
+struct ConvergenceTracker {
+    every: usize,
+    /// Stop once the strategy moves less than this total variation per 100k iterations.
+    threshold: Option<f64>,
+    previous: Option<(StrategyTable, usize)>,
+}
+
+impl ConvergenceTracker {
+    fn from_args(args: &Args, iterations: usize) -> Option<Self> {
+        let threshold: Option<f64> = args.parse_value("converged-distance");
+        let every = args.parse_value("track-distance").or(threshold.map(|_| (iterations / 20).max(1)))?;
+        Some(ConvergenceTracker { every, threshold, previous: None })
+    }
+
+    fn chunk_size(&self, num_threads: usize) -> usize {
+        (self.every / num_threads).max(1)
+    }
+
+    /// Records a snapshot taken after `completed` iterations and returns why
+    /// training should stop, if it should.
+    fn observe(&mut self, snapshot: StrategyTable, config: &GameConfig, completed: usize) -> Option<&'static str> {
+        let mut stop = None;
+        if let Some((previous, at)) = &self.previous {
+            let reach = reach::info_set_reach_and_actions(&snapshot, config);
+            let distance = StrategyDistance::with_reach(&snapshot, previous, &reach);
+            let per_100k = distance.tv * 100_000.0 / (completed - at).max(1) as f64;
+            println!(
+                "Strategy moved {:.6} total variation (KL {:.6}) over {} iterations, {:.6} per 100k.",
+                distance.tv,
+                distance.kl,
+                completed - at,
+                per_100k
+            );
+            if self.threshold.is_some_and(|t| per_100k < t) {
+                stop = Some("strategy stopped changing");
+            }
+        }
+        self.previous = Some((snapshot, completed));
+        stop
+    }
+}
+
+fn load_deal_script(path: &str, config: &GameConfig) -> DealScript {
+    let script = DealScript::load(path).unwrap_or_else(|e| {
+        eprintln!("Unable to read deal script {}: {}", path, e);
+        std::process::exit(2);
+    });
+    if !script.fits(config) {
+        eprintln!("Deal script {} has no {}v{} deals.", path, config.dice_p1, config.dice_p2);
+        std::process::exit(2);
+    }
+    script
+}
+
+fn load_deal_targets(path: &str, config: &GameConfig, share: f64) -> DealTargets {
+    let records = record::read_records(path).unwrap_or_else(|e| {
+        eprintln!("Unable to read game records {}: {}", path, e);
+        std::process::exit(2);
+    });
+    let targets = DealTargets::from_records(&records, config, share);
+    if targets.len() == 0 {
+        eprintln!("Game records {} have no {}v{} deals.", path, config.dice_p1, config.dice_p2);
+        std::process::exit(2);
+    }
+    targets
+}
+
+/// Reads `--sampling <chance|outcome>` and `--exploration <epsilon>`.
+pub fn sampling_from_args(args: &Args) -> Sampling {
+    match args.value("sampling") {
+        None | Some("chance") => Sampling::Chance,
+        Some("outcome") => Sampling::Outcome { exploration: args.parse_value("exploration").unwrap_or(0.6) },
+        Some(other) => {
+            eprintln!("Invalid value for --sampling: {}", other);
+            std::process::exit(2);
+        }
+    }
+}
+
+/// When to discard the average strategy, so iterations played while the
+/// regrets were still poor stop weighing on the final strategy.
+struct ResetSchedule {
+    /// Total iteration counts to reset at, in increasing order.
+    at: Vec<usize>,
+    /// Reset every this many iterations instead.
+    every: Option<usize>,
+    /// Factor applied to cumulative regrets at each reset; 1 keeps them.
+    regret_scale: f32,
+}
+
+impl ResetSchedule {
+    /// Reads `--reset-averages <n[,n...]|every:n>` and `--reset-regret-scale <factor>`.
+    fn from_args(args: &Args) -> Option<Self> {
+        let spec = args.value("reset-averages")?;
+        let invalid = || -> ! {
+            eprintln!("Invalid value for --reset-averages: {} (expected n[,n...] or every:n)", spec);
+            std::process::exit(2);
+        };
+        let (at, every) = match spec.strip_prefix("every:") {
+            Some(n) => (Vec::new(), Some(n.parse().ok().filter(|&n| n > 0).unwrap_or_else(|| invalid()))),
+            None => {
+                let mut at: Vec<usize> = spec.split(',').map(|n| n.trim().parse().unwrap_or_else(|_| invalid())).collect();
+                at.sort_unstable();
+                (at, None)
+            }
+        };
+        Some(ResetSchedule {
+            at,
+            every,
+            regret_scale: args.parse_value("reset-regret-scale").unwrap_or(1.0),
+        })
+    }
+
+    /// The first reset point after `iterations`.
+    fn next_after(&self, iterations: usize) -> Option<usize> {
+        match self.every {
+            Some(n) => Some((iterations / n + 1) * n),
+            None => self.at.iter().copied().find(|&p| p > iterations),
+        }
+    }
+}
+
+/// Trains one configuration on every rayon thread, honoring the autosave and
+/// early-stopping options, and returns the merged node map.
+pub fn train_config(args: &Args, config: &GameConfig, iterations: usize) -> HashMap<String, CFRNode> {
+    train_config_from(args, config, iterations, HashMap::new())
+}
+
+/// As `train_config`, continuing from `initial` (keyed by real info sets, as
+/// `train_config` returns them) rather than from scratch. Each worker starts
+/// from an equal share of it, so the merged sums count it once.
+pub fn train_config_from(args: &Args, config: &GameConfig, iterations: usize, initial: HashMap<String, CFRNode>) -> HashMap<String, CFRNode> {
+    let (p1_dice, p2_dice) = (config.dice_p1, config.dice_p2);
+    let autosave = AutosavePolicy::from_args(args);
+    let mut stopping = StoppingRule::from_args(args, iterations);
+    let mut convergence = ConvergenceTracker::from_args(args, iterations);
+    let resets = ResetSchedule::from_args(args);
+    let freezing = FreezePolicy::from_args(args);
+    let best_response_opponent = args.has("cfr-br");
+    // These walk every hand a player could hold, which cannot say which of the opponent's dice they see.
+    let hand_enumerating = ["cfr-br", "target-exploitability", "track-distance", "min-reach", "exhaustive-deals", "target-records"];
+    if let Some(option) = hand_enumerating.iter().find(|&&o| args.has(o)) {
+        if let Err(e) = config.require_private_hands(&format!("--{}", option)) {
+            eprintln!("{}.", e);
+            std::process::exit(2);
+        }
+    }
+    let sampling = sampling_from_args(args);
+    if best_response_opponent && sampling != Sampling::Chance {
+        eprintln!("--cfr-br walks the full tree; it cannot be combined with --sampling outcome.");
+        std::process::exit(2);
+    }
+    let target_share: f64 = args.parse_value("target-share").unwrap_or(0.5);
+    if !(0.0..1.0).contains(&target_share) {
+        eprintln!("--target-share must be at least 0 and below 1.");
+        std::process::exit(2);
+    }
+    let deal_targets = args.value("target-records").map(|path| {
+        if best_response_opponent || args.has("deal-script") {
+            eprintln!("--target-records weights sampled deals; it cannot be combined with --cfr-br or --deal-script.");
+            std::process::exit(2);
+        }
+        let targets = load_deal_targets(path, config, target_share);
+        println!("Targeting {} recorded deals from {} ({:.0}% of deals).", targets.len(), path, target_share * 100.0);
+        Arc::new(targets)
+    });
+    let enumeration = args.has("exhaustive-deals").then(|| {
+        if args.has("deal-script") || deal_targets.is_some() {
+            eprintln!("--exhaustive-deals deals every hand pair in turn; it cannot be combined with --deal-script or --target-records.");
+            std::process::exit(2);
+        }
+        Arc::new(DealEnumeration::new(config))
+    });
+    let cursor_path = DealCursor::filename(&strategy_filename(p1_dice, p2_dice));
+    let deal_start = match &enumeration {
+        None => 0,
+        Some(_) if args.has("resume-deals") => {
+            eprintln!("--resume-deals needs the nodes trained so far, which only --out-of-core keeps with the deal cursor.");
+            std::process::exit(2);
+        }
+        Some(deals) => enumeration_start(args, deals, &cursor_path),
+    };
+    let save_cursor = |done: usize| {
+        if let Some(deals) = &enumeration {
+            let cursor = DealCursor { next: deal_start + done, per_sweep: deals.len() };
+            cursor.save(&cursor_path).expect("Unable to write the deal cursor");
+            println!("Next deal {} ({} of sweep {}), saved to {}.", cursor.next, cursor.next % cursor.per_sweep, cursor.next / cursor.per_sweep + 1, cursor_path);
+        }
+    };
+    let trainer = CFRTrainer {
+        // The best response and the sampled traversals look nodes up by real info
+        // set, so CFR-BR and outcome sampling train without symmetry.
+        face_symmetry: symmetry::applies(config) && !args.has("no-symmetry") && !best_response_opponent && sampling == Sampling::Chance,
+        minimizer: args.parse_value("minimizer").unwrap_or_default(),
+        best_response_opponent,
+        deal_script: args.value("deal-script").map(|path| Arc::new(load_deal_script(path, config))),
+        deal_targets,
+        sampling,
+    };
+    // Canonical nodes are expanded before anything outside training looks at them.
+    let export = |nodes: HashMap<String, CFRNode>| if trainer.face_symmetry { symmetry::expand(&nodes) } else { nodes };
+
+    let algorithm = match trainer.sampling {
+        _ if best_response_opponent => "CFR-BR".to_string(),
+        Sampling::Chance => "Vanilla CFR".to_string(),
+        sampling => format!("MCCFR, {}", sampling),
+    };
+    println!("Starting Rust training ({}, {}) for {}v{} with {} iterations...", algorithm, trainer.minimizer, p1_dice, p2_dice, iterations);
+    let dashboard = start_dashboard(args, format!("{}v{}, {}", p1_dice, p2_dice, algorithm), iterations);
+    // Without a stopping rule, --check-every still measures exploitability for the dashboard.
+    let dashboard_check: Option<usize> = if stopping.is_none() && dashboard.is_some() { args.parse_value("check-every") } else { None };
+    if dashboard_check.is_some() {
+        if let Err(e) = config.require_private_hands("--check-every") {
+            eprintln!("{}.", e);
+            std::process::exit(2);
+        }
+    }
+    
+    let start_time = Instant::now();
+    let deadline = args.parse_value::<f64>("time-limit").map(|minutes| start_time + Duration::from_secs_f64(minutes * 60.0));
+
+    // Determine number of threads; the first `iterations % num_threads` workers run one extra iteration.
+    let num_threads = rayon::current_num_threads();
+    let quotas: Vec<usize> = (0..num_threads).map(|w| iterations / num_threads + usize::from(w < iterations % num_threads)).collect();
+    let iters_per_thread = quotas[0];
+    // With --work-stealing the quotas only size the chunks: workers instead pull
+    // batches from each chunk's share of the budget, so which worker deals
+    // what, and so a seeded run's result, depends on timing.
+    let stealing = args.has("work-stealing");
+    // Worker w deals w, w + workers, ... of the sequence, so only equal shares
+    // (give or take the low workers' extra one) cover a contiguous range that
+    // the deal cursor can record. Stealing and a time limit both break that.
+    if args.has("exhaustive-deals") || args.has("deal-script") {
+        if let Some(option) = ["work-stealing", "time-limit"].into_iter().find(|o| args.has(o)) {
+            eprintln!("--{} gives workers uneven shares of the deals; it cannot be combined with --exhaustive-deals or --deal-script.", option);
+            std::process::exit(2);
+        }
+    }
+    // Iterations between looks at the shared budget and the time limit. Even,
+    // so outcome sampling's traversers alternate evenly within each batch.
+    let batch = args.parse_value("batch-size").unwrap_or((iterations / (num_threads * 256)).clamp(2, 1024)).max(1).next_multiple_of(2);
+
+    match (stealing, iterations % num_threads) {
+        (true, _) => println!("Running on {} threads, pulling batches of {} iterations from a shared budget.", num_threads, batch),
+        (false, 0) => println!("Running on {} threads, {} iterations per thread.", num_threads, iters_per_thread),
+        (false, extra) => println!("Running on {} threads, {} iterations per thread ({} of them run {}).", num_threads, iters_per_thread - 1, extra, iters_per_thread),
+    }
+    if trainer.face_symmetry {
+        println!("Sharing nodes between info sets that differ only by face labels.");
+    }
+    if freezing.is_some() {
+        println!("Average-only mode: freezing converged and rarely visited nodes; the saved strategy cannot resume exact CFR.");
+    }
+
+    // Parallel Map-Reduce, run in chunks so intermediate results can be saved
+    if !initial.is_empty() {
+        println!("Warm-starting from {} info sets.", initial.len());
+    }
+    let mut initial = if trainer.face_symmetry { symmetry::canonicalize(&initial) } else { initial };
+    initial.values_mut().for_each(|node| node.scale(1.0 / num_threads as f32));
+    let mut worker_nodes: Vec<HashMap<String, CFRNode>> = (0..num_threads).map(|_| initial.clone()).collect();
+    let seed: Option<u64> = args.parse_value("seed");
+    let mut chance: Vec<ChanceStream> = (0..num_threads)
+        .map(|w| {
+            let stream = ChanceStream::new(seed, w, num_threads);
+            match &enumeration {
+                Some(deals) => stream.enumerating(Arc::clone(deals), deal_start),
+                None => stream,
+            }
+        })
+        .collect();
+    let mut chunk_size = autosave.chunk_size(iters_per_thread, num_threads);
+    if let Some(rule) = &stopping {
+        chunk_size = chunk_size.min(rule.chunk_size(num_threads));
+    }
+    if let Some(policy) = &freezing {
+        chunk_size = chunk_size.min(policy.chunk_size(iters_per_thread));
+    }
+    if let Some(tracker) = &convergence {
+        chunk_size = chunk_size.min(tracker.chunk_size(num_threads));
+    }
+    if dashboard.is_some() {
+        chunk_size = chunk_size.min((iters_per_thread / 100).max(1));
+    }
+    if let Some(every) = dashboard_check {
+        chunk_size = chunk_size.min((every / num_threads).max(1));
+    }
+    let mut worker_done = vec![0; num_threads];
+    let mut total = 0;
+    let mut last = iterations == 0;
+    let mut since_save = 0;
+    let mut since_check = 0;
+    let mut since_track = 0;
+    let mut last_save = Instant::now();
+    let mut final_exploitability = None;
+    let mut last_reset = 0;
+
+    while !last {
+        let mut chunk = chunk_size;
+        let next_reset = resets.as_ref().and_then(|r| r.next_after(last_reset));
+        if let Some(point) = next_reset {
+            // End the chunk at the reset point so the reset lands on schedule.
+            chunk = chunk.min(point.saturating_sub(total).div_ceil(num_threads).max(1));
+        }
+        let per_worker: Vec<usize> = match stealing {
+            true => {
+                let mut budget = (chunk * num_threads).min(iterations - total);
+                if let Some(point) = next_reset {
+                    budget = budget.min(point.saturating_sub(total).max(1));
+                }
+                let claimed = AtomicUsize::new(0);
+                worker_nodes
+                    .par_iter_mut()
+                    .zip(chance.par_iter_mut())
+                    .map(|(nodes, chance)| {
+                        let mut ran = 0;
+                        while deadline.is_none_or(|d| Instant::now() < d) {
+                            let start = claimed.fetch_add(batch, Ordering::Relaxed);
+                            if start >= budget {
+                                break;
+                            }
+                            let count = batch.min(budget - start);
+                            trainer.train_into(nodes, chance, config, count);
+                            ran += count;
+                        }
+                        ran
+                    })
+                    .collect()
+            }
+            false => worker_nodes
+                .par_iter_mut()
+                .zip(chance.par_iter_mut())
+                .zip(quotas.par_iter().zip(&worker_done))
+                .map(|((nodes, chance), (&quota, &done))| {
+                    let count = chunk.min(quota - done);
+                    // Only a time limit splits the quota, which would otherwise change the traversal order.
+                    let step = if deadline.is_some() { batch } else { count };
+                    let mut ran = 0;
+                    while ran < count && deadline.is_none_or(|d| Instant::now() < d) {
+                        trainer.train_into(nodes, chance, config, step.min(count - ran));
+                        ran += step.min(count - ran);
+                    }
+                    ran
+                })
+                .collect(),
+        };
+        worker_done.iter_mut().zip(&per_worker).for_each(|(done, ran)| *done += ran);
+        let ran: usize = per_worker.iter().sum();
+        total += ran;
+        let timed_out = deadline.is_some_and(|d| Instant::now() >= d);
+        last = total >= iterations || timed_out;
+        if timed_out && total < iterations {
+            println!("Time limit reached after {} iterations.", total);
+        }
+        since_save += ran;
+        since_check += ran;
+        since_track += ran;
+
+        if let (Some(schedule), Some(point)) = (&resets, next_reset) {
+            if total >= point && !last {
+                for node in worker_nodes.iter_mut().flat_map(|nodes| nodes.values_mut()) {
+                    node.reset_average(schedule.regret_scale);
+                }
+                println!("Reset the average strategy after {} iterations (regrets scaled by {}).", total, schedule.regret_scale);
+                last_reset = total;
+            }
+        }
+
+        if let Some(policy) = &freezing {
+            if !last {
+                let frozen: usize = worker_nodes.par_iter_mut().zip(&worker_done).map(|(nodes, &done)| policy.apply(nodes, done, trainer.minimizer)).sum();
+                if frozen > 0 {
+                    println!("Froze {} nodes after {} iterations.", frozen, total);
+                }
+            }
+        }
+
+        if let Some(rule) = stopping.as_mut() {
+            if since_check >= rule.check_every || last {
+                let value = exploitability::exploitability(&export(snapshot_nodes(&worker_nodes)), config);
+                println!("Exploitability after {} iterations: {:.6}", total, value);
+                final_exploitability = Some(value);
+                since_check = 0;
+                if let Some(dashboard) = &dashboard {
+                    dashboard.update(|status| status.exploitability = Some((value, total)));
+                }
+                if let Some(reason) = rule.observe(value) {
+                    println!("Stopping early: {}.", reason);
+                    break;
+                }
+            }
+        }
+        if let (Some(dashboard), Some(every)) = (&dashboard, dashboard_check) {
+            if since_check >= every || last {
+                let value = exploitability::exploitability(&export(snapshot_nodes(&worker_nodes)), config);
+                final_exploitability = Some(value);
+                since_check = 0;
+                dashboard.update(|status| status.exploitability = Some((value, total)));
+            }
+        }
+        if let Some(dashboard) = &dashboard {
+            let nodes = worker_nodes.iter().map(HashMap::len).sum();
+            let memory = worker_nodes.iter().map(stats::memory_bytes).sum();
+            dashboard.update(|status| {
+                status.done = total;
+                status.nodes = Some(nodes);
+                status.memory_bytes = Some(memory);
+            });
+        }
+
+        if let Some(tracker) = convergence.as_mut() {
+            if since_track >= tracker.every || last {
+                since_track = 0;
+                let snapshot = StrategyTable::from_nodes(&export(snapshot_nodes(&worker_nodes)), SavePrecision::Full);
+                if let Some(reason) = tracker.observe(snapshot, config, total) {
+                    println!("Stopping early: {}.", reason);
+                    break;
+                }
+            }
+        }
+
+        if autosave.enabled() && !last && autosave.is_due(since_save, last_save) {
+            println!("Autosave after {} iterations.", total);
+            // Streamed shard by shard, so the autosave never holds a merged copy of every worker's nodes.
+            save_strategy_streaming(&worker_nodes, config, &strategy_filename(p1_dice, p2_dice), SavePrecision::from_args(args), export);
+            save_cursor(total);
+            since_save = 0;
+            last_save = Instant::now();
+        }
+    }
+
+    if let Some(dashboard) = &dashboard {
+        dashboard.update(|status| status.finished = true);
+    }
+    // Merge in worker order so the f32 sums, and so the saved file, do not depend on scheduling.
+    let final_nodes = worker_nodes.into_iter().fold(HashMap::new(), merge_nodes);
+    if trainer.face_symmetry {
+        println!("Trained {} canonical info sets.", final_nodes.len());
+    }
+
+    let duration = start_time.elapsed();
+    println!("Training complete in {:.2?} ({} iterations)", duration, total);
+    println!("Iterations per second: {:.2}", total as f64 / duration.as_secs_f64());
+    if stealing {
+        let shares: Vec<String> = worker_done.iter().map(usize::to_string).collect();
+        println!("Iterations per worker: {}", shares.join(", "));
+    }
+    if let Some(value) = final_exploitability {
+        println!("Final exploitability: {:.6}", value);
+    }
+    save_cursor(total);
+
+    let stats = TrainingStats::collect(&final_nodes);
+    print!("{}", stats);
+    if let Some(path) = args.value("stats-json") {
+        write_atomically(path, |file| file.write_all(stats.to_json().as_bytes())).expect("Unable to write training statistics");
+        println!("Wrote training statistics to {}.", path);
+    }
+
+    let mut final_nodes = export(final_nodes);
+    if let Some(path) = args.value("export-regrets") {
+        write_atomically(path, |file| export::write_regrets(file, &final_nodes, ExportFormat::from_path(path), Some(config)))
+            .expect("Unable to write regrets");
+        println!("Wrote cumulative regrets to {}.", path);
+    }
+    if let Some(min_reach) = args.parse_value::<f64>("min-reach") {
+        let reach = reach::info_set_reach(&final_nodes, config);
+        let before = final_nodes.len();
+        final_nodes.retain(|key, _| reach.get(key).is_some_and(|&r| r >= min_reach));
+        println!("Dropped {} of {} info sets reached with probability below {}.", before - final_nodes.len(), before, min_reach);
+    }
+    final_nodes
+}
+
+/// The global deal an enumeration starts at: `--deal-start`, the cursor in
+/// `cursor_path` under `--resume-deals`, or the first.
+fn enumeration_start(args: &Args, deals: &DealEnumeration, cursor_path: &str) -> usize {
+    let start = match args.parse_value::<usize>("deal-start") {
+        Some(start) => start,
+        None if args.has("resume-deals") => {
+            let cursor = DealCursor::load(cursor_path).unwrap_or_else(|e| {
+                eprintln!("Unable to read the deal cursor: {}", e);
+                std::process::exit(2);
+            });
+            if cursor.per_sweep != deals.len() {
+                eprintln!("{} was saved for sweeps of {} deals; these rules have {}.", cursor_path, cursor.per_sweep, deals.len());
+                std::process::exit(2);
+            }
+            cursor.next
+        }
+        None => 0,
+    };
+    println!("Enumerating all {} deals per sweep, starting at deal {} (sweep {}).", deals.len(), start, start / deals.len() + 1);
+    start
+}
+
+/// Options `train_out_of_core` cannot honor: they need every node in memory
+/// at once, or several workers.
+const IN_MEMORY_OPTIONS: [&str; 16] = [
+    "cfr-br",
+    "target-records",
+    "target-exploitability",
+    "check-every",
+    "patience",
+    "track-distance",
+    "converged-distance",
+    "reset-averages",
+    "average-only",
+    "freeze-visits",
+    "freeze-tolerance",
+    "freeze-rare",
+    "min-reach",
+    "stats-json",
+    "export-regrets",
+    "work-stealing",
+];
+
+/// Trains one configuration on a single worker whose nodes live in shard
+/// files under `dir`, with only `--cache-shards` of them in memory, for trees
+/// too big for RAM. Shards left in `dir` by an earlier run are trained on
+/// as a warm start, not resumed: the run trains `iterations` more from the
+/// start of its chance stream, and a run that was killed may have left
+/// shards from different points of training (see `ShardedNodes::flush`).
+/// Only an enumeration picks up where it left off, from the deal cursor kept
+/// in `dir` under `--resume-deals`. The strategy is written shard by shard.
+pub fn train_out_of_core(args: &Args, config: &GameConfig, iterations: usize, dir: &str) {
+    if let Some(option) = IN_MEMORY_OPTIONS.iter().find(|&&o| args.has(o)) {
+        eprintln!("--{} needs the whole node map in memory; it cannot be combined with --out-of-core.", option);
+        std::process::exit(2);
+    }
+    let (p1_dice, p2_dice) = (config.dice_p1, config.dice_p2);
+    let shards = args.parse_value("shards").unwrap_or(sharded::DEFAULT_SHARDS);
+    let cache_shards = args.parse_value("cache-shards").unwrap_or(sharded::DEFAULT_CACHE_SHARDS);
+    let mut store = ShardedNodes::open(dir, shards, cache_shards, config).unwrap_or_else(|e| {
+        eprintln!("Unable to open node shards in {}: {}", dir, e);
+        std::process::exit(2);
+    });
+    let sampling = sampling_from_args(args);
+    let trainer = CFRTrainer {
+        face_symmetry: symmetry::applies(config) && !args.has("no-symmetry") && sampling == Sampling::Chance,
+        minimizer: args.parse_value("minimizer").unwrap_or_default(),
+        best_response_opponent: false,
+        deal_script: args.value("deal-script").map(|path| Arc::new(load_deal_script(path, config))),
+        deal_targets: None,
+        sampling,
+    };
+    let export = |nodes: HashMap<String, CFRNode>| if trainer.face_symmetry { symmetry::expand(&nodes) } else { nodes };
+    let filename = strategy_filename(p1_dice, p2_dice);
+    let precision = SavePrecision::from_args(args);
+    let enumeration = args.has("exhaustive-deals").then(|| {
+        if trainer.deal_script.is_some() {
+            eprintln!("--exhaustive-deals deals every hand pair in turn; it cannot be combined with --deal-script.");
+            std::process::exit(2);
+        }
+        if let Err(e) = config.require_private_hands("--exhaustive-deals") {
+            eprintln!("{}.", e);
+            std::process::exit(2);
+        }
+        Arc::new(DealEnumeration::new(config))
+    });
+    // The cursor is saved right after each flush of the shards, so resuming
+    // from it continues the nodes as they were then. Shards evicted since hold
+    // a few later deals already, which a resumed run trains on again.
+    let cursor_path = Path::new(dir).join("cursor.deals").to_string_lossy().into_owned();
+    let deal_start = enumeration.as_ref().map_or(0, |deals| enumeration_start(args, deals, &cursor_path));
+    let save_cursor = |done: usize| {
+        if let Some(deals) = &enumeration {
+            let cursor = DealCursor { next: deal_start + done, per_sweep: deals.len() };
+            cursor.save(&cursor_path).expect("Unable to write the deal cursor");
+            println!("Next deal {} ({} of sweep {}), saved to {}.", cursor.next, cursor.next % cursor.per_sweep, cursor.next / cursor.per_sweep + 1, cursor_path);
+        }
+    };
+
+    let algorithm = match trainer.sampling {
+        Sampling::Chance => "Vanilla CFR".to_string(),
+        sampling => format!("MCCFR, {}", sampling),
+    };
+    println!("Starting Rust training ({}, {}) for {}v{} with {} iterations...", algorithm, trainer.minimizer, p1_dice, p2_dice, iterations);
+    println!("Out of core: {} node shards in {}, at most {} in memory, on 1 thread.", store.shards(), dir, cache_shards);
+    if trainer.face_symmetry {
+        println!("Sharing nodes between info sets that differ only by face labels.");
+    }
+
+    let dashboard = start_dashboard(args, format!("{}v{}, {}, out of core", p1_dice, p2_dice, algorithm), iterations);
+
+    let start_time = Instant::now();
+    let autosave = AutosavePolicy::from_args(args);
+    let mut chunk_size = autosave.chunk_size(iterations, 1);
+    if dashboard.is_some() {
+        chunk_size = chunk_size.min((iterations / 100).max(1));
+    }
+    let deadline = args.parse_value::<f64>("time-limit").map(|minutes| start_time + Duration::from_secs_f64(minutes * 60.0));
+    if deadline.is_some() {
+        chunk_size = chunk_size.min((iterations / 100).max(1));
+    }
+    let mut chance = ChanceStream::new(args.parse_value("seed"), 0, 1);
+    if let Some(deals) = &enumeration {
+        chance = chance.enumerating(Arc::clone(deals), deal_start);
+    }
+    let mut done = 0;
+    let mut since_save = 0;
+    let mut last_save = Instant::now();
+    while done < iterations {
+        let chunk = chunk_size.min(iterations - done);
+        trainer.train_into(&mut store, &mut chance, config, chunk);
+        done += chunk;
+        since_save += chunk;
+        if let Some(dashboard) = &dashboard {
+            dashboard.update(|status| status.done = done);
+        }
+        if done < iterations && deadline.is_some_and(|d| Instant::now() >= d) {
+            println!("Time limit reached after {} iterations.", done);
+            break;
+        }
+        if autosave.enabled() && done < iterations && autosave.is_due(since_save, last_save) {
+            println!("Autosave after {} iterations.", done);
+            save_sharded_strategy(&mut store, config, &filename, precision, export);
+            save_cursor(done);
+            since_save = 0;
+            last_save = Instant::now();
+        }
+    }
+
+    let duration = start_time.elapsed();
+    println!("Training complete in {:.2?} ({} iterations)", duration, done);
+    println!("Iterations per second: {:.2}", done as f64 / duration.as_secs_f64());
+    println!("Shard loads: {}, shard writes: {}", store.loads, store.writes);
+    if let Some(dashboard) = &dashboard {
+        dashboard.update(|status| status.finished = true);
+    }
+    save_sharded_strategy(&mut store, config, &filename, precision, export);
+    save_cursor(done);
+}
+
+/// The `--dashboard <port>` status server, if asked for.
+fn start_dashboard(args: &Args, label: String, iterations: usize) -> Option<Dashboard> {
+    let port: u16 = args.parse_value("dashboard")?;
+    Some(Dashboard::start(port, label, iterations).unwrap_or_else(|e| {
+        eprintln!("Unable to serve the dashboard on port {}: {}", port, e);
+        std::process::exit(2);
+    }))
+}
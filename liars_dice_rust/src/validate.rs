@@ -0,0 +1,192 @@
+use crate::game::{BidRules, GameConfig, GameState, HistoryAbstraction, Refinement, DICE_FACES};
+use crate::strategy::{parse_action, read_metadata, InfoSetKey};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// A single inconsistency found in a strategy file.
+#[derive(Debug)]
+pub struct Problem {
+    pub line: u64,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+struct InfoSetSummary {
+    first_line: u64,
+    total: f64,
+    actions: Vec<String>,
+}
+
+/// Checks a strategy CSV against the dice configuration it claims to solve.
+///
+/// Reports rows that cannot be parsed, info sets that cannot occur in an
+/// game with the configured dice counts, actions that are illegal for the info set,
+/// and info sets whose probabilities do not sum to 1 within `tolerance`.
+pub fn validate_strategy_file(
+    path: &str,
+    config: &GameConfig,
+    tolerance: f64,
+) -> Result<Vec<Problem>, csv::Error> {
+    let mut reader = csv::Reader::from_path(path)?;
+    let mut problems = Vec::new();
+
+    // Line 0 stands for the metadata sidecar, which has no CSV line of its own.
+    if let Ok(recorded) = read_metadata(path) {
+        if recorded != *config {
+            problems.push(Problem {
+                line: 0,
+                message: format!("metadata records different rules than declared: {:?}", recorded),
+            });
+        }
+    }
+    let mut info_sets: HashMap<String, InfoSetSummary> = HashMap::new();
+    let mut orphans: HashSet<String> = HashSet::new();
+
+    for record in reader.records() {
+        let record = record?;
+        let line = record.position().map_or(0, |p| p.line());
+        let mut report = |message: String| problems.push(Problem { line, message });
+
+        if record.len() != 3 {
+            report(format!("expected 3 fields, found {}", record.len()));
+            continue;
+        }
+        let (info_set, action_str, prob_str) = (&record[0], &record[1], &record[2]);
+
+        let prob: f64 = match prob_str.parse() {
+            Ok(p) if (0.0..=1.0).contains(&p) => p,
+            _ => {
+                report(format!("invalid probability '{}'", prob_str));
+                continue;
+            }
+        };
+
+        let action = match parse_action(action_str) {
+            Some(a) => a,
+            None => {
+                report(format!("invalid action '{}'", action_str));
+                continue;
+            }
+        };
+
+        // Unreachable info sets are reported once, not once per action row.
+        if orphans.contains(info_set) {
+            continue;
+        }
+        let key = match InfoSetKey::parse(info_set) {
+            Some(key) => key,
+            None => {
+                report(format!("malformed info set '{}'", info_set));
+                orphans.insert(info_set.to_string());
+                continue;
+            }
+        };
+        if let Some(reason) = orphan_reason(&key, config) {
+            report(format!("info set '{}' cannot occur in a {}v{} game: {}", info_set, config.dice_p1, config.dice_p2, reason));
+            orphans.insert(info_set.to_string());
+            continue;
+        }
+
+        let mut state = GameState::new(config);
+        state.current_bid = key.current_bid;
+        if state.action_index(action.clone()).is_none() {
+            report(format!("action '{}' is not legal in info set '{}'", action_str, info_set));
+        }
+
+        let summary = info_sets.entry(info_set.to_string()).or_insert_with(|| InfoSetSummary {
+            first_line: line,
+            total: 0.0,
+            actions: Vec::new(),
+        });
+        if summary.actions.iter().any(|a| a == action_str) {
+            report(format!("duplicate action '{}' for info set '{}'", action_str, info_set));
+        } else {
+            summary.actions.push(action_str.to_string());
+            summary.total += prob;
+        }
+    }
+
+    for (info_set, summary) in &info_sets {
+        if (summary.total - 1.0).abs() > tolerance {
+            problems.push(Problem {
+                line: summary.first_line,
+                message: format!("probabilities for info set '{}' sum to {:.6}", info_set, summary.total),
+            });
+        }
+    }
+
+    problems.sort_by_key(|p| p.line);
+    Ok(problems)
+}
+
+/// Explains why an info set is unreachable in the given configuration, if it is.
+fn orphan_reason(key: &InfoSetKey, config: &GameConfig) -> Option<String> {
+    let player = key.history_len % 2;
+    let openings = config.openings();
+    let expected: Vec<u8> = openings.iter().map(|(round, _)| if player == 0 { round.dice_p1 } else { round.dice_p2 }).collect();
+
+    if !expected.contains(&(key.hand.len() as u8)) {
+        let role = if player == 0 { "opener" } else { "responder" };
+        return Some(format!("the {} holds {} dice, hand has {}", role, expected[0], key.hand.len()));
+    }
+    if key.hand.windows(2).any(|w| w[0] > w[1]) {
+        return Some("hand is not sorted".to_string());
+    }
+
+    let remembered = key.earlier_bids.len() + key.current_bid.is_some() as usize;
+    let expected_remembered = match config.history_abstraction {
+        HistoryAbstraction::LastBids(k) => k.min(key.history_len),
+        HistoryAbstraction::Full => key.history_len,
+        HistoryAbstraction::Pressure => 1.min(key.history_len),
+    };
+    if key.pressure.is_some() != (config.history_abstraction == HistoryAbstraction::Pressure) {
+        return Some(format!("pressure features do not fit the {} history abstraction", config.history_abstraction));
+    }
+    if key.peek.is_some() != config.handicaps.has_peek() {
+        return Some(format!("peek features do not fit the handicaps ({})", config.handicaps));
+    }
+    // Under a refinement each extra bid must be earned by a split of the bids after it.
+    let split = |suffix: usize| {
+        let bids = key.remembered_bids().skip(remembered - suffix).map(|(q, f)| format!("{}-{}", q, f));
+        config.refinement.as_ref().is_some_and(|r| r.contains(&Refinement::public_key(bids, key.history_len)))
+    };
+    let refined = remembered > expected_remembered && remembered <= key.history_len && (expected_remembered..remembered).all(split);
+    if remembered != expected_remembered && !refined {
+        return Some(format!("remembers {} bids, the history abstraction keeps {}", remembered, expected_remembered));
+    }
+    if let Some(current) = key.current_bid {
+        let mut chain = key.earlier_bids.clone();
+        chain.push(current);
+        if chain.windows(2).any(|w| !config.bid_rules.is_raise(w[0], w[1])) {
+            return Some("remembered bids are not a legal raise sequence".to_string());
+        }
+    }
+
+    match key.current_bid {
+        None if key.history_len != 0 => Some("no current bid after bidding started".to_string()),
+        None => None,
+        Some(_) if key.history_len == 0 => Some("current bid before any action".to_string()),
+        Some((q, f)) => {
+            if !config.quantity_allowed(q) || f == 0 || f > DICE_FACES {
+                return Some(format!("bid {}-{} is out of range", q, f));
+            }
+            // Longest possible bid sequence ending at this bid; the ladder can revisit quantities.
+            let max_len = match config.bid_rules {
+                BidRules::Standard | BidRules::NoFaceReset => Some((q as usize - 1) * DICE_FACES as usize + f as usize),
+                BidRules::QuantityOnly => Some(q as usize),
+                BidRules::AcesLadder => None,
+            };
+            match max_len {
+                Some(max_len) if key.history_len > max_len => {
+                    Some(format!("{} bids cannot end at bid {}-{}", key.history_len, q, f))
+                }
+                _ => None,
+            }
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use crate::cli::Args;
+use crate::exact;
+use crate::exploitability;
+use crate::game::{BidRules, GameConfig, HistoryAbstraction, QuantityCap};
+use crate::kuhn;
+use crate::train;
+
+/// A game small enough for `exact::solve`, whose value training must reproduce.
+/// All are played with perfect recall, where CFR is guaranteed to converge.
+pub struct ReferenceGame {
+    pub name: &'static str,
+    pub config: GameConfig,
+}
+
+pub fn reference_games() -> Vec<ReferenceGame> {
+    let tiny = |dice_p1, dice_p2| {
+        let mut config = GameConfig::new(dice_p1, dice_p2);
+        config.quantity_cap = QuantityCap::Fixed(1);
+        config.history_abstraction = HistoryAbstraction::Full;
+        config
+    };
+    vec![
+        ReferenceGame { name: "1v1, three-sided dice", config: tiny(1, 1).with_face_weights([1.0, 1.0, 1.0, 0.0, 0.0, 0.0]) },
+        ReferenceGame { name: "1v1, quantity 1", config: tiny(1, 1) },
+        ReferenceGame { name: "1v2, quantity 1", config: tiny(1, 2) },
+        // Quantity-only raises are what face symmetry applies to.
+        ReferenceGame {
+            name: "1v1, quantity-only bids",
+            config: GameConfig { bid_rules: BidRules::QuantityOnly, quantity_cap: QuantityCap::Total, ..tiny(1, 1) },
+        },
+    ]
+}
+
+/// A training setup to check, as the options that select it and the share of
+/// the base iteration count that converges about as far in similar time:
+/// sampled iterations are cheap, best-response iterations expensive.
+pub struct Variant {
+    pub name: &'static str,
+    pub options: &'static [&'static str],
+    pub iteration_scale: f64,
+}
+
+pub const VARIANTS: &[Variant] = &[
+    Variant { name: "chance-sampled CFR+", options: &[], iteration_scale: 1.0 },
+    Variant { name: "without symmetry", options: &["--no-symmetry"], iteration_scale: 1.0 },
+    Variant { name: "hedge", options: &["--minimizer", "hedge"], iteration_scale: 1.0 },
+    Variant { name: "CFR-BR", options: &["--cfr-br"], iteration_scale: 0.25 },
+    Variant { name: "outcome sampling", options: &["--sampling", "outcome"], iteration_scale: 50.0 },
+];
+
+/// How one variant did on one reference game.
+pub struct Check {
+    pub game: &'static str,
+    pub variant: &'static str,
+    pub game_value: f64,
+    /// Midpoint of the trained strategy's two best-response values, which
+    /// brackets its own value and meets the game value at an equilibrium.
+    pub estimated_value: f64,
+    pub exploitability: f64,
+    pub passed: bool,
+}
+
+/// The tolerance `run` uses unless one is given: sampled CFR's error
+/// shrinks like 1/sqrt(iterations), and every variant on every reference
+/// game stays inside this bound from a few thousand iterations up.
+pub fn default_tolerance(iterations: usize) -> f64 {
+    12.0 / (iterations.max(1) as f64).sqrt()
+}
+
+/// Trains every variant briefly on every reference game and checks the value
+/// and exploitability it reaches against the exact solution. Last comes a
+/// standalone sanity check: `kuhn`'s own CFR+, not the crate's trainer, on
+/// Kuhn poker against its closed-form value, which no code here computes.
+pub fn run(iterations: usize, tolerance: f64) -> Result<Vec<Check>, String> {
+    let mut checks = Vec::new();
+    for game in reference_games() {
+        let solution = exact::solve(&game.config)?;
+        for variant in VARIANTS {
+            // A fixed seed keeps the verdict reproducible from run to run.
+            let options: Vec<String> = variant.options.iter().chain(&["--seed", "1"]).map(|o| o.to_string()).collect();
+            let scaled = (iterations as f64 * variant.iteration_scale).round() as usize;
+            let nodes = train::train_config(&Args::parse(&options), &game.config, scaled);
+            let br0 = exploitability::best_response_value(&nodes, &game.config, 0);
+            let br1 = exploitability::best_response_value(&nodes, &game.config, 1);
+            let estimated_value = (br0 - br1) / 2.0;
+            let exploitability = (br0 + br1) / 2.0;
+            checks.push(Check {
+                game: game.name,
+                variant: variant.name,
+                game_value: solution.value,
+                estimated_value,
+                exploitability,
+                passed: exploitability <= tolerance && (estimated_value - solution.value).abs() <= tolerance,
+            });
+        }
+    }
+
+    let nodes = kuhn::train(iterations);
+    let (br0, br1) = (kuhn::best_response_value(&nodes, 0), kuhn::best_response_value(&nodes, 1));
+    let (estimated_value, exploitability) = ((br0 - br1) / 2.0, (br0 + br1) / 2.0);
+    checks.push(Check {
+        game: "Kuhn poker",
+        variant: "standalone CFR+",
+        game_value: kuhn::GAME_VALUE,
+        estimated_value,
+        exploitability,
+        passed: exploitability <= tolerance && (estimated_value - kuhn::GAME_VALUE).abs() <= tolerance,
+    });
+    Ok(checks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cfr::{CFRTrainer, Sampling};
+    use crate::deals::ChanceStream;
+    use std::collections::HashMap;
+
+    #[test]
+    fn short_cfr_plus_run_approaches_the_exact_value() {
+        let game = &reference_games()[0];
+        let solution = exact::solve(&game.config).unwrap();
+        let trainer = CFRTrainer {
+            face_symmetry: false,
+            minimizer: Default::default(),
+            best_response_opponent: false,
+            deal_script: None,
+            deal_targets: None,
+            sampling: Sampling::Chance,
+        };
+        let iterations = 2000;
+        let mut nodes = HashMap::new();
+        trainer.train_into(&mut nodes, &mut ChanceStream::new(Some(1), 0, 1), &game.config, iterations);
+
+        let br0 = exploitability::best_response_value(&nodes, &game.config, 0);
+        let br1 = exploitability::best_response_value(&nodes, &game.config, 1);
+        // Chance-sampled CFR+ here stays within about 1/sqrt(T) of the
+        // equilibrium; twice that leaves room for an unlucky seed.
+        let tolerance = 2.0 / (iterations as f64).sqrt();
+        assert!((br0 + br1) / 2.0 <= tolerance, "exploitability {}", (br0 + br1) / 2.0);
+        assert!(((br0 - br1) / 2.0 - solution.value).abs() <= tolerance, "value {} vs {}", (br0 - br1) / 2.0, solution.value);
+    }
+
+    #[test]
+    fn default_tolerance_tightens_with_iterations() {
+        assert!(default_tolerance(100_000) < default_tolerance(20_000));
+        assert!((default_tolerance(10_000) - 0.12).abs() < 1e-12);
+    }
+}